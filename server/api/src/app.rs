@@ -1,46 +1,168 @@
 use std::{sync::Arc, time::Duration};
 
 use axum::Router;
-use bb8_redis::RedisConnectionManager;
-use tower_http::cors::{Any, CorsLayer};
+use axum_redis_cache::{
+    cache::RedisCache,
+    codec::CacheCipher,
+    errors::RedisUtilsError,
+    pool::{RedisConnectionManager, RedisNodeRole},
+    rate_limit::{RateLimitAlgorithm, RateLimitKey, RateLimitLayer, RateLimitLayerBuilder},
+};
+use bb8::Pool;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
 use crate::{
-    config::Settings, github::router::GithubRepositoryRouter,
-    health_check::router::HealthCheckRouter, state::AppState,
+    config::{ApplicationSettings, RateLimitSettings, RedisMode, RedisSettings},
+    github::{
+        client::GithubHttpClient, graphql::GithubGraphqlClient, router::GithubRepositoryRouter,
+        tokens::GithubTokenPool,
+    },
+    health_check::{checks::CachedCheck, router::HealthCheckRouter},
+    reload::SettingsHandle,
+    shutdown::Readiness,
+    state::AppState,
 };
 
-const REDIS_POOL_CONNECTION_TIMEOUT: u64 = 10;
+const GITHUB_REVALIDATION_PREFIX: &str = "github:revalidation";
+const RATE_LIMIT_PREFIX: &str = "rate_limit";
 
 pub struct App {
     pub router: Router,
-    #[allow(dead_code)]
     pub state: Arc<AppState>,
 }
 
 impl App {
-    pub async fn new(settings: Settings) -> Result<App, anyhow::Error> {
-        let github_settings = settings.github.clone();
+    pub async fn new(settings_handle: SettingsHandle) -> Result<App, anyhow::Error> {
+        let settings = settings_handle.load();
         let redis_settings = settings.redis.clone();
+        let rate_limit_settings = settings.rate_limit.clone();
+        let cache_cipher = redis_settings.get_cache_cipher()?;
+
+        let redis_pool = build_redis_pool(&redis_settings, RedisNodeRole::Primary).await?;
+        let redis_read_pool = if redis_settings.read_from_replicas {
+            Some(build_redis_pool(&redis_settings, RedisNodeRole::Replica).await?)
+        } else {
+            None
+        };
 
-        let redis_manager = RedisConnectionManager::new(redis_settings.url).unwrap();
-        let redis_pool = bb8::Pool::builder()
-            .connection_timeout(Duration::from_secs(REDIS_POOL_CONNECTION_TIMEOUT))
-            .build(redis_manager)
-            .await?;
+        let github_client = GithubHttpClient::new(
+            settings_handle.clone(),
+            build_revalidation_cache(&redis_settings, &redis_pool, &cache_cipher),
+            build_token_pool(&redis_settings, &redis_pool),
+        )?;
+        let github_graphql_client =
+            GithubGraphqlClient::new(settings_handle.clone(), github_client.clone());
 
         let state = Arc::new(AppState {
-            github_settings,
+            github_client,
+            github_graphql_client,
+            settings: settings_handle,
+            readiness: Readiness::default(),
+            github_probe: CachedCheck::default(),
             redis_pool,
+            redis_read_pool,
+            cache_cipher,
         });
         let router = Router::new()
             .nest("/", HealthCheckRouter::build())
-            .layer(CorsLayer::new().allow_origin(Any))
             .nest(
                 "/api/v1/github",
-                GithubRepositoryRouter::build(state.clone()),
+                GithubRepositoryRouter::build(state.clone()).layer(build_rate_limit_layer(
+                    &rate_limit_settings,
+                    &redis_settings,
+                    &state,
+                )?),
             )
+            .layer(build_cors_layer(&settings.application))
             .with_state(state.clone());
 
         Ok(App { router, state })
     }
 }
+
+async fn build_redis_pool(
+    redis_settings: &RedisSettings,
+    role: RedisNodeRole,
+) -> Result<Pool<RedisConnectionManager>, anyhow::Error> {
+    let redis_manager = match redis_settings.mode {
+        RedisMode::Standalone => RedisConnectionManager::new(redis_settings.url.clone())?,
+        RedisMode::Cluster => RedisConnectionManager::cluster(redis_settings.get_urls(), role)?,
+        RedisMode::Sentinel => RedisConnectionManager::sentinel(
+            redis_settings.get_urls(),
+            redis_settings
+                .sentinel_service_name
+                .clone()
+                .unwrap_or_default(),
+            role,
+        )?,
+    };
+
+    let redis_pool = bb8::Pool::builder()
+        .connection_timeout(Duration::from_secs(redis_settings.pool_connection_timeout))
+        .build(redis_manager)
+        .await?;
+
+    Ok(redis_pool)
+}
+
+// The last responses of Github are read from the primary pool, as a stale entry read from a replica would make the
+// conditional requests fail to revalidate.
+fn build_revalidation_cache(
+    redis_settings: &RedisSettings,
+    redis_pool: &Pool<RedisConnectionManager>,
+    cache_cipher: &Option<CacheCipher>,
+) -> RedisCache {
+    let revalidation_cache = RedisCache::new(redis_pool.clone())
+        .with_prefix(redis_settings.prefixed_key(GITHUB_REVALIDATION_PREFIX));
+
+    match cache_cipher.clone() {
+        Some(cipher) => revalidation_cache.with_encryption(cipher),
+        None => revalidation_cache,
+    }
+}
+
+fn build_token_pool(
+    redis_settings: &RedisSettings,
+    redis_pool: &Pool<RedisConnectionManager>,
+) -> GithubTokenPool {
+    let token_pool = GithubTokenPool::new(redis_pool.clone());
+
+    match &redis_settings.key_prefix {
+        Some(key_prefix) => token_pool.with_key_prefix(key_prefix),
+        None => token_pool,
+    }
+}
+
+fn build_cors_layer(application_settings: &ApplicationSettings) -> CorsLayer {
+    let allow_origin = if application_settings.allows_any_origin() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(application_settings.get_cors_allowed_origins())
+    };
+
+    CorsLayer::new().allow_origin(allow_origin)
+}
+
+// Limits the requests that every client can make to the api, so a single client cannot exhaust the Github API rate limit.
+fn build_rate_limit_layer(
+    rate_limit_settings: &RateLimitSettings,
+    redis_settings: &RedisSettings,
+    state: &AppState,
+) -> Result<RateLimitLayer, RedisUtilsError> {
+    let key = if rate_limit_settings.trust_forwarded_for {
+        RateLimitKey::ForwardedClientIp
+    } else {
+        RateLimitKey::ClientIp
+    };
+
+    RateLimitLayerBuilder::new(
+        state.redis_pool.clone(),
+        RateLimitAlgorithm::SlidingWindow {
+            limit: rate_limit_settings.requests,
+            window: Duration::from_secs(rate_limit_settings.window),
+        },
+    )
+    .with_key(key)
+    .with_prefix(redis_settings.prefixed_key(RATE_LIMIT_PREFIX))
+    .build()
+}
@@ -1,8 +1,10 @@
 use axum::{
-    http::{HeaderMap, HeaderValue, StatusCode},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
+    Json,
 };
 use redis::RedisError;
+use serde_json::json;
 use std::error::Error;
 
 const GITHUB_RATE_LIMIT_HEADERS: [&str; 3] =
@@ -12,7 +14,12 @@ const GITHUB_RATE_LIMIT_HEADERS: [&str; 3] =
 pub enum RustGoodFirstIssuesError {
     Reqwest(reqwest::Error),
     GithubAPI(StatusCode, HeaderMap<HeaderValue>, String),
+    // The rate limit resource of Github, and the seconds to wait until it can be requested again
+    GithubRateLimited(String, u64),
     ParseUrl(url::ParseError),
+    ParseJson(serde_json::Error),
+    Jwt(jsonwebtoken::errors::Error),
+    Io(std::io::Error),
     Redis(RedisError),
     RedisConnection(bb8::RunError<redis::RedisError>),
 }
@@ -30,9 +37,25 @@ impl std::fmt::Display for RustGoodFirstIssuesError {
             RustGoodFirstIssuesError::ParseUrl(err) => {
                 write!(f, "Parse url error: {}", err)
             }
+            RustGoodFirstIssuesError::ParseJson(err) => {
+                write!(f, "Parse json error: {}", err)
+            }
+            RustGoodFirstIssuesError::Jwt(err) => {
+                write!(f, "Github App JWT error: {}", err)
+            }
+            RustGoodFirstIssuesError::Io(err) => {
+                write!(f, "IO error: {}", err)
+            }
             RustGoodFirstIssuesError::GithubAPI(status_code, _, message) => {
                 write!(f, "Github API error {}: {}", status_code, message)
             }
+            RustGoodFirstIssuesError::GithubRateLimited(resource, retry_after) => {
+                write!(
+                    f,
+                    "Github {} rate limit exceeded, retry after {} seconds",
+                    resource, retry_after
+                )
+            }
             RustGoodFirstIssuesError::Redis(err) => {
                 let error_msg = format!("Redis error: {}", err);
 
@@ -48,6 +71,8 @@ impl std::fmt::Display for RustGoodFirstIssuesError {
     }
 }
 
+impl std::error::Error for RustGoodFirstIssuesError {}
+
 impl IntoResponse for RustGoodFirstIssuesError {
     fn into_response(self) -> Response {
         let err_message = self.to_string();
@@ -68,6 +93,17 @@ impl IntoResponse for RustGoodFirstIssuesError {
                 // Just returning the rate limit headers from Github API
                 (status_code, rate_limit_headers, err_message).into_response()
             }
+            // The requests are not sent to Github until the rate limit is reset, so the clients are told when to retry
+            RustGoodFirstIssuesError::GithubRateLimited(resource, retry_after) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(json!({
+                    "message": err_message,
+                    "resource": resource,
+                    "retry_after": retry_after,
+                })),
+            )
+                .into_response(),
             RustGoodFirstIssuesError::Reqwest(err) => (
                 err.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
                 err_message,
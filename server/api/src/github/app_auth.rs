@@ -0,0 +1,194 @@
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, Header};
+use reqwest::{header, Client};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::{
+    config::{GithubAppSettings, GithubSettings},
+    errors::RustGoodFirstIssuesError,
+};
+
+use super::client::{build_url, parse_error_from_response, GITHUB_API_VERSION_HEADER};
+
+// Github rejects the JWTs that expire in more than 10 minutes
+const JWT_EXPIRATION_SECS: i64 = 540;
+// The JWTs are issued in the past, as Github rejects the ones issued in the future when the clocks drift
+const JWT_CLOCK_DRIFT_SECS: i64 = 60;
+// The installation tokens last one hour, and they are refreshed this long before they expire, so a request never uses an
+// expired token.
+const INSTALLATION_TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+
+#[derive(Debug, Serialize)]
+struct AppJwtClaims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstallationTokenResponse {
+    token: Secret<String>,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+struct InstallationToken {
+    app_id: u64,
+    installation_id: u64,
+    token: Secret<String>,
+    expires_at: DateTime<Utc>,
+}
+
+impl InstallationToken {
+    // The token is only reused for the same installation, as the app settings can be reloaded.
+    fn is_valid_for(&self, app_settings: &GithubAppSettings, now: DateTime<Utc>) -> bool {
+        self.app_id == app_settings.id
+            && self.installation_id == app_settings.installation_id
+            && now + Duration::seconds(INSTALLATION_TOKEN_REFRESH_MARGIN_SECS) < self.expires_at
+    }
+}
+
+// Authenticates the requests as a Github App installation. The app signs a JWT with its private key, which is exchanged for
+// an installation access token. The installation token is kept in memory until shortly before it expires, or until Github
+// rejects it.
+#[derive(Clone, Debug, Default)]
+pub struct GithubAppAuth {
+    installation_token: Arc<Mutex<Option<InstallationToken>>>,
+}
+
+impl GithubAppAuth {
+    // Returns the cached installation token, or requests a new one when it is about to expire. The lock is kept while the
+    // token is requested, so the concurrent requests wait for the same token instead of requesting one each.
+    pub async fn get_installation_token(
+        &self,
+        http_client: &Client,
+        github_settings: &GithubSettings,
+        app_settings: &GithubAppSettings,
+    ) -> Result<Secret<String>, RustGoodFirstIssuesError> {
+        let mut installation_token = self.installation_token.lock().await;
+
+        if let Some(installation_token) = installation_token
+            .as_ref()
+            .filter(|installation_token| installation_token.is_valid_for(app_settings, Utc::now()))
+        {
+            return Ok(installation_token.token.clone());
+        }
+
+        let new_installation_token =
+            request_installation_token(http_client, github_settings, app_settings).await?;
+        let token = new_installation_token.token.clone();
+
+        tracing::info!(
+            app_id = app_settings.id,
+            installation_id = app_settings.installation_id,
+            expires_at = %new_installation_token.expires_at,
+            "Github App installation token created"
+        );
+
+        *installation_token = Some(new_installation_token);
+
+        Ok(token)
+    }
+
+    // Removes the cached installation token when it is the one Github rejected, so a token that was already replaced by a
+    // concurrent request is kept.
+    pub async fn clear_installation_token(&self, rejected_token: &str) {
+        let mut installation_token = self.installation_token.lock().await;
+
+        if installation_token
+            .as_ref()
+            .is_some_and(|installation_token| {
+                installation_token.token.expose_secret() == rejected_token
+            })
+        {
+            tracing::warn!("Github rejected the installation token, it will be created again");
+
+            *installation_token = None;
+        }
+    }
+}
+
+#[tracing::instrument(name = "Request Github App installation token", skip_all)]
+async fn request_installation_token(
+    http_client: &Client,
+    github_settings: &GithubSettings,
+    app_settings: &GithubAppSettings,
+) -> Result<InstallationToken, RustGoodFirstIssuesError> {
+    let jwt = create_app_jwt(app_settings, Utc::now())?;
+    let url = build_url(
+        &github_settings.get_api_url(),
+        &format!(
+            "/app/installations/{}/access_tokens",
+            app_settings.installation_id
+        ),
+    )?;
+
+    let response = http_client
+        .post(url)
+        .bearer_auth(jwt)
+        .header(header::USER_AGENT, github_settings.user_agent.as_str())
+        .header(
+            GITHUB_API_VERSION_HEADER,
+            github_settings.api_version.as_str(),
+        )
+        .send()
+        .await
+        .map_err(RustGoodFirstIssuesError::Reqwest)?;
+
+    if !response.status().is_success() {
+        return Err(parse_error_from_response(response).await);
+    }
+
+    let json: InstallationTokenResponse = response
+        .json()
+        .await
+        .map_err(RustGoodFirstIssuesError::Reqwest)?;
+
+    Ok(InstallationToken {
+        app_id: app_settings.id,
+        installation_id: app_settings.installation_id,
+        token: json.token,
+        expires_at: json.expires_at,
+    })
+}
+
+fn create_app_jwt(
+    app_settings: &GithubAppSettings,
+    now: DateTime<Utc>,
+) -> Result<String, RustGoodFirstIssuesError> {
+    let claims = AppJwtClaims {
+        iat: now.timestamp() - JWT_CLOCK_DRIFT_SECS,
+        exp: now.timestamp() + JWT_EXPIRATION_SECS,
+        iss: app_settings.id.to_string(),
+    };
+    let encoding_key = app_settings
+        .get_encoding_key()
+        .map_err(RustGoodFirstIssuesError::Jwt)?;
+
+    jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &encoding_key)
+        .map_err(RustGoodFirstIssuesError::Jwt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_installation_token_is_refreshed_before_it_expires() {
+        let app_settings = GithubAppSettings::new(1, 2, String::new());
+        let now = Utc::now();
+        let installation_token = InstallationToken {
+            app_id: 1,
+            installation_id: 2,
+            token: Secret::new(String::from("token")),
+            expires_at: now + Duration::minutes(10),
+        };
+
+        assert!(installation_token.is_valid_for(&app_settings, now));
+        assert!(!installation_token.is_valid_for(&app_settings, now + Duration::minutes(6)));
+        assert!(!installation_token.is_valid_for(&GithubAppSettings::new(1, 3, String::new()), now));
+    }
+}
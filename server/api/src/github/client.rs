@@ -1,17 +1,36 @@
-use reqwest::{header, Client, Url};
+use axum_redis_cache::cache::RedisCache;
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    Client, Proxy, StatusCode, Url,
+};
+use secrecy::ExposeSecret;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::{
+    config::{GithubAuth, GithubSettings},
+    errors::RustGoodFirstIssuesError,
+    reload::SettingsHandle,
+};
 
-use crate::{config::GithubSettings, errors::RustGoodFirstIssuesError};
+use super::{
+    app_auth::GithubAppAuth,
+    graphql::get_rate_limited_message,
+    rate_limit::GithubRateLimitError,
+    retry::{RetryBackoff, RetryableFailure},
+    throttle::{GithubThrottle, RequestUrgency},
+    tokens::{GithubToken, GithubTokenPool, RateLimitResource},
+};
 
 use super::models::{
-    GetGithubRepositoriesParams, GetGithubRepositoriesResponse,
+    GetGithubBudgetResponse, GetGithubRepositoriesParams, GetGithubRepositoriesResponse,
     GetGithubRepositoryGoodFirstIssuesParams, GetGithubRepositoryGoodFirstIssuesPathParams,
     GetGithubRepositoryGoodFirstIssuesResponse, GithubIssue, GithubIssueAPI, GithubPullRequest,
     GithubRepository as GithubRepositoryModel, SearchGithubRepositoriesResponseAPI,
 };
 
-const GITHUB_API_VERSION: &str = "2022-11-28";
-const GITHUB_API_USERNAME: &str = "frankPairs";
-const DEFAULT_PER_PAGE: u32 = 10;
+pub(super) const GITHUB_API_VERSION_HEADER: &str = "X-GitHub-Api-Version";
 const DEFAULT_PAGE: u32 = 1;
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -19,34 +38,73 @@ pub struct GithubApiErrorPayload {
     message: String,
 }
 
+// The last successful response of a Github url, with the validators Github sent for it. Github does not count the
+// `304 Not Modified` responses against the rate limit, so the payload is revalidated with a conditional request instead of
+// being downloaded again.
+#[derive(Debug, Deserialize, Serialize)]
+struct RevalidationEntry {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    payload: serde_json::Value,
+}
+
+// The http client is built once, so its connections are reused by every request. The rest of the Github settings (like the
+// token) are read from the current settings on every request, so they can be reloaded.
+#[derive(Clone, Debug)]
 pub struct GithubHttpClient {
     http_client: Client,
-    base_url: Url,
+    settings: SettingsHandle,
+    // Keeps the revalidation entries, keyed by the Github url
+    revalidation_cache: RedisCache,
+    token_pool: GithubTokenPool,
+    app_auth: GithubAppAuth,
+    throttle: GithubThrottle,
 }
 
 impl GithubHttpClient {
-    pub fn new(settings: GithubSettings) -> Result<Self, RustGoodFirstIssuesError> {
+    pub fn new(
+        settings: SettingsHandle,
+        revalidation_cache: RedisCache,
+        token_pool: GithubTokenPool,
+    ) -> Result<Self, RustGoodFirstIssuesError> {
+        let http_settings = settings.load().github.http.clone();
         let mut headers = header::HeaderMap::new();
 
-        headers.insert("Accept", "application/vnd.github+json".parse().unwrap());
         headers.insert(
-            "Authorization",
-            format!("Bearer {}", settings.get_token()).parse().unwrap(),
+            header::ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
         );
-        headers.insert("X-GitHub-Api-Version", GITHUB_API_VERSION.parse().unwrap());
-        headers.insert("User-Agent", GITHUB_API_USERNAME.parse().unwrap());
 
-        let http_client: Client = Client::builder()
+        let mut builder = Client::builder()
             .default_headers(headers)
-            .build()
-            .map_err(RustGoodFirstIssuesError::Reqwest)?;
+            .connect_timeout(Duration::from_secs(http_settings.connect_timeout))
+            .timeout(Duration::from_secs(http_settings.timeout))
+            .pool_max_idle_per_host(http_settings.pool_max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(http_settings.pool_idle_timeout));
+
+        for certificate in http_settings
+            .get_ca_certificates()
+            .map_err(RustGoodFirstIssuesError::Io)?
+        {
+            builder = builder.add_root_certificate(certificate);
+        }
 
-        let base_url =
-            Url::parse(&settings.get_api_url()).map_err(RustGoodFirstIssuesError::ParseUrl)?;
+        if let Some(proxy) = &http_settings.proxy {
+            builder = builder.proxy(
+                Proxy::all(proxy.expose_secret().as_str())
+                    .map_err(RustGoodFirstIssuesError::Reqwest)?,
+            );
+        }
+
+        let http_client = builder.build().map_err(RustGoodFirstIssuesError::Reqwest)?;
 
         Ok(Self {
             http_client,
-            base_url,
+            settings,
+            revalidation_cache,
+            token_pool,
+            app_auth: GithubAppAuth::default(),
+            throttle: GithubThrottle::default(),
         })
     }
 
@@ -54,11 +112,10 @@ impl GithubHttpClient {
     pub async fn get_rust_repositories(
         &self,
         params: &GetGithubRepositoriesParams,
+        urgency: RequestUrgency,
     ) -> Result<GetGithubRepositoriesResponse, RustGoodFirstIssuesError> {
-        let mut url = self
-            .base_url
-            .join("/search/repositories?")
-            .map_err(RustGoodFirstIssuesError::ParseUrl)?;
+        let settings = self.settings.load();
+        let mut url = build_url(&settings.github.get_api_url(), "/search/repositories?")?;
 
         url.query_pairs_mut()
             .append_pair("q", "language:rust")
@@ -66,25 +123,16 @@ impl GithubHttpClient {
             .append_pair("order", "desc")
             .append_pair(
                 "per_page",
-                &params.per_page.unwrap_or(DEFAULT_PER_PAGE).to_string(),
+                &params
+                    .per_page
+                    .unwrap_or(settings.github.default_per_page)
+                    .to_string(),
             )
             .append_pair("page", &params.page.unwrap_or(DEFAULT_PAGE).to_string());
 
-        let response = self
-            .http_client
-            .get(url)
-            .send()
-            .await
-            .map_err(RustGoodFirstIssuesError::Reqwest)?;
-
-        if !response.status().is_success() {
-            return Err(self.parse_error_from_response(response).await);
-        }
-
-        let json: SearchGithubRepositoriesResponseAPI = response
-            .json()
-            .await
-            .map_err(RustGoodFirstIssuesError::Reqwest)?;
+        let json: SearchGithubRepositoriesResponseAPI = self
+            .get_revalidated(&settings.github, RateLimitResource::Search, url, urgency)
+            .await?;
 
         Ok(GetGithubRepositoriesResponse {
             total_count: json.total_count,
@@ -115,40 +163,30 @@ impl GithubHttpClient {
         &self,
         path_params: &GetGithubRepositoryGoodFirstIssuesPathParams,
         params: &GetGithubRepositoryGoodFirstIssuesParams,
+        urgency: RequestUrgency,
     ) -> Result<GetGithubRepositoryGoodFirstIssuesResponse, RustGoodFirstIssuesError> {
-        let mut url = self
-            .base_url
-            .join(&format!(
-                "/repos/{}/{}/issues?",
-                params.owner, path_params.repo
-            ))
-            .map_err(RustGoodFirstIssuesError::ParseUrl)?;
+        let settings = self.settings.load();
+        let mut url = build_url(
+            &settings.github.get_api_url(),
+            &format!("/repos/{}/{}/issues?", params.owner, path_params.repo),
+        )?;
 
         url.query_pairs_mut()
-            .append_pair("labels", "good first issue")
+            .append_pair("labels", &settings.github.labels.join(","))
             .append_pair("sort", "updated")
             .append_pair("direction", "desc")
             .append_pair(
                 "per_page",
-                &params.per_page.unwrap_or(DEFAULT_PER_PAGE).to_string(),
+                &params
+                    .per_page
+                    .unwrap_or(settings.github.default_per_page)
+                    .to_string(),
             )
             .append_pair("page", &params.page.unwrap_or(DEFAULT_PAGE).to_string());
 
-        let response = self
-            .http_client
-            .get(url)
-            .send()
-            .await
-            .map_err(RustGoodFirstIssuesError::Reqwest)?;
-
-        if !response.status().is_success() {
-            return Err(self.parse_error_from_response(response).await);
-        }
-
-        let json: Vec<GithubIssueAPI> = response
-            .json()
-            .await
-            .map_err(RustGoodFirstIssuesError::Reqwest)?;
+        let json: Vec<GithubIssueAPI> = self
+            .get_revalidated(&settings.github, RateLimitResource::Core, url, urgency)
+            .await?;
 
         Ok(GetGithubRepositoryGoodFirstIssuesResponse {
             items: json
@@ -172,19 +210,532 @@ impl GithubHttpClient {
         })
     }
 
-    async fn parse_error_from_response(
+    // Returns the known quotas of the tokens used to authenticate the requests.
+    pub async fn get_budget(&self) -> Result<GetGithubBudgetResponse, RustGoodFirstIssuesError> {
+        let settings = self.settings.load();
+        let tokens = self.get_tokens(&settings.github).await?;
+
+        Ok(GetGithubBudgetResponse {
+            resources: self.token_pool.get_budget(&tokens).await?,
+        })
+    }
+
+    // Github does not count the requests to this endpoint against the rate limit, so it is used to check that the API is
+    // reachable and the token is valid.
+    #[tracing::instrument(name = "Get rate limit from Github API", skip(self))]
+    pub async fn get_rate_limit(&self) -> Result<(), RustGoodFirstIssuesError> {
+        let settings = self.settings.load();
+        let url = build_url(&settings.github.get_api_url(), "/rate_limit")?;
+
+        let response = self
+            .send_get(&settings.github, RateLimitResource::Core, url)
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(parse_error_from_response(response).await);
+        }
+
+        Ok(())
+    }
+
+    async fn send_get(
+        &self,
+        github_settings: &GithubSettings,
+        resource: RateLimitResource,
+        url: Url,
+    ) -> Result<reqwest::Response, RustGoodFirstIssuesError> {
+        self.send(github_settings, resource, url, HeaderMap::new(), None, None)
+            .await
+    }
+
+    // Sends a query to the GraphQL API. The queries only read data, so they are retried like the other requests.
+    pub(super) async fn send_graphql_query(
+        &self,
+        github_settings: &GithubSettings,
+        query: &serde_json::Value,
+        urgency: RequestUrgency,
+    ) -> Result<reqwest::Response, RustGoodFirstIssuesError> {
+        let url = build_graphql_url(&github_settings.get_api_url())?;
+
+        self.send(
+            github_settings,
+            RateLimitResource::Graphql,
+            url,
+            HeaderMap::new(),
+            Some(query),
+            Some(urgency),
+        )
+        .await
+    }
+
+    // Sends a request that is counted against the rate limit of the resource, so it is throttled when its quota runs low (if
+    // it is deferrable), and it is not sent while the resource is backing off.
+    async fn send_conditional_get(
+        &self,
+        github_settings: &GithubSettings,
+        resource: RateLimitResource,
+        url: Url,
+        conditional_headers: HeaderMap,
+        urgency: RequestUrgency,
+    ) -> Result<reqwest::Response, RustGoodFirstIssuesError> {
+        self.send(
+            github_settings,
+            resource,
+            url,
+            conditional_headers,
+            None,
+            Some(urgency),
+        )
+        .await
+    }
+
+    // Sends the request until it does not fail because of a transient error, the max attempts are reached, or the next
+    // attempt would start after the deadline. The result of the last attempt is returned. The requests counted against the
+    // rate limit of the resource come with their urgency, and the other ones with None.
+    async fn send(
+        &self,
+        github_settings: &GithubSettings,
+        resource: RateLimitResource,
+        url: Url,
+        conditional_headers: HeaderMap,
+        body: Option<&serde_json::Value>,
+        counted: Option<RequestUrgency>,
+    ) -> Result<reqwest::Response, RustGoodFirstIssuesError> {
+        let retry_settings = &github_settings.retry;
+        let deadline = Instant::now() + Duration::from_secs(retry_settings.deadline);
+        let mut backoff = RetryBackoff::new(retry_settings);
+        let mut attempt = 1;
+
+        loop {
+            let result = self
+                .send_attempt(
+                    github_settings,
+                    resource,
+                    url.clone(),
+                    conditional_headers.clone(),
+                    body,
+                    counted,
+                )
+                .await;
+
+            let Some(failure) = RetryableFailure::from_result(&result) else {
+                return result;
+            };
+
+            let delay = backoff.next_delay(&failure);
+
+            if attempt >= retry_settings.max_attempts || Instant::now() + delay > deadline {
+                return result;
+            }
+
+            tracing::warn!(
+                url = %url,
+                attempt,
+                reason = failure.reason,
+                delay_ms = delay.as_millis() as u64,
+                "Retrying Github request"
+            );
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    // Sends the request with the token that has the most remaining requests for the resource, saving the quota Github
+    // reports for it on the response. When Github rejects the request because a rate limit was exceeded, the token backs
+    // off from the resource and a rate limit error is returned. The other 403 and 429 errors are returned as they are. The
+    // requests with a body are sent as POST requests. The counted requests wait for the throttle, for longer when they are
+    // deferrable.
+    async fn send_attempt(
+        &self,
+        github_settings: &GithubSettings,
+        resource: RateLimitResource,
+        url: Url,
+        conditional_headers: HeaderMap,
+        body: Option<&serde_json::Value>,
+        counted: Option<RequestUrgency>,
+    ) -> Result<reqwest::Response, RustGoodFirstIssuesError> {
+        let tokens = self.get_tokens(github_settings).await?;
+        let (token, quota) = self
+            .token_pool
+            .select(tokens, resource, counted.is_none())
+            .await?;
+
+        if let Some(urgency) = counted {
+            self.throttle
+                .wait(resource, quota, urgency, &github_settings.throttle)
+                .await;
+        }
+
+        tracing::debug!(token_id = token.id, "Sending request to Github");
+
+        let request = match body {
+            Some(body) => self.http_client.post(url).json(body),
+            None => self.http_client.get(url),
+        };
+
+        let response = request
+            .bearer_auth(token.expose_secret())
+            .header(header::USER_AGENT, github_settings.user_agent.as_str())
+            .header(
+                GITHUB_API_VERSION_HEADER,
+                github_settings.api_version.as_str(),
+            )
+            .headers(conditional_headers)
+            .send()
+            .await
+            .map_err(RustGoodFirstIssuesError::Reqwest)?;
+
+        self.token_pool
+            .record(&token, resource, response.headers())
+            .await;
+
+        let status = response.status();
+
+        // The installation token can be revoked before it expires, like when the app is suspended, so the next request gets a
+        // new one instead of reusing it until it expires
+        if status == StatusCode::UNAUTHORIZED && github_settings.auth == GithubAuth::App {
+            self.app_auth
+                .clear_installation_token(token.expose_secret())
+                .await;
+        }
+
+        if resource == RateLimitResource::Graphql && status.is_success() {
+            return self.check_graphql_rate_limit(&token, response).await;
+        }
+
+        if !GithubRateLimitError::is_rate_limit_status(status) {
+            return Ok(response);
+        }
+
+        let headers = response.headers().clone();
+        let message = read_error_message(response).await?;
+        let Some(rate_limit_error) =
+            GithubRateLimitError::from_response(status, &headers, &message)
+        else {
+            return Err(RustGoodFirstIssuesError::GithubAPI(
+                status, headers, message,
+            ));
+        };
+
+        Err(self
+            .back_off(
+                &token,
+                resource,
+                status,
+                headers,
+                message,
+                &rate_limit_error,
+            )
+            .await)
+    }
+
+    // Github reports the exceeded rate limit of the GraphQL API as an error of a successful response, so its body is read to
+    // back off the token from the GraphQL API. The other responses are returned with the body that was read.
+    async fn check_graphql_rate_limit(
         &self,
+        token: &GithubToken,
         response: reqwest::Response,
-    ) -> RustGoodFirstIssuesError {
-        let status_code = response.status();
+    ) -> Result<reqwest::Response, RustGoodFirstIssuesError> {
+        let status = response.status();
         let headers = response.headers().clone();
-        let result: Result<GithubApiErrorPayload, reqwest::Error> = response.json().await;
+        let body = response
+            .bytes()
+            .await
+            .map_err(RustGoodFirstIssuesError::Reqwest)?;
+
+        let Some(message) = get_rate_limited_message(&body) else {
+            let mut response = axum::http::Response::new(body);
+
+            *response.status_mut() = status;
+            *response.headers_mut() = headers;
+
+            return Ok(reqwest::Response::from(response));
+        };
+
+        let rate_limit_error = GithubRateLimitError::from_graphql_response(&headers);
+
+        Err(self
+            .back_off(
+                token,
+                RateLimitResource::Graphql,
+                StatusCode::TOO_MANY_REQUESTS,
+                headers,
+                message,
+                &rate_limit_error,
+            )
+            .await)
+    }
+
+    // Backs off the token from the resource, and returns the error of the request that exceeded the rate limit.
+    async fn back_off(
+        &self,
+        token: &GithubToken,
+        resource: RateLimitResource,
+        status: StatusCode,
+        headers: HeaderMap,
+        message: String,
+        rate_limit_error: &GithubRateLimitError,
+    ) -> RustGoodFirstIssuesError {
+        let retry_after = self
+            .token_pool
+            .back_off(token, resource, &headers, rate_limit_error)
+            .await;
+
+        // Github asked to retry right away, so the error is returned as it is
+        if retry_after == 0 {
+            return RustGoodFirstIssuesError::GithubAPI(status, headers, message);
+        }
+
+        RustGoodFirstIssuesError::GithubRateLimited(resource.as_str().to_string(), retry_after)
+    }
+
+    // Returns the tokens to authenticate the requests with: the personal access tokens of the settings, or the installation
+    // token of the Github App.
+    async fn get_tokens(
+        &self,
+        github_settings: &GithubSettings,
+    ) -> Result<Vec<GithubToken>, RustGoodFirstIssuesError> {
+        let tokens = match (&github_settings.auth, &github_settings.app) {
+            (GithubAuth::App, Some(app_settings)) => vec![
+                self.app_auth
+                    .get_installation_token(&self.http_client, github_settings, app_settings)
+                    .await?,
+            ],
+            _ => github_settings.get_tokens(),
+        };
+
+        Ok(tokens.into_iter().map(GithubToken::new).collect())
+    }
+
+    // Gets the payload of the url, revalidating the last response with a conditional request when there is one. On a
+    // `304 Not Modified`, the last payload is returned. The revalidation cache fails open: when Redis is not available, the
+    // request is sent without the conditional headers.
+    async fn get_revalidated<T: DeserializeOwned>(
+        &self,
+        github_settings: &GithubSettings,
+        resource: RateLimitResource,
+        url: Url,
+        urgency: RequestUrgency,
+    ) -> Result<T, RustGoodFirstIssuesError> {
+        let revalidation_key = url.to_string();
+        let revalidation_ttl = Duration::from_secs(github_settings.revalidation_ttl);
+        let last_response = self.get_last_response::<T>(&revalidation_key).await;
 
-        match result {
-            Ok(error_payload) => {
-                RustGoodFirstIssuesError::GithubAPI(status_code, headers, error_payload.message)
+        let conditional_headers = match &last_response {
+            Some((entry, _)) => conditional_headers(entry),
+            None => HeaderMap::new(),
+        };
+
+        let response = self
+            .send_conditional_get(github_settings, resource, url, conditional_headers, urgency)
+            .await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some((entry, payload)) = last_response {
+                tracing::debug!(url = revalidation_key, "Github response not modified");
+
+                // The entry is saved again, so the urls that keep being requested are not revalidated from scratch
+                self.save_last_response(&revalidation_key, &entry, revalidation_ttl)
+                    .await;
+
+                return Ok(payload);
             }
-            Err(err) => RustGoodFirstIssuesError::Reqwest(err),
         }
+
+        if !response.status().is_success() {
+            return Err(parse_error_from_response(response).await);
+        }
+
+        let etag = get_header_value(response.headers(), header::ETAG);
+        let last_modified = get_header_value(response.headers(), header::LAST_MODIFIED);
+        let payload: serde_json::Value = response
+            .json()
+            .await
+            .map_err(RustGoodFirstIssuesError::Reqwest)?;
+        let json = T::deserialize(&payload).map_err(RustGoodFirstIssuesError::ParseJson)?;
+
+        if etag.is_some() || last_modified.is_some() {
+            let entry = RevalidationEntry {
+                etag,
+                last_modified,
+                payload,
+            };
+
+            self.save_last_response(&revalidation_key, &entry, revalidation_ttl)
+                .await;
+        }
+
+        Ok(json)
+    }
+
+    // Returns the last response of the url with its decoded payload. Entries that cannot be read or decoded are ignored, so
+    // the payload is requested again.
+    async fn get_last_response<T: DeserializeOwned>(
+        &self,
+        revalidation_key: &str,
+    ) -> Option<(RevalidationEntry, T)> {
+        let entry = match self
+            .revalidation_cache
+            .get::<RevalidationEntry>(revalidation_key)
+            .await
+        {
+            Ok(entry) => entry?,
+            Err(err) => {
+                tracing::warn!(error = %err, "Unable to get the last Github response");
+
+                return None;
+            }
+        };
+
+        let payload = T::deserialize(&entry.payload).ok()?;
+
+        Some((entry, payload))
+    }
+
+    async fn save_last_response(
+        &self,
+        revalidation_key: &str,
+        entry: &RevalidationEntry,
+        revalidation_ttl: Duration,
+    ) {
+        if let Err(err) = self
+            .revalidation_cache
+            .set(revalidation_key, entry, revalidation_ttl)
+            .await
+        {
+            tracing::warn!(error = %err, "Unable to save the last Github response");
+        }
+    }
+}
+
+pub(super) async fn parse_error_from_response(
+    response: reqwest::Response,
+) -> RustGoodFirstIssuesError {
+    let status_code = response.status();
+    let headers = response.headers().clone();
+    let result: Result<GithubApiErrorPayload, reqwest::Error> = response.json().await;
+
+    match result {
+        Ok(error_payload) => {
+            RustGoodFirstIssuesError::GithubAPI(status_code, headers, error_payload.message)
+        }
+        Err(err) => RustGoodFirstIssuesError::Reqwest(err),
+    }
+}
+
+// Returns the message of a Github error response. The rate limit errors are not always sent as json, so the body is used as
+// the message when it cannot be parsed.
+async fn read_error_message(
+    response: reqwest::Response,
+) -> Result<String, RustGoodFirstIssuesError> {
+    let body = response
+        .text()
+        .await
+        .map_err(RustGoodFirstIssuesError::Reqwest)?;
+
+    Ok(serde_json::from_str::<GithubApiErrorPayload>(&body)
+        .map(|error_payload| error_payload.message)
+        .unwrap_or(body))
+}
+
+fn conditional_headers(entry: &RevalidationEntry) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    if let Some(etag) = entry
+        .etag
+        .as_deref()
+        .and_then(|etag| HeaderValue::from_str(etag).ok())
+    {
+        headers.insert(header::IF_NONE_MATCH, etag);
+    }
+
+    if let Some(last_modified) = entry
+        .last_modified
+        .as_deref()
+        .and_then(|last_modified| HeaderValue::from_str(last_modified).ok())
+    {
+        headers.insert(header::IF_MODIFIED_SINCE, last_modified);
+    }
+
+    headers
+}
+
+fn get_header_value(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+// The path is joined relative to the api url, so the path prefix of the Github Enterprise Server urls (like `/api/v3`) is
+// kept.
+pub(super) fn build_url(api_url: &str, path: &str) -> Result<Url, RustGoodFirstIssuesError> {
+    let mut base_url = Url::parse(api_url).map_err(RustGoodFirstIssuesError::ParseUrl)?;
+
+    if !base_url.path().ends_with('/') {
+        let base_path = format!("{}/", base_url.path());
+
+        base_url.set_path(&base_path);
+    }
+
+    base_url
+        .join(path.trim_start_matches('/'))
+        .map_err(RustGoodFirstIssuesError::ParseUrl)
+}
+
+// The GraphQL API of Github Enterprise Server is not under the path prefix of the REST API, but next to it: the REST API is
+// served on `/api/v3` and the GraphQL API on `/api/graphql`.
+fn build_graphql_url(api_url: &str) -> Result<Url, RustGoodFirstIssuesError> {
+    let mut base_url = Url::parse(api_url).map_err(RustGoodFirstIssuesError::ParseUrl)?;
+    let base_path = base_url.path().trim_end_matches('/').to_string();
+
+    if let Some(prefix) = base_path.strip_suffix("/v3") {
+        base_url.set_path(prefix);
+    }
+
+    build_url(base_url.as_str(), "/graphql")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_url_keeps_the_path_prefix() {
+        assert_eq!(
+            build_url("https://api.github.com", "/rate_limit")
+                .unwrap()
+                .as_str(),
+            "https://api.github.com/rate_limit"
+        );
+        assert_eq!(
+            build_url("https://ghe.corp/api/v3", "/search/repositories?")
+                .unwrap()
+                .as_str(),
+            "https://ghe.corp/api/v3/search/repositories?"
+        );
+        assert_eq!(
+            build_url("https://ghe.corp/api/v3/", "/rate_limit")
+                .unwrap()
+                .as_str(),
+            "https://ghe.corp/api/v3/rate_limit"
+        );
+    }
+
+    #[test]
+    fn test_build_graphql_url_of_enterprise_server() {
+        assert_eq!(
+            build_graphql_url("https://api.github.com")
+                .unwrap()
+                .as_str(),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            build_graphql_url("https://ghe.corp/api/v3/")
+                .unwrap()
+                .as_str(),
+            "https://ghe.corp/api/graphql"
+        );
     }
 }
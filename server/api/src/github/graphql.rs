@@ -0,0 +1,286 @@
+use reqwest::StatusCode;
+use serde::de::IgnoredAny;
+use serde_json::json;
+
+use crate::{errors::RustGoodFirstIssuesError, reload::SettingsHandle};
+
+use super::{
+    client::{parse_error_from_response, GithubHttpClient},
+    models::{
+        GetGithubRepositoriesWithGoodFirstIssuesParams,
+        GetGithubRepositoriesWithGoodFirstIssuesResponse, GithubIssue, GithubIssueGraphqlAPI,
+        GithubIssueState, GithubIssueStateGraphqlAPI, GithubRepository as GithubRepositoryModel,
+        GithubRepositoryGraphqlAPI, GithubRepositoryWithGoodFirstIssues, GraphqlResponseAPI,
+        SearchGithubRepositoriesGraphqlAPI,
+    },
+    throttle::RequestUrgency,
+};
+
+// Github does not return more than 100 nodes per connection
+const MAX_PAGE_SIZE: u32 = 100;
+const RATE_LIMITED_ERROR_TYPE: &str = "RATE_LIMITED";
+
+// The Rust repositories, sorted like the ones of the REST API, with their open issues and good first issues
+const SEARCH_REPOSITORIES_QUERY: &str = r#"
+query SearchRepositoriesWithGoodFirstIssues($query: String!, $first: Int!, $after: String, $labels: [String!], $issues: Int!) {
+  rateLimit {
+    cost
+    remaining
+  }
+  search(type: REPOSITORY, query: $query, first: $first, after: $after) {
+    repositoryCount
+    pageInfo {
+      hasNextPage
+      endCursor
+    }
+    nodes {
+      ... on Repository {
+        databaseId
+        nameWithOwner
+        isPrivate
+        url
+        description
+        stargazerCount
+        hasIssuesEnabled
+        owner {
+          avatarUrl
+        }
+        licenseInfo {
+          name
+        }
+        openIssues: issues(states: OPEN) {
+          totalCount
+        }
+        goodFirstIssues: issues(states: OPEN, labels: $labels, first: $issues, orderBy: {field: UPDATED_AT, direction: DESC}) {
+          totalCount
+          nodes {
+            databaseId
+            title
+            body
+            url
+            state
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+// Fetches the data of the Github GraphQL API. A page of repositories is fetched together with the good first issues of every
+// repository in a single query, instead of a request per repository to the REST API. The requests are sent by the REST
+// client, so they share its tokens, throttling, backoff and retries. The client backs off the token of a query that exceeded
+// the rate limit of the GraphQL API.
+#[derive(Clone, Debug)]
+pub struct GithubGraphqlClient {
+    settings: SettingsHandle,
+    http_client: GithubHttpClient,
+}
+
+impl GithubGraphqlClient {
+    pub fn new(settings: SettingsHandle, http_client: GithubHttpClient) -> Self {
+        GithubGraphqlClient {
+            settings,
+            http_client,
+        }
+    }
+
+    #[tracing::instrument(
+        name = "Get Rust repositories with good first issues from Github GraphQL API",
+        skip(self)
+    )]
+    pub async fn get_rust_repositories_with_good_first_issues(
+        &self,
+        params: &GetGithubRepositoriesWithGoodFirstIssuesParams,
+        urgency: RequestUrgency,
+    ) -> Result<GetGithubRepositoriesWithGoodFirstIssuesResponse, RustGoodFirstIssuesError> {
+        let settings = self.settings.load();
+        let graphql_settings = &settings.github.graphql;
+        let page_size = get_page_size(
+            params.per_page.unwrap_or(settings.github.default_per_page),
+            graphql_settings.max_query_cost,
+        );
+
+        let query = json!({
+            "query": SEARCH_REPOSITORIES_QUERY,
+            "variables": {
+                "query": "language:rust sort:help-wanted-issues-desc",
+                "first": page_size,
+                "after": params.cursor,
+                "labels": settings.github.labels,
+                "issues": graphql_settings.issues_per_repository,
+            },
+        });
+
+        let response = self
+            .http_client
+            .send_graphql_query(&settings.github, &query, urgency)
+            .await?;
+        if !response.status().is_success() {
+            return Err(parse_error_from_response(response).await);
+        }
+
+        let headers = response.headers().clone();
+
+        let json: GraphqlResponseAPI<SearchGithubRepositoriesGraphqlAPI> = response
+            .json()
+            .await
+            .map_err(RustGoodFirstIssuesError::Reqwest)?;
+
+        let errors_message = json
+            .errors
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ");
+
+        // The data of a query can be partial, like when a repository cannot be read, so the errors are only returned when
+        // there is no data
+        let Some(data) = json.data else {
+            return Err(RustGoodFirstIssuesError::GithubAPI(
+                StatusCode::BAD_GATEWAY,
+                headers,
+                errors_message,
+            ));
+        };
+
+        if !json.errors.is_empty() {
+            tracing::warn!(
+                errors = errors_message,
+                "Github GraphQL query returned partial data"
+            );
+        }
+
+        if let Some(rate_limit) = &data.rate_limit {
+            tracing::debug!(
+                cost = rate_limit.cost,
+                remaining = rate_limit.remaining,
+                "Github GraphQL query cost"
+            );
+        }
+
+        Ok(GetGithubRepositoriesWithGoodFirstIssuesResponse {
+            total_count: data.search.repository_count,
+            items: data
+                .search
+                .nodes
+                .into_iter()
+                .flatten()
+                .filter_map(map_repository)
+                .collect(),
+            next_cursor: if data.search.page_info.has_next_page {
+                data.search.page_info.end_cursor
+            } else {
+                None
+            },
+        })
+    }
+}
+
+// Returns the message of the error of a GraphQL response that exceeded the rate limit, or None when the response did not
+// exceed it (or it cannot be parsed).
+pub(super) fn get_rate_limited_message(body: &[u8]) -> Option<String> {
+    let json: GraphqlResponseAPI<IgnoredAny> = serde_json::from_slice(body).ok()?;
+
+    json.errors
+        .into_iter()
+        .find(|error| error.error_type.as_deref() == Some(RATE_LIMITED_ERROR_TYPE))
+        .map(|error| error.message)
+}
+
+// The nodes without a database id cannot be identified like the ones of the REST API, so they are skipped.
+fn map_repository(repo: GithubRepositoryGraphqlAPI) -> Option<GithubRepositoryWithGoodFirstIssues> {
+    Some(GithubRepositoryWithGoodFirstIssues {
+        repository: GithubRepositoryModel {
+            id: repo.database_id?,
+            url: repo.url,
+            name: repo.name_with_owner,
+            private: repo.is_private,
+            avatar_url: repo.owner.avatar_url,
+            description: repo.description,
+            stars_count: repo.stargazer_count,
+            open_issues_count: repo.open_issues.total_count,
+            has_issues: repo.has_issues_enabled,
+            license: repo.license_info.map(|license| license.name),
+        },
+        good_first_issues_count: repo.good_first_issues.total_count,
+        good_first_issues: repo
+            .good_first_issues
+            .nodes
+            .into_iter()
+            .flatten()
+            .filter_map(map_issue)
+            .collect(),
+    })
+}
+
+// The issues connection does not include the pull requests, so the issues never have one
+fn map_issue(issue: GithubIssueGraphqlAPI) -> Option<GithubIssue> {
+    Some(GithubIssue {
+        id: issue.database_id?,
+        title: issue.title,
+        description: None,
+        body: issue.body,
+        url: issue.url,
+        state: match issue.state {
+            GithubIssueStateGraphqlAPI::Open => GithubIssueState::Open,
+            GithubIssueStateGraphqlAPI::Closed => GithubIssueState::Close,
+        },
+        pull_request: None,
+    })
+}
+
+// Github computes the cost of a query from the number of requests needed to fulfill its connections, divided by 100 and
+// rounded: one for the search, and two for every repository (its open issues and its good first issues).
+// https://docs.github.com/en/graphql/overview/rate-limits-and-node-limits-for-the-graphql-api#calculating-points-for-the-primary-rate-limit
+fn estimate_query_cost(page_size: u32) -> u32 {
+    let requests = 1 + 2 * page_size;
+
+    ((requests as f64 / 100.0).round() as u32).max(1)
+}
+
+// Returns the largest page, up to the requested size, whose query does not cost more than the max cost.
+fn get_page_size(per_page: u32, max_query_cost: u32) -> u32 {
+    (1..=per_page.clamp(1, MAX_PAGE_SIZE))
+        .rev()
+        .find(|page_size| estimate_query_cost(*page_size) <= max_query_cost)
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_query_cost() {
+        assert_eq!(estimate_query_cost(1), 1);
+        assert_eq!(estimate_query_cost(74), 1);
+        assert_eq!(estimate_query_cost(75), 2);
+        assert_eq!(estimate_query_cost(100), 2);
+    }
+
+    #[test]
+    fn test_rate_limited_message() {
+        let rate_limited = br#"{
+            "data": null,
+            "errors": [{ "type": "RATE_LIMITED", "message": "API rate limit exceeded for user ID 1." }]
+        }"#;
+        let failed = br#"{ "errors": [{ "message": "Parse error on \"}\" (RCURLY) at [1, 2]" }] }"#;
+
+        assert_eq!(
+            get_rate_limited_message(rate_limited),
+            Some(String::from("API rate limit exceeded for user ID 1."))
+        );
+        assert_eq!(get_rate_limited_message(failed), None);
+        assert_eq!(get_rate_limited_message(b"not json"), None);
+    }
+
+    #[test]
+    fn test_page_size_does_not_exceed_max_query_cost() {
+        assert_eq!(get_page_size(10, 1), 10);
+        assert_eq!(get_page_size(100, 1), 74);
+        assert_eq!(get_page_size(100, 2), 100);
+        assert_eq!(get_page_size(500, 5), MAX_PAGE_SIZE);
+        assert_eq!(get_page_size(0, 1), 1);
+    }
+}
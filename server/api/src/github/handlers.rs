@@ -11,20 +11,52 @@ use crate::errors::RustGoodFirstIssuesError;
 use crate::github::models::GetGithubRepositoriesParams;
 use crate::state::AppState;
 
-use super::client::GithubHttpClient;
-use super::models::{
-    GetGithubRepositoryGoodFirstIssuesParams, GetGithubRepositoryGoodFirstIssuesPathParams,
+use super::{
+    models::{
+        GetGithubRepositoriesWithGoodFirstIssuesParams, GetGithubRepositoryGoodFirstIssuesParams,
+        GetGithubRepositoryGoodFirstIssuesPathParams,
+    },
+    throttle::RequestUrgency,
 };
 
 #[tracing::instrument(name = "Get Github repositories handler", skip(state))]
 pub async fn get_repositories(
     state: State<Arc<AppState>>,
+    urgency: RequestUrgency,
     params: Query<GetGithubRepositoriesParams>,
 ) -> Result<Response, RustGoodFirstIssuesError> {
     let params = params.0;
-    let github_client = GithubHttpClient::new(state.github_settings.clone())?;
 
-    let res = github_client.get_rust_repositories(&params).await?;
+    let res = state
+        .github_client
+        .get_rust_repositories(&params, urgency)
+        .await?;
+
+    return Ok((StatusCode::OK, Json(res)).into_response());
+}
+
+#[tracing::instrument(
+    name = "Get Github repositories with good first issues handler",
+    skip(state)
+)]
+pub async fn get_repositories_with_good_first_issues(
+    state: State<Arc<AppState>>,
+    urgency: RequestUrgency,
+    params: Query<GetGithubRepositoriesWithGoodFirstIssuesParams>,
+) -> Result<Response, RustGoodFirstIssuesError> {
+    let params = params.0;
+
+    let res = state
+        .github_graphql_client
+        .get_rust_repositories_with_good_first_issues(&params, urgency)
+        .await?;
+
+    return Ok((StatusCode::OK, Json(res)).into_response());
+}
+
+#[tracing::instrument(name = "Get Github budget handler", skip(state))]
+pub async fn get_budget(state: State<Arc<AppState>>) -> Result<Response, RustGoodFirstIssuesError> {
+    let res = state.github_client.get_budget().await?;
 
     return Ok((StatusCode::OK, Json(res)).into_response());
 }
@@ -32,16 +64,16 @@ pub async fn get_repositories(
 #[tracing::instrument(name = "Get Github repository good first issues", skip(state))]
 pub async fn get_repository_good_first_issues(
     state: State<Arc<AppState>>,
+    urgency: RequestUrgency,
     path: Path<GetGithubRepositoryGoodFirstIssuesPathParams>,
     params: Query<GetGithubRepositoryGoodFirstIssuesParams>,
 ) -> Result<Response, RustGoodFirstIssuesError> {
     let params = params.0;
     let path_params = path.0;
 
-    let github_client = GithubHttpClient::new(state.github_settings.clone())?;
-
-    let res = github_client
-        .get_repository_good_first_issues(&path_params, &params)
+    let res = state
+        .github_client
+        .get_repository_good_first_issues(&path_params, &params, urgency)
         .await?;
 
     return Ok((StatusCode::OK, Json(res)).into_response());
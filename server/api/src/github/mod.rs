@@ -1,5 +1,11 @@
+pub mod app_auth;
 pub mod client;
+pub mod graphql;
 mod handlers;
 mod middlewares;
 pub mod models;
+pub mod rate_limit;
+pub mod retry;
 pub mod router;
+pub mod throttle;
+pub mod tokens;
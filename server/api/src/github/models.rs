@@ -1,6 +1,33 @@
 use redis_macros::FromRedisValue;
 use serde::{Deserialize, Serialize};
 
+// The known quotas of the Github tokens, for every rate limit resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetGithubBudgetResponse {
+    pub resources: Vec<GithubResourceBudget>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GithubResourceBudget {
+    pub resource: String,
+    pub remaining: u64,
+    pub limit: u64,
+    // Whether every token is exhausted
+    pub exhausted: bool,
+    pub tokens: Vec<GithubTokenBudget>,
+}
+
+// The quota of a token, which is unknown until a request is made with it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GithubTokenBudget {
+    pub id: String,
+    pub remaining: Option<u64>,
+    pub limit: Option<u64>,
+    // Seconds since epoch
+    pub reset: Option<u64>,
+    pub exhausted: bool,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SearchGithubRepositoriesResponseAPI {
     pub total_count: u32,
@@ -112,3 +139,122 @@ pub struct GetGithubRepositoryGoodFirstIssuesPathParams {
 pub struct GetGithubRepositoryGoodFirstIssuesResponse {
     pub items: Vec<GithubIssue>,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct GetGithubRepositoriesWithGoodFirstIssuesParams {
+    pub per_page: Option<u32>,
+    // The cursor of the page, returned with the previous one
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRedisValue)]
+pub struct GetGithubRepositoriesWithGoodFirstIssuesResponse {
+    pub total_count: u32,
+    pub items: Vec<GithubRepositoryWithGoodFirstIssues>,
+    // The cursor of the next page, or None on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GithubRepositoryWithGoodFirstIssues {
+    #[serde(flatten)]
+    pub repository: GithubRepository,
+    pub good_first_issues_count: u32,
+    // The most recently updated good first issues
+    pub good_first_issues: Vec<GithubIssue>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GraphqlResponseAPI<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GraphqlErrorAPI>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GraphqlErrorAPI {
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchGithubRepositoriesGraphqlAPI {
+    pub rate_limit: Option<GraphqlRateLimitAPI>,
+    pub search: GithubRepositorySearchGraphqlAPI,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GraphqlRateLimitAPI {
+    pub cost: u32,
+    pub remaining: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubRepositorySearchGraphqlAPI {
+    pub repository_count: u32,
+    pub page_info: GraphqlPageInfoAPI,
+    // The nodes that cannot be read, like the repositories that are not accessible anymore, are null
+    pub nodes: Vec<Option<GithubRepositoryGraphqlAPI>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphqlPageInfoAPI {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubRepositoryGraphqlAPI {
+    pub database_id: Option<u32>,
+    pub name_with_owner: String,
+    pub is_private: bool,
+    pub url: String,
+    pub description: Option<String>,
+    pub stargazer_count: u32,
+    pub has_issues_enabled: bool,
+    pub owner: GithubRepositoryOwnerGraphqlAPI,
+    pub license_info: Option<GithubRepositoryLicenseAPI>,
+    pub open_issues: GraphqlCountAPI,
+    pub good_first_issues: GithubIssueConnectionGraphqlAPI,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubRepositoryOwnerGraphqlAPI {
+    pub avatar_url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphqlCountAPI {
+    pub total_count: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubIssueConnectionGraphqlAPI {
+    pub total_count: u32,
+    pub nodes: Vec<Option<GithubIssueGraphqlAPI>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubIssueGraphqlAPI {
+    pub database_id: Option<u32>,
+    pub title: String,
+    pub body: Option<String>,
+    pub url: String,
+    pub state: GithubIssueStateGraphqlAPI,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum GithubIssueStateGraphqlAPI {
+    Open,
+    Closed,
+}
@@ -0,0 +1,283 @@
+use axum::http::{HeaderMap, StatusCode};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+// Github recommends waiting at least one minute when a rate limit error does not tell when to retry, and waiting an
+// exponentially increasing amount of time when the secondary rate limits keep being exceeded.
+const MIN_BACKOFF_TIME: u64 = 60;
+const MAX_BACKOFF_TIME: u64 = 1800;
+
+// The kind of rate limit exceeded by a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GithubRateLimitKind {
+    // The requests of the token per hour, reported on the x-ratelimit-* headers
+    Primary,
+    // The concurrent requests, or the requests per minute, of the token
+    Secondary,
+    // The abuse detection mechanism, which Github used to report the secondary rate limits with
+    Abuse,
+}
+
+impl GithubRateLimitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GithubRateLimitKind::Primary => "primary",
+            GithubRateLimitKind::Secondary => "secondary",
+            GithubRateLimitKind::Abuse => "abuse",
+        }
+    }
+}
+
+// The rate limit of a Github error response. Based on Github documentation, it is possible that there is a rate limit
+// error when status codes are 429 or 403. For more information, you can check the official page
+// https://docs.github.com/en/rest/using-the-rest-api/rate-limits-for-the-rest-api?apiVersion=2022-11-28#exceeding-the-rate-limit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GithubRateLimitError {
+    pub kind: GithubRateLimitKind,
+    // The time in seconds that you should wait before making the next request
+    pub retry_after: Option<i64>,
+    // The number of requests remaining in the current rate limit window
+    pub ratelimit_remaining: Option<i64>,
+    // The time at which the current rate limit window resets, in UTC epoch seconds
+    pub ratelimit_reset: Option<i64>,
+}
+
+impl GithubRateLimitError {
+    pub fn is_rate_limit_status(status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::FORBIDDEN
+    }
+
+    // Returns the rate limit error of a Github response, or None when the response did not exceed a rate limit. The secondary
+    // rate limits are often reported with just a message, so the message of the response is classified as well as its
+    // headers.
+    pub fn from_response(status: StatusCode, headers: &HeaderMap, message: &str) -> Option<Self> {
+        if !Self::is_rate_limit_status(status) {
+            return None;
+        }
+
+        let retry_after = get_header_number(headers, "retry-after");
+        let ratelimit_remaining = get_header_number(headers, "x-ratelimit-remaining");
+        let ratelimit_reset = get_header_number(headers, "x-ratelimit-reset");
+        let message = message.to_lowercase();
+
+        let kind = if message.contains("abuse") {
+            GithubRateLimitKind::Abuse
+        } else if message.contains("secondary rate limit") {
+            GithubRateLimitKind::Secondary
+        } else if ratelimit_remaining == Some(0) || message.contains("api rate limit exceeded") {
+            GithubRateLimitKind::Primary
+        } else if status == StatusCode::TOO_MANY_REQUESTS || retry_after.is_some() {
+            // Github only sends the retry-after header for the secondary rate limits
+            GithubRateLimitKind::Secondary
+        } else {
+            // The other 403 errors are not related to the rate limits, like the ones of the missing permissions
+            return None;
+        };
+
+        Some(GithubRateLimitError {
+            kind,
+            retry_after,
+            ratelimit_remaining,
+            ratelimit_reset,
+        })
+    }
+
+    // Returns the rate limit error of a GraphQL query that exceeded the rate limit of the GraphQL API, which is reported as an
+    // error of a successful response. The GraphQL API only has a primary rate limit, reset like the one of the REST API.
+    pub fn from_graphql_response(headers: &HeaderMap) -> Self {
+        GithubRateLimitError {
+            kind: GithubRateLimitKind::Primary,
+            retry_after: None,
+            ratelimit_remaining: get_header_number(headers, "x-ratelimit-remaining"),
+            ratelimit_reset: get_header_number(headers, "x-ratelimit-reset"),
+        }
+    }
+
+    // Returns whether the backoff time depends on the number of consecutive times the limit was exceeded. It is only the case
+    // for the secondary rate limits that do not tell when to retry.
+    pub fn is_exponential(&self) -> bool {
+        self.kind != GithubRateLimitKind::Primary && self.retry_after.is_none()
+    }
+
+    // Returns the time in seconds to wait before sending the next request, given the number of consecutive times the limit
+    // was exceeded (starting at 1). When the retry-after header is present, it is respected. The primary rate limit is waited
+    // until it is reset. Otherwise, it waits one minute, doubled every consecutive time, up to the max backoff time.
+    //
+    // It applies the logic describe on the official Github API documentation:
+    // https://docs.github.com/en/rest/using-the-rest-api/best-practices-for-using-the-rest-api?apiVersion=2022-11-28#handle-rate-limit-errors-appropriately
+    pub fn get_backoff_time(&self, strikes: u32) -> u64 {
+        if let Some(retry_after) = self.retry_after {
+            return retry_after.max(0) as u64;
+        }
+
+        if self.kind == GithubRateLimitKind::Primary {
+            return match self.ratelimit_reset.filter(|reset| *reset > 0) {
+                Some(reset) => (reset - Utc::now().timestamp()).max(0) as u64,
+                None => MIN_BACKOFF_TIME,
+            };
+        }
+
+        let exponent = strikes.saturating_sub(1).min(u64::BITS - 1);
+
+        MIN_BACKOFF_TIME
+            .saturating_mul(1 << exponent)
+            .min(MAX_BACKOFF_TIME)
+    }
+}
+
+fn get_header_number(headers: &HeaderMap, name: &str) -> Option<i64> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn headers(values: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in values {
+            headers.insert(*name, value.parse().unwrap());
+        }
+
+        headers
+    }
+
+    fn rate_limit_error(
+        status: StatusCode,
+        values: &[(&'static str, &str)],
+        message: &str,
+    ) -> Option<GithubRateLimitError> {
+        GithubRateLimitError::from_response(status, &headers(values), message)
+    }
+
+    #[test]
+    fn test_classify_primary_rate_limit() {
+        let error = rate_limit_error(
+            StatusCode::FORBIDDEN,
+            &[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700000000"),
+            ],
+            "API rate limit exceeded for user ID 1.",
+        )
+        .unwrap();
+
+        assert_eq!(error.kind, GithubRateLimitKind::Primary);
+        assert!(!error.is_exponential());
+    }
+
+    #[test]
+    fn test_classify_secondary_rate_limit_from_the_message() {
+        let error = rate_limit_error(
+            StatusCode::FORBIDDEN,
+            &[("x-ratelimit-remaining", "4000")],
+            "You have exceeded a secondary rate limit. Please wait a few minutes before you try again.",
+        )
+        .unwrap();
+
+        assert_eq!(error.kind, GithubRateLimitKind::Secondary);
+        assert!(error.is_exponential());
+        assert_eq!(
+            rate_limit_error(StatusCode::TOO_MANY_REQUESTS, &[], "Too many requests")
+                .unwrap()
+                .kind,
+            GithubRateLimitKind::Secondary
+        );
+    }
+
+    #[test]
+    fn test_classify_abuse_rate_limit() {
+        let error = rate_limit_error(
+            StatusCode::FORBIDDEN,
+            &[("retry-after", "30")],
+            "You have triggered an abuse detection mechanism.",
+        )
+        .unwrap();
+
+        assert_eq!(error.kind, GithubRateLimitKind::Abuse);
+        assert!(!error.is_exponential());
+    }
+
+    #[test]
+    fn test_classify_errors_that_are_not_rate_limits() {
+        assert!(rate_limit_error(
+            StatusCode::FORBIDDEN,
+            &[("x-ratelimit-remaining", "4000")],
+            "Resource not accessible by integration",
+        )
+        .is_none());
+        assert!(rate_limit_error(
+            StatusCode::UNAUTHORIZED,
+            &[("retry-after", "60")],
+            "Bad credentials"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_get_backoff_time_when_retry_after_is_present() {
+        let error = rate_limit_error(StatusCode::FORBIDDEN, &[("retry-after", "10")], "").unwrap();
+
+        assert_eq!(error.get_backoff_time(1), 10);
+        assert_eq!(error.get_backoff_time(5), 10);
+    }
+
+    #[test]
+    fn test_get_backoff_time_until_the_primary_rate_limit_is_reset() {
+        let tomorrow = (Utc::now() + Duration::days(1)).timestamp().to_string();
+        let error = rate_limit_error(
+            StatusCode::FORBIDDEN,
+            &[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", &tomorrow),
+            ],
+            "",
+        )
+        .unwrap();
+
+        assert!((86399..=86400).contains(&error.get_backoff_time(1)));
+    }
+
+    #[test]
+    fn test_get_backoff_time_is_at_least_one_minute_without_reset_info() {
+        let error = rate_limit_error(
+            StatusCode::FORBIDDEN,
+            &[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "0")],
+            "",
+        )
+        .unwrap();
+
+        assert_eq!(error.get_backoff_time(1), 60);
+    }
+
+    #[test]
+    fn test_graphql_rate_limit_is_waited_until_it_is_reset() {
+        let tomorrow = (Utc::now() + Duration::days(1)).timestamp().to_string();
+        let error = GithubRateLimitError::from_graphql_response(&headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", &tomorrow),
+        ]));
+
+        assert_eq!(error.kind, GithubRateLimitKind::Primary);
+        assert!(!error.is_exponential());
+        assert!((86399..=86400).contains(&error.get_backoff_time(1)));
+        assert_eq!(
+            GithubRateLimitError::from_graphql_response(&HeaderMap::new()).get_backoff_time(1),
+            60
+        );
+    }
+
+    #[test]
+    fn test_get_backoff_time_is_exponential_for_secondary_rate_limits() {
+        let error = rate_limit_error(StatusCode::TOO_MANY_REQUESTS, &[], "").unwrap();
+
+        assert_eq!(error.get_backoff_time(1), 60);
+        assert_eq!(error.get_backoff_time(2), 120);
+        assert_eq!(error.get_backoff_time(3), 240);
+        assert_eq!(error.get_backoff_time(100), MAX_BACKOFF_TIME);
+    }
+}
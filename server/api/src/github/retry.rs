@@ -0,0 +1,175 @@
+use rand::Rng;
+use reqwest::{
+    header::{self, HeaderMap},
+    StatusCode,
+};
+use std::time::Duration;
+
+use crate::{config::GithubRetrySettings, errors::RustGoodFirstIssuesError};
+
+use super::rate_limit::GithubRateLimitError;
+
+// The transient errors of Github, returned while it is deployed or overloaded
+const RETRYABLE_STATUS_CODES: [StatusCode; 3] = [
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+// A failed attempt of a request that can be sent again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryableFailure {
+    pub reason: &'static str,
+    // The time Github asked to wait before sending the request again
+    pub retry_after: Option<Duration>,
+}
+
+impl RetryableFailure {
+    // Returns the failure of the attempt when it can be retried: the 502, 503 and 504 responses, the connection errors and
+    // the rate limits Github tells when to retry. The other 4xx errors are never retried, as they would fail again.
+    pub fn from_result(
+        result: &Result<reqwest::Response, RustGoodFirstIssuesError>,
+    ) -> Option<Self> {
+        match result {
+            Ok(response) if RETRYABLE_STATUS_CODES.contains(&response.status()) => {
+                Some(RetryableFailure {
+                    reason: "server_error",
+                    retry_after: get_retry_after(response.headers()),
+                })
+            }
+            Err(RustGoodFirstIssuesError::Reqwest(err))
+                if err.is_connect() || err.is_timeout() || err.is_request() =>
+            {
+                Some(RetryableFailure {
+                    reason: "connection_error",
+                    retry_after: None,
+                })
+            }
+            Err(RustGoodFirstIssuesError::GithubRateLimited(_, retry_after)) => {
+                Some(RetryableFailure {
+                    reason: "rate_limited",
+                    retry_after: Some(Duration::from_secs(*retry_after)),
+                })
+            }
+            // The rate limits Github asked to retry right away
+            Err(RustGoodFirstIssuesError::GithubAPI(status, headers, _))
+                if GithubRateLimitError::is_rate_limit_status(*status) =>
+            {
+                get_retry_after(headers).map(|retry_after| RetryableFailure {
+                    reason: "rate_limited",
+                    retry_after: Some(retry_after),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+// The delays between the attempts of a request, following the "decorrelated jitter" strategy: every delay is a random time
+// between the base delay and three times the previous one, up to the max delay. The jitter keeps the replicas of the api from
+// retrying at the same time.
+#[derive(Clone, Debug)]
+pub struct RetryBackoff {
+    base_delay: Duration,
+    max_delay: Duration,
+    previous_delay: Duration,
+}
+
+impl RetryBackoff {
+    pub fn new(retry_settings: &GithubRetrySettings) -> Self {
+        RetryBackoff {
+            base_delay: Duration::from_millis(retry_settings.base_delay_ms),
+            max_delay: Duration::from_millis(retry_settings.max_delay_ms),
+            previous_delay: Duration::from_millis(retry_settings.base_delay_ms),
+        }
+    }
+
+    // Returns the time to wait before the next attempt, which is never shorter than the time Github asked to wait.
+    pub fn next_delay(&mut self, failure: &RetryableFailure) -> Duration {
+        self.previous_delay = decorrelated_jitter(
+            self.base_delay,
+            self.max_delay,
+            self.previous_delay,
+            &mut rand::thread_rng(),
+        );
+
+        match failure.retry_after {
+            Some(retry_after) => self.previous_delay.max(retry_after),
+            None => self.previous_delay,
+        }
+    }
+}
+
+fn decorrelated_jitter(
+    base_delay: Duration,
+    max_delay: Duration,
+    previous_delay: Duration,
+    rng: &mut impl Rng,
+) -> Duration {
+    let upper_bound = previous_delay.saturating_mul(3).max(base_delay);
+
+    rng.gen_range(base_delay..=upper_bound).min(max_delay)
+}
+
+fn get_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_decorrelated_jitter_is_bounded() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let base_delay = Duration::from_millis(100);
+        let max_delay = Duration::from_millis(2000);
+        let mut previous_delay = base_delay;
+
+        for _ in 0..100 {
+            let delay = decorrelated_jitter(base_delay, max_delay, previous_delay, &mut rng);
+
+            assert!(delay >= base_delay);
+            assert!(delay <= max_delay);
+            assert!(delay <= previous_delay * 3);
+
+            previous_delay = delay;
+        }
+    }
+
+    #[test]
+    fn test_next_delay_respects_retry_after() {
+        let mut backoff = RetryBackoff::new(&GithubRetrySettings {
+            max_attempts: 3,
+            base_delay_ms: 100,
+            max_delay_ms: 2000,
+            deadline: 10,
+        });
+
+        let delay = backoff.next_delay(&RetryableFailure {
+            reason: "server_error",
+            retry_after: Some(Duration::from_secs(5)),
+        });
+
+        assert_eq!(delay, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_rate_limits_without_retry_after_are_not_retried() {
+        let result = Err(RustGoodFirstIssuesError::GithubAPI(
+            StatusCode::FORBIDDEN,
+            HeaderMap::new(),
+            String::from("Resource not accessible by integration"),
+        ));
+
+        assert_eq!(RetryableFailure::from_result(&result), None);
+    }
+}
@@ -1,17 +1,29 @@
 use crate::{
-    github::handlers::{get_repositories, get_repository_good_first_issues},
+    github::handlers::{
+        get_budget, get_repositories, get_repositories_with_good_first_issues,
+        get_repository_good_first_issues,
+    },
     state::AppState,
 };
 use axum::{handler::Handler, middleware, routing, Router};
-use axum_redis_cache::middlewares::RedisCacheLayerBuilder;
+use axum_redis_cache::{
+    extractors::{KeyNormalization, ParamNormalization},
+    middlewares::RedisCacheLayerBuilder,
+    namespace::NamespaceBudget,
+};
 use std::sync::Arc;
 
 use super::{
-    middlewares::rate_limit_middleware,
-    models::{GetGithubRepositoriesResponse, GetGithubRepositoryGoodFirstIssuesResponse},
+    middlewares::cache_expiration_time_middleware,
+    models::{
+        GetGithubRepositoriesResponse, GetGithubRepositoriesWithGoodFirstIssuesResponse,
+        GetGithubRepositoryGoodFirstIssuesResponse,
+    },
 };
 
-const GITHUB_REDIS_EXPIRATION_TIME: i64 = 600;
+// Every page of the Github endpoints is saved as a different entry, so the number of entries of each route is limited.
+const GITHUB_REDIS_NAMESPACE_MAX_KEYS: u64 = 1000;
+const GITHUB_REDIS_NAMESPACE_MAX_BYTES: u64 = 64 * 1024 * 1024;
 
 pub struct GithubRepositoryRouter;
 
@@ -20,26 +32,65 @@ impl GithubRepositoryRouter {
         Router::new()
             .route(
                 "/repositories",
-                routing::get(get_repositories).layer(
-                    RedisCacheLayerBuilder::new(state.redis_pool.clone())
-                        .with_expiration_time(GITHUB_REDIS_EXPIRATION_TIME)
-                        .build::<GetGithubRepositoriesResponse>(),
+                routing::get(get_repositories)
+                    .layer(cache_layer_builder(&state).build::<GetGithubRepositoriesResponse>()),
+            )
+            // The repositories are fetched with their good first issues from the GraphQL API, in a single query per page
+            .route(
+                "/repositories/good-first-issues",
+                routing::get(
+                    get_repositories_with_good_first_issues.layer(
+                        cache_layer_builder(&state)
+                            .build::<GetGithubRepositoriesWithGoodFirstIssuesResponse>(),
+                    ),
                 ),
             )
             .route(
                 "/repositories/:repo/good-first-issues",
                 routing::get(
                     get_repository_good_first_issues.layer(
-                        RedisCacheLayerBuilder::new(state.redis_pool.clone())
-                            .with_expiration_time(GITHUB_REDIS_EXPIRATION_TIME)
+                        cache_layer_builder(&state)
                             .build::<GetGithubRepositoryGoodFirstIssuesResponse>(),
                     ),
                 ),
             )
             .route_layer(middleware::from_fn_with_state(
                 state.clone(),
-                rate_limit_middleware,
+                cache_expiration_time_middleware,
             ))
+            // The budget is read from Redis, so it is not cached
+            .route("/budget", routing::get(get_budget))
             .with_state(state)
     }
 }
+
+fn cache_layer_builder(state: &AppState) -> RedisCacheLayerBuilder {
+    let builder = RedisCacheLayerBuilder::new(state.redis_pool.clone())
+        .with_expiration_time(state.settings.load().github.cache_expiration_time as i64)
+        .with_route_namespace()
+        .with_namespace_budget(NamespaceBudget {
+            max_keys: Some(GITHUB_REDIS_NAMESPACE_MAX_KEYS),
+            max_bytes: Some(GITHUB_REDIS_NAMESPACE_MAX_BYTES),
+        })
+        .with_key_normalization(
+            KeyNormalization::new()
+                .with_param("page", ParamNormalization::Integer)
+                .with_param("per_page", ParamNormalization::Integer)
+                .with_param("owner", ParamNormalization::CaseInsensitive),
+        );
+
+    let builder = match state.settings.load().redis.key_prefix.clone() {
+        Some(key_prefix) => builder.with_key_prefix(key_prefix),
+        None => builder,
+    };
+
+    let builder = match state.redis_read_pool.clone() {
+        Some(redis_read_pool) => builder.with_read_pool(redis_read_pool),
+        None => builder,
+    };
+
+    match state.cache_cipher.clone() {
+        Some(cipher) => builder.with_encryption(cipher),
+        None => builder,
+    }
+}
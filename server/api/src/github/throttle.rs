@@ -0,0 +1,267 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderMap},
+};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::Instant;
+
+use crate::config::GithubThrottleSettings;
+
+use super::tokens::{RateLimitResource, TokenQuota};
+
+const PREFETCH_PURPOSE: &str = "prefetch";
+// The browsers send the purpose of the speculative requests on Sec-Purpose, and some older ones on Purpose
+const PURPOSE_HEADERS: [&str; 2] = ["sec-purpose", "purpose"];
+
+// Tells whether a user is waiting for a Github request. When a quota runs low, the deferrable requests are delayed up to the
+// max delay of the throttle settings, while the urgent ones are only delayed up to the (shorter) urgent max delay.
+//
+// The requests sent to answer the api requests are urgent, except the ones of the prefetches made by the browsers (which
+// send `Sec-Purpose: prefetch`), as nobody is looking at their response yet. The health check only calls an endpoint that
+// is not counted against the rate limits, so it is never throttled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestUrgency {
+    Urgent,
+    Deferrable,
+}
+
+impl RequestUrgency {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let is_prefetch = PURPOSE_HEADERS
+            .iter()
+            .filter_map(|name| headers.get(*name)?.to_str().ok())
+            .any(|purpose| purpose.split(';').next().map(str::trim) == Some(PREFETCH_PURPOSE));
+
+        if is_prefetch {
+            RequestUrgency::Deferrable
+        } else {
+            RequestUrgency::Urgent
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestUrgency
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(RequestUrgency::from_headers(&parts.headers))
+    }
+}
+
+// Paces the requests counted against a rate limit resource when its remaining requests run low, so the api slows down before
+// Github starts rejecting the requests. The paced requests are queued: every request reserves the next free slot of its
+// resource, and waits for it before it is sent.
+//
+// The queue is kept by every replica of the api, but they share the quotas saved on Redis, so all of them slow down when the
+// quota runs low.
+#[derive(Clone, Debug, Default)]
+pub struct GithubThrottle {
+    next_slots: Arc<Mutex<HashMap<&'static str, Instant>>>,
+}
+
+impl GithubThrottle {
+    // Waits until the request can be sent, given the quota of the token that sends it. The urgent requests wait for a shorter
+    // time, and they take a slot anyway, so the deferrable ones are spread around them.
+    pub async fn wait(
+        &self,
+        resource: RateLimitResource,
+        quota: Option<TokenQuota>,
+        urgency: RequestUrgency,
+        throttle_settings: &GithubThrottleSettings,
+    ) {
+        let Some(interval) = get_interval(quota, get_now_secs(), throttle_settings.low_watermark)
+        else {
+            return;
+        };
+
+        let delay = self.reserve_slot(
+            resource,
+            interval,
+            Duration::from_secs(throttle_settings.max_delay),
+        );
+
+        let delay = match urgency {
+            RequestUrgency::Urgent => {
+                delay.min(Duration::from_secs(throttle_settings.urgent_max_delay))
+            }
+            RequestUrgency::Deferrable => delay,
+        };
+
+        if delay.is_zero() {
+            return;
+        }
+
+        tracing::info!(
+            resource = resource.as_str(),
+            remaining = quota.map(|quota| quota.remaining),
+            urgency = ?urgency,
+            delay_ms = delay.as_millis() as u64,
+            "Github quota is running low, throttling request"
+        );
+
+        tokio::time::sleep(delay).await;
+    }
+
+    // Returns the time to wait until the next free slot of the resource, which is never longer than the max delay, and
+    // reserves the following one.
+    fn reserve_slot(
+        &self,
+        resource: RateLimitResource,
+        interval: Duration,
+        max_delay: Duration,
+    ) -> Duration {
+        let now = Instant::now();
+        let mut next_slots = self.next_slots.lock().expect("Throttle lock poisoned");
+        let next_slot = next_slots
+            .get(resource.as_str())
+            .copied()
+            .unwrap_or(now)
+            .max(now);
+        let delay = next_slot.duration_since(now).min(max_delay);
+
+        next_slots.insert(resource.as_str(), now + delay + interval);
+
+        delay
+    }
+}
+
+// Returns the time between the requests that spreads the remaining requests until the window is reset, or None when there
+// are enough remaining requests (or the quota is unknown).
+fn get_interval(quota: Option<TokenQuota>, now: u64, low_watermark: f64) -> Option<Duration> {
+    let quota = quota?;
+    let limit = quota.limit?;
+
+    if quota.reset <= now || quota.remaining as f64 > limit as f64 * low_watermark {
+        return None;
+    }
+
+    Some(Duration::from_secs_f64(
+        (quota.reset - now) as f64 / (quota.remaining + 1) as f64,
+    ))
+}
+
+fn get_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn quota(remaining: u64) -> Option<TokenQuota> {
+        Some(TokenQuota {
+            remaining,
+            reset: NOW + 100,
+            limit: Some(1000),
+        })
+    }
+
+    #[test]
+    fn test_interval_when_quota_is_low() {
+        assert_eq!(get_interval(quota(500), NOW, 0.1), None);
+        assert_eq!(get_interval(None, NOW, 0.1), None);
+        assert_eq!(
+            get_interval(quota(99), NOW, 0.1),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            get_interval(quota(0), NOW, 0.1),
+            Some(Duration::from_secs(100))
+        );
+        assert_eq!(get_interval(quota(0), NOW + 100, 0.1), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_are_queued_up_to_max_delay() {
+        let throttle = GithubThrottle::default();
+        let interval = Duration::from_secs(2);
+        let max_delay = Duration::from_secs(5);
+
+        let delays: Vec<Duration> = (0..5)
+            .map(|_| throttle.reserve_slot(RateLimitResource::Search, interval, max_delay))
+            .collect();
+
+        assert_eq!(delays, [0, 2, 4, 5, 5].map(Duration::from_secs).to_vec());
+        assert!(throttle
+            .reserve_slot(RateLimitResource::Core, interval, max_delay)
+            .is_zero());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_urgent_requests_are_delayed_less_than_deferrable_ones() {
+        let throttle = GithubThrottle::default();
+        let throttle_settings = GithubThrottleSettings {
+            low_watermark: 0.1,
+            max_delay: 5,
+            urgent_max_delay: 1,
+        };
+        let quota = Some(TokenQuota {
+            remaining: 0,
+            reset: get_now_secs() + 100,
+            limit: Some(1000),
+        });
+        let started_at = Instant::now();
+
+        for urgency in [RequestUrgency::Urgent, RequestUrgency::Urgent] {
+            throttle
+                .wait(RateLimitResource::Core, quota, urgency, &throttle_settings)
+                .await;
+        }
+
+        // The first urgent request takes the free slot, and the second one waits up to the urgent max delay
+        assert_eq!(started_at.elapsed(), Duration::from_secs(1));
+
+        // The slots taken by the urgent requests are waited for, up to the max delay
+        throttle
+            .wait(
+                RateLimitResource::Core,
+                quota,
+                RequestUrgency::Deferrable,
+                &throttle_settings,
+            )
+            .await;
+
+        assert_eq!(started_at.elapsed(), Duration::from_secs(6));
+    }
+
+    #[test]
+    fn test_prefetches_are_deferrable() {
+        let urgency = |name: &'static str, value: &'static str| {
+            let mut headers = HeaderMap::new();
+
+            headers.insert(name, value.parse().unwrap());
+
+            RequestUrgency::from_headers(&headers)
+        };
+
+        assert_eq!(
+            RequestUrgency::from_headers(&HeaderMap::new()),
+            RequestUrgency::Urgent
+        );
+        assert_eq!(
+            urgency("sec-purpose", "prefetch"),
+            RequestUrgency::Deferrable
+        );
+        assert_eq!(
+            urgency("sec-purpose", "prefetch;prerender"),
+            RequestUrgency::Deferrable
+        );
+        assert_eq!(urgency("purpose", "prefetch"), RequestUrgency::Deferrable);
+        assert_eq!(urgency("sec-purpose", "prerender"), RequestUrgency::Urgent);
+    }
+}
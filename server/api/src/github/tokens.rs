@@ -0,0 +1,683 @@
+use axum_redis_cache::pool::RedisConnectionManager;
+use bb8::Pool;
+use redis::{AsyncCommands, Script};
+use reqwest::header::HeaderMap;
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::RustGoodFirstIssuesError;
+
+use super::{
+    models::{GithubResourceBudget, GithubTokenBudget},
+    rate_limit::GithubRateLimitError,
+};
+
+// The quotas and backoffs of every token are saved with the same hash tag, so they can be read with a single pipeline on
+// Redis Cluster
+const TOKEN_KEYS_HASH_TAG: &str = "github:tokens";
+const TOKEN_BACKOFF_KEY: &str = "backoff";
+const TOKEN_STRIKES_KEY: &str = "strikes";
+// Longer than the max backoff time, so the count is kept while the token keeps exceeding the rate limit
+const TOKEN_STRIKES_EXPIRATION_TIME: i64 = 3600;
+const RATE_LIMIT_LIMIT_HEADER: &str = "x-ratelimit-limit";
+const RATE_LIMIT_REMAINING_HEADER: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET_HEADER: &str = "x-ratelimit-reset";
+const RATE_LIMIT_RESOURCE_HEADER: &str = "x-ratelimit-resource";
+// The number of bytes of the token hash used as id
+const TOKEN_ID_BYTES: usize = 6;
+
+// Saves the quota of a token, unless a newer one was already saved by another replica: a quota of a later window always
+// replaces the saved one, while a quota of the same window only replaces it when it has less remaining requests. The quota
+// expires when its window is reset.
+//
+// KEYS[1]: quota key. ARGV[1]: remaining requests, ARGV[2]: reset time in seconds since epoch, ARGV[3]: max requests of the
+// window, or 0 when it is unknown.
+const RECORD_QUOTA_SCRIPT: &str = r#"
+local remaining = tonumber(ARGV[1])
+local reset = tonumber(ARGV[2])
+local limit = tonumber(ARGV[3])
+local saved = redis.call('HMGET', KEYS[1], 'remaining', 'reset')
+local saved_remaining = tonumber(saved[1])
+local saved_reset = tonumber(saved[2])
+
+if saved_reset ~= nil and (reset < saved_reset or (reset == saved_reset and remaining >= saved_remaining)) then
+    return 0
+end
+
+redis.call('HSET', KEYS[1], 'remaining', remaining, 'reset', reset)
+
+if limit > 0 then
+    redis.call('HSET', KEYS[1], 'limit', limit)
+end
+
+redis.call('EXPIREAT', KEYS[1], reset)
+
+return 1
+"#;
+
+// Github limits the requests of every token per resource. The search endpoints have their own (lower) limit, and the GraphQL
+// API limits the points of the queries instead of the requests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitResource {
+    Core,
+    Search,
+    Graphql,
+}
+
+impl RateLimitResource {
+    pub const ALL: [RateLimitResource; 3] = [
+        RateLimitResource::Core,
+        RateLimitResource::Search,
+        RateLimitResource::Graphql,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitResource::Core => "core",
+            RateLimitResource::Search => "search",
+            RateLimitResource::Graphql => "graphql",
+        }
+    }
+}
+
+// A Github token with an id derived from its hash, so it can be logged and saved on Redis without exposing the token.
+#[derive(Clone, Debug)]
+pub struct GithubToken {
+    pub id: String,
+    token: Secret<String>,
+}
+
+impl GithubToken {
+    pub fn new(token: Secret<String>) -> Self {
+        let hash = Sha256::digest(token.expose_secret().as_bytes());
+        let id = hash[..TOKEN_ID_BYTES]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        GithubToken { id, token }
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        self.token.expose_secret()
+    }
+}
+
+// The remaining requests of a token until its rate limit window is reset, as reported by Github.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenQuota {
+    pub remaining: u64,
+    // Seconds since epoch
+    pub reset: u64,
+    // The max number of requests of the window
+    pub limit: Option<u64>,
+}
+
+impl TokenQuota {
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        Some(TokenQuota {
+            remaining: get_header_number(headers, RATE_LIMIT_REMAINING_HEADER)?,
+            reset: get_header_number(headers, RATE_LIMIT_RESET_HEADER)?,
+            limit: get_header_number(headers, RATE_LIMIT_LIMIT_HEADER),
+        })
+    }
+
+    // A token is only exhausted when Github reported that it has no remaining requests and its window is not reset yet.
+    pub fn is_exhausted(&self, now: u64) -> bool {
+        self.remaining == 0 && self.reset > now
+    }
+}
+
+// Keeps the quota of every Github token on Redis, so all the replicas of the api share the same view of the tokens, and picks
+// the token with the most remaining requests for every request.
+//
+// When Github rejects a request because a rate limit was exceeded, the token backs off from the rate limit resource of the
+// request until the limit is reset. The backoff applies to every request counted against the same resource, whatever the
+// route that sends it.
+#[derive(Clone, Debug)]
+pub struct GithubTokenPool {
+    redis_pool: Pool<RedisConnectionManager>,
+    hash_tag: String,
+}
+
+impl GithubTokenPool {
+    pub fn new(redis_pool: Pool<RedisConnectionManager>) -> Self {
+        GithubTokenPool {
+            redis_pool,
+            hash_tag: TOKEN_KEYS_HASH_TAG.to_string(),
+        }
+    }
+
+    // The prefix is part of the hash tag, so the keys of every token are still saved on the same Redis Cluster slot.
+    pub fn with_key_prefix(self, key_prefix: &str) -> Self {
+        GithubTokenPool {
+            hash_tag: format!("{}:{}", key_prefix, TOKEN_KEYS_HASH_TAG),
+            ..self
+        }
+    }
+
+    // Picks the healthiest token for the resource, returning its quota when it is known. The tokens that are backing off from
+    // the resource are skipped, unless `ignore_backoff` is set, and a rate limit error is returned when every token is
+    // backing off. It fails open: when the quotas or backoffs cannot be read from Redis, the first token is used. The tokens
+    // cannot be empty, as the settings require at least one.
+    pub async fn select(
+        &self,
+        tokens: Vec<GithubToken>,
+        resource: RateLimitResource,
+        ignore_backoff: bool,
+    ) -> Result<(GithubToken, Option<TokenQuota>), RustGoodFirstIssuesError> {
+        let quotas = match self.get_quotas(&tokens, resource).await {
+            Ok(quotas) => quotas,
+            Err(err) => {
+                tracing::warn!(error = %err, "Unable to get the quotas of the Github tokens");
+
+                vec![None; tokens.len()]
+            }
+        };
+
+        let backoffs = if ignore_backoff {
+            vec![None; tokens.len()]
+        } else {
+            self.get_backoffs(&tokens, resource)
+                .await
+                .unwrap_or_else(|err| {
+                    tracing::warn!(error = %err, "Unable to get the backoffs of the Github tokens");
+
+                    vec![None; tokens.len()]
+                })
+        };
+
+        let index = pick_token(&quotas, &backoffs, get_now_secs()).map_err(|retry_after| {
+            RustGoodFirstIssuesError::GithubRateLimited(resource.as_str().to_string(), retry_after)
+        })?;
+        let token = tokens
+            .into_iter()
+            .nth(index)
+            .expect("At least one Github token is required");
+
+        Ok((token, quotas[index]))
+    }
+
+    // Returns the known quotas of every token, for every resource.
+    pub async fn get_budget(
+        &self,
+        tokens: &[GithubToken],
+    ) -> Result<Vec<GithubResourceBudget>, RustGoodFirstIssuesError> {
+        let mut budget = vec![];
+
+        for resource in RateLimitResource::ALL {
+            let quotas = self.get_quotas(tokens, resource).await?;
+
+            budget.push(get_resource_budget(
+                resource,
+                tokens,
+                &quotas,
+                get_now_secs(),
+            ));
+        }
+
+        Ok(budget)
+    }
+
+    // Saves the quota reported by Github on the response headers, for the resource reported by Github.
+    pub async fn record(
+        &self,
+        token: &GithubToken,
+        resource: RateLimitResource,
+        headers: &HeaderMap,
+    ) {
+        let Some(quota) = TokenQuota::from_headers(headers) else {
+            return;
+        };
+
+        let resource = get_reported_resource(headers, resource);
+
+        if quota.is_exhausted(get_now_secs()) {
+            tracing::warn!(
+                token_id = token.id,
+                resource,
+                reset = quota.reset,
+                "Github token exhausted"
+            );
+        }
+
+        if let Err(err) = self.save_quota(token, resource, quota).await {
+            tracing::warn!(error = %err, "Unable to save the quota of the Github token");
+        }
+    }
+
+    // Makes the token back off from the resource Github reported on the response headers, returning the seconds to wait. The
+    // consecutive times the token exceeded a secondary rate limit are counted, so it backs off longer every time. It fails
+    // open: when the count cannot be read from Redis, it is expected to be the first time.
+    pub async fn back_off(
+        &self,
+        token: &GithubToken,
+        resource: RateLimitResource,
+        headers: &HeaderMap,
+        rate_limit_error: &GithubRateLimitError,
+    ) -> u64 {
+        let resource = get_reported_resource(headers, resource);
+
+        let strikes = if rate_limit_error.is_exponential() {
+            self.add_strike(token, resource).await.unwrap_or_else(|err| {
+                tracing::warn!(error = %err, "Unable to count the rate limit errors of the Github token");
+
+                1
+            })
+        } else {
+            1
+        };
+        let retry_after = rate_limit_error.get_backoff_time(strikes);
+
+        tracing::warn!(
+            token_id = token.id,
+            resource,
+            kind = rate_limit_error.kind.as_str(),
+            strikes,
+            retry_after,
+            "Github rate limit exceeded, backing off"
+        );
+
+        if retry_after > 0 {
+            if let Err(err) = self.save_backoff(token, resource, retry_after).await {
+                tracing::warn!(error = %err, "Unable to save the backoff of the Github token");
+            }
+        }
+
+        retry_after
+    }
+
+    async fn get_quotas(
+        &self,
+        tokens: &[GithubToken],
+        resource: RateLimitResource,
+    ) -> Result<Vec<Option<TokenQuota>>, RustGoodFirstIssuesError> {
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RustGoodFirstIssuesError::RedisConnection)?;
+        let mut pipeline = redis::pipe();
+
+        for token in tokens {
+            pipeline
+                .cmd("HMGET")
+                .arg(self.quota_key(token, resource.as_str()))
+                .arg("remaining")
+                .arg("reset")
+                .arg("limit");
+        }
+
+        let quotas: Vec<(Option<u64>, Option<u64>, Option<u64>)> = pipeline
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(RustGoodFirstIssuesError::Redis)?;
+
+        Ok(quotas
+            .into_iter()
+            .map(|quota| match quota {
+                (Some(remaining), Some(reset), limit) => Some(TokenQuota {
+                    remaining,
+                    reset,
+                    limit,
+                }),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn save_quota(
+        &self,
+        token: &GithubToken,
+        resource: &str,
+        quota: TokenQuota,
+    ) -> Result<(), RustGoodFirstIssuesError> {
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RustGoodFirstIssuesError::RedisConnection)?;
+
+        Script::new(RECORD_QUOTA_SCRIPT)
+            .key(self.quota_key(token, resource))
+            .arg(quota.remaining)
+            .arg(quota.reset)
+            .arg(quota.limit.unwrap_or_default())
+            .invoke_async::<()>(&mut *redis_conn)
+            .await
+            .map_err(RustGoodFirstIssuesError::Redis)
+    }
+
+    // Returns the seconds until every token can request the resource again, or None when it is not backing off.
+    async fn get_backoffs(
+        &self,
+        tokens: &[GithubToken],
+        resource: RateLimitResource,
+    ) -> Result<Vec<Option<u64>>, RustGoodFirstIssuesError> {
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RustGoodFirstIssuesError::RedisConnection)?;
+        let mut pipeline = redis::pipe();
+
+        for token in tokens {
+            pipeline.ttl(self.backoff_key(token, resource.as_str()));
+        }
+
+        // The TTL is negative when the key does not exist
+        let ttls: Vec<i64> = pipeline
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(RustGoodFirstIssuesError::Redis)?;
+
+        Ok(ttls
+            .into_iter()
+            .map(|ttl| u64::try_from(ttl).ok().filter(|ttl| *ttl > 0))
+            .collect())
+    }
+
+    // Counts a consecutive rate limit error of the token. The count expires when no error happens for a while, so the token
+    // backs off for one minute again.
+    async fn add_strike(
+        &self,
+        token: &GithubToken,
+        resource: &str,
+    ) -> Result<u32, RustGoodFirstIssuesError> {
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RustGoodFirstIssuesError::RedisConnection)?;
+        let strikes_key = self.strikes_key(token, resource);
+
+        let (strikes,): (u32,) = redis::pipe()
+            .incr(&strikes_key, 1)
+            .expire(&strikes_key, TOKEN_STRIKES_EXPIRATION_TIME)
+            .ignore()
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(RustGoodFirstIssuesError::Redis)?;
+
+        Ok(strikes)
+    }
+
+    fn quota_key(&self, token: &GithubToken, resource: &str) -> String {
+        format!("{{{}}}:{}:{}", self.hash_tag, resource, token.id)
+    }
+
+    fn backoff_key(&self, token: &GithubToken, resource: &str) -> String {
+        format!(
+            "{{{}}}:{}:{}:{}",
+            self.hash_tag, TOKEN_BACKOFF_KEY, resource, token.id
+        )
+    }
+
+    fn strikes_key(&self, token: &GithubToken, resource: &str) -> String {
+        format!(
+            "{{{}}}:{}:{}:{}",
+            self.hash_tag, TOKEN_STRIKES_KEY, resource, token.id
+        )
+    }
+
+    async fn save_backoff(
+        &self,
+        token: &GithubToken,
+        resource: &str,
+        retry_after: u64,
+    ) -> Result<(), RustGoodFirstIssuesError> {
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RustGoodFirstIssuesError::RedisConnection)?;
+
+        redis_conn
+            .set_ex::<_, _, ()>(self.backoff_key(token, resource), retry_after, retry_after)
+            .await
+            .map_err(RustGoodFirstIssuesError::Redis)
+    }
+}
+
+// Returns the index of the token with the most remaining requests, among the tokens that are not backing off. The tokens
+// without a known quota, or whose window was already reset, are expected to have all their requests available. When every
+// token is exhausted, the one whose window is reset first is picked. When every token is backing off, it returns the seconds
+// until the first one can be used again.
+fn pick_token(
+    quotas: &[Option<TokenQuota>],
+    backoffs: &[Option<u64>],
+    now: u64,
+) -> Result<usize, u64> {
+    if backoffs.iter().all(Option::is_some) {
+        if let Some(retry_after) = backoffs.iter().flatten().min() {
+            return Err(*retry_after);
+        }
+    }
+
+    let tokens: Vec<(usize, &Option<TokenQuota>)> = quotas
+        .iter()
+        .zip(backoffs)
+        .enumerate()
+        .filter(|(_, (_, backoff))| backoff.is_none())
+        .map(|(index, (quota, _))| (index, quota))
+        .collect();
+
+    let available_tokens = tokens
+        .iter()
+        .filter(|(_, quota)| !quota.is_some_and(|quota| quota.is_exhausted(now)))
+        .map(|(index, quota)| match quota {
+            Some(quota) if quota.reset > now => (*index, quota.remaining),
+            _ => (*index, u64::MAX),
+        });
+
+    // max_by_key returns the last max element, so the tokens are reversed to pick the first one on ties
+    if let Some((index, _)) = available_tokens
+        .rev()
+        .max_by_key(|(_, remaining)| *remaining)
+    {
+        return Ok(index);
+    }
+
+    Ok(tokens
+        .iter()
+        .filter_map(|(index, quota)| quota.map(|quota| (*index, quota.reset)))
+        .min_by_key(|(_, reset)| *reset)
+        .map(|(index, _)| index)
+        .unwrap_or_default())
+}
+
+// The remaining requests of the resource are the sum of the remaining requests of the tokens with a known quota. The quotas
+// of the windows that were already reset are not known anymore.
+fn get_resource_budget(
+    resource: RateLimitResource,
+    tokens: &[GithubToken],
+    quotas: &[Option<TokenQuota>],
+    now: u64,
+) -> GithubResourceBudget {
+    let token_budgets: Vec<GithubTokenBudget> = tokens
+        .iter()
+        .zip(quotas)
+        .map(|(token, quota)| {
+            let quota = quota.filter(|quota| quota.reset > now);
+
+            GithubTokenBudget {
+                id: token.id.clone(),
+                remaining: quota.map(|quota| quota.remaining),
+                limit: quota.and_then(|quota| quota.limit),
+                reset: quota.map(|quota| quota.reset),
+                exhausted: quota.is_some_and(|quota| quota.is_exhausted(now)),
+            }
+        })
+        .collect();
+
+    GithubResourceBudget {
+        resource: resource.as_str().to_string(),
+        remaining: token_budgets
+            .iter()
+            .filter_map(|token_budget| token_budget.remaining)
+            .sum(),
+        limit: token_budgets
+            .iter()
+            .filter_map(|token_budget| token_budget.limit)
+            .sum(),
+        exhausted: token_budgets
+            .iter()
+            .all(|token_budget| token_budget.exhausted),
+        tokens: token_budgets,
+    }
+}
+
+// Github reports the resource the request counted against, which is used instead of the expected one when it is present.
+fn get_reported_resource(headers: &HeaderMap, resource: RateLimitResource) -> &str {
+    headers
+        .get(RATE_LIMIT_RESOURCE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or(resource.as_str())
+}
+
+fn get_header_number(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
+
+fn get_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn quota(remaining: u64, reset: u64) -> Option<TokenQuota> {
+        Some(TokenQuota {
+            remaining,
+            reset,
+            limit: Some(5000),
+        })
+    }
+
+    #[test]
+    fn test_pick_token_prefers_most_remaining_requests() {
+        assert_eq!(
+            pick_token(
+                &[quota(10, NOW + 60), quota(4000, NOW + 60)],
+                &[None, None],
+                NOW
+            ),
+            Ok(1)
+        );
+        assert_eq!(
+            pick_token(
+                &[quota(10, NOW + 60), quota(10, NOW + 60)],
+                &[None, None],
+                NOW
+            ),
+            Ok(0)
+        );
+        // The quota of the first token is from a window that was already reset
+        assert_eq!(
+            pick_token(
+                &[quota(0, NOW - 1), quota(4000, NOW + 60)],
+                &[None, None],
+                NOW
+            ),
+            Ok(0)
+        );
+        assert_eq!(
+            pick_token(&[quota(4000, NOW + 60), None], &[None, None], NOW),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_pick_token_when_every_token_is_exhausted() {
+        assert_eq!(
+            pick_token(
+                &[quota(0, NOW + 600), quota(0, NOW + 60)],
+                &[None, None],
+                NOW
+            ),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_pick_token_skips_the_tokens_backing_off() {
+        assert_eq!(
+            pick_token(
+                &[quota(4000, NOW + 60), quota(10, NOW + 60)],
+                &[Some(30), None],
+                NOW
+            ),
+            Ok(1)
+        );
+        assert_eq!(
+            pick_token(
+                &[quota(4000, NOW + 60), quota(10, NOW + 60)],
+                &[Some(30), Some(10)],
+                NOW
+            ),
+            Err(10)
+        );
+    }
+
+    #[test]
+    fn test_resource_budget_only_counts_known_quotas() {
+        let tokens = [
+            GithubToken::new(Secret::new(String::from("token-a"))),
+            GithubToken::new(Secret::new(String::from("token-b"))),
+            GithubToken::new(Secret::new(String::from("token-c"))),
+        ];
+
+        let budget = get_resource_budget(
+            RateLimitResource::Core,
+            &tokens,
+            &[
+                quota(0, NOW + 60),
+                quota(100, NOW + 60),
+                quota(100, NOW - 1),
+            ],
+            NOW,
+        );
+
+        assert_eq!(budget.resource, "core");
+        assert_eq!(budget.remaining, 100);
+        assert_eq!(budget.limit, 10000);
+        assert!(!budget.exhausted);
+        assert!(budget.tokens[0].exhausted);
+        assert_eq!(budget.tokens[2].remaining, None);
+    }
+
+    #[test]
+    fn test_token_id_does_not_expose_the_token() {
+        let token = GithubToken::new(Secret::new(String::from("ghp_secret")));
+
+        assert_eq!(token.id.len(), TOKEN_ID_BYTES * 2);
+        assert!(!token.id.contains("secret"));
+        assert!(!format!("{:?}", token).contains("ghp_secret"));
+    }
+
+    #[tokio::test]
+    async fn test_key_prefix_is_part_of_the_hash_tag() {
+        let manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+        let redis_pool = Pool::builder().build_unchecked(manager);
+        let token = GithubToken::new(Secret::new(String::from("test-token")));
+
+        let token_pool = GithubTokenPool::new(redis_pool.clone());
+        let prefixed_token_pool = GithubTokenPool::new(redis_pool).with_key_prefix("test:1");
+
+        assert_eq!(
+            token_pool.backoff_key(&token, "core"),
+            format!("{{github:tokens}}:backoff:core:{}", token.id)
+        );
+        assert_eq!(
+            prefixed_token_pool.quota_key(&token, "core"),
+            format!("{{test:1:github:tokens}}:core:{}", token.id)
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use axum_redis_cache::pool::RedisConnectionManager;
+use bb8::Pool;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+use crate::github::client::GithubHttpClient;
+
+use super::models::{DependencyCheck, DependencyStatus};
+
+// The key is never created, it is only used to check that the RedisJSON commands are available
+const REDIS_JSON_PROBE_KEY: &str = "health:json_probe";
+
+// Keeps the result of the last check of a dependency, so it is not checked on every request.
+#[derive(Clone, Debug, Default)]
+pub struct CachedCheck {
+    last_check: Arc<Mutex<Option<(Instant, DependencyCheck)>>>,
+}
+
+impl CachedCheck {
+    pub async fn get_or_check<F, Fut>(&self, ttl: Duration, check: F) -> DependencyCheck
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = DependencyCheck>,
+    {
+        if let Some((checked_at, dependency_check)) = self.last_check().as_ref() {
+            if checked_at.elapsed() < ttl {
+                return DependencyCheck {
+                    cached: true,
+                    ..dependency_check.clone()
+                };
+            }
+        }
+
+        let dependency_check = check().await;
+
+        *self.last_check.lock().expect("Health check lock poisoned") =
+            Some((Instant::now(), dependency_check.clone()));
+
+        dependency_check
+    }
+
+    fn last_check(&self) -> Option<(Instant, DependencyCheck)> {
+        self.last_check
+            .lock()
+            .expect("Health check lock poisoned")
+            .clone()
+    }
+}
+
+// Checks that Redis replies to PING and has the RedisJSON module loaded, as the cached responses are saved as JSON documents.
+pub async fn check_redis(
+    redis_pool: &Pool<RedisConnectionManager>,
+    timeout: Duration,
+) -> DependencyCheck {
+    timed_check(timeout, async {
+        let mut redis_conn = redis_pool.get().await.map_err(|err| err.to_string())?;
+
+        redis::cmd("PING")
+            .query_async::<()>(&mut *redis_conn)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        redis::cmd("JSON.TYPE")
+            .arg(REDIS_JSON_PROBE_KEY)
+            .query_async::<()>(&mut *redis_conn)
+            .await
+            .map_err(|err| format!("RedisJSON module is not available: {}", err))
+    })
+    .await
+}
+
+// Checks that the Github API is reachable and the token is valid.
+pub async fn check_github(github_client: &GithubHttpClient, timeout: Duration) -> DependencyCheck {
+    timed_check(timeout, async {
+        github_client
+            .get_rate_limit()
+            .await
+            .map_err(|err| err.to_string())
+    })
+    .await
+}
+
+async fn timed_check(
+    timeout: Duration,
+    check: impl Future<Output = Result<(), String>>,
+) -> DependencyCheck {
+    let started_at = Instant::now();
+    let result = tokio::time::timeout(timeout, check)
+        .await
+        .unwrap_or_else(|_| Err(format!("Check timed out after {:?}", timeout)));
+    let latency_ms = started_at.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(()) => DependencyCheck {
+            status: DependencyStatus::Up,
+            latency_ms,
+            error: None,
+            cached: false,
+        },
+        Err(error) => DependencyCheck {
+            status: DependencyStatus::Down,
+            latency_ms,
+            error: Some(error),
+            cached: false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cached_check_reuses_result_within_ttl() {
+        let cached_check = CachedCheck::default();
+        let ttl = Duration::from_secs(60);
+
+        let first = cached_check
+            .get_or_check(ttl, || timed_check(ttl, async { Ok(()) }))
+            .await;
+        let second = cached_check
+            .get_or_check(ttl, || {
+                timed_check(ttl, async { Err(String::from("Down")) })
+            })
+            .await;
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+
+        let third = cached_check
+            .get_or_check(ttl, || {
+                timed_check(ttl, async { Err(String::from("Down")) })
+            })
+            .await;
+
+        assert!(!first.cached);
+        assert!(second.cached);
+        assert_eq!(second.status, DependencyStatus::Up);
+        assert!(!third.cached);
+        assert_eq!(third.status, DependencyStatus::Down);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timed_check_fails_after_timeout() {
+        let dependency_check = timed_check(Duration::from_secs(1), async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+
+            Ok(())
+        })
+        .await;
+
+        assert_eq!(dependency_check.status, DependencyStatus::Down);
+    }
+}
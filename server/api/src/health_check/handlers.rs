@@ -1,9 +1,150 @@
+use axum::extract::State;
 use axum::response::Response;
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use std::{sync::Arc, time::Duration};
 
+use crate::config::{DependencyFailure, HealthSettings};
 use crate::errors::RustGoodFirstIssuesError;
+use crate::state::AppState;
+
+use super::checks::{check_github, check_redis};
+use super::models::{
+    DependencyChecks, DependencyStatus, LivenessResponse, LivenessStatus, ReadinessResponse,
+    ReadinessStatus,
+};
+
+// The health check fails while the api is draining, so the load balancer stops sending requests to it.
+#[tracing::instrument(name = "Health check handler", skip(state))]
+pub async fn health_check(
+    state: State<Arc<AppState>>,
+) -> Result<Response, RustGoodFirstIssuesError> {
+    if !state.readiness.is_ready() {
+        return Ok((StatusCode::SERVICE_UNAVAILABLE).into_response());
+    }
 
-#[tracing::instrument(name = "Health check handler")]
-pub async fn health_check() -> Result<Response, RustGoodFirstIssuesError> {
     return Ok((StatusCode::OK).into_response());
 }
+
+// The api is alive as long as it can reply, no matter the state of its dependencies.
+#[tracing::instrument(name = "Liveness handler")]
+pub async fn liveness() -> Response {
+    (
+        StatusCode::OK,
+        Json(LivenessResponse {
+            status: LivenessStatus::Alive,
+        }),
+    )
+        .into_response()
+}
+
+#[tracing::instrument(name = "Readiness handler", skip(state))]
+pub async fn readiness(state: State<Arc<AppState>>) -> Response {
+    if !state.readiness.is_ready() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ReadinessResponse {
+                status: ReadinessStatus::Draining,
+                checks: None,
+            }),
+        )
+            .into_response();
+    }
+
+    let settings = state.settings.load();
+    let timeout = Duration::from_secs(settings.health.check_timeout);
+
+    let (redis, github) = tokio::join!(
+        check_redis(&state.redis_pool, timeout),
+        state.github_probe.get_or_check(
+            Duration::from_secs(settings.health.github_probe_ttl),
+            || check_github(&state.github_client, timeout),
+        )
+    );
+
+    let checks = DependencyChecks { redis, github };
+    let status = get_readiness_status(&checks, &settings.health);
+    let status_code = match status {
+        ReadinessStatus::Ready | ReadinessStatus::Degraded => StatusCode::OK,
+        ReadinessStatus::Failing | ReadinessStatus::Draining => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (
+        status_code,
+        Json(ReadinessResponse {
+            status,
+            checks: Some(checks),
+        }),
+    )
+        .into_response()
+}
+
+fn get_readiness_status(
+    checks: &DependencyChecks,
+    health_settings: &HealthSettings,
+) -> ReadinessStatus {
+    let failures: Vec<DependencyFailure> = [
+        (&checks.redis, health_settings.redis_failure),
+        (&checks.github, health_settings.github_failure),
+    ]
+    .into_iter()
+    .filter(|(check, _)| check.status == DependencyStatus::Down)
+    .map(|(_, failure)| failure)
+    .collect();
+
+    if failures.contains(&DependencyFailure::Failing) {
+        ReadinessStatus::Failing
+    } else if failures.is_empty() {
+        ReadinessStatus::Ready
+    } else {
+        ReadinessStatus::Degraded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health_check::models::DependencyCheck;
+
+    fn dependency_check(status: DependencyStatus) -> DependencyCheck {
+        DependencyCheck {
+            status,
+            latency_ms: 1,
+            error: None,
+            cached: false,
+        }
+    }
+
+    fn health_settings(redis_failure: DependencyFailure) -> HealthSettings {
+        HealthSettings {
+            redis_failure,
+            github_failure: DependencyFailure::Failing,
+            check_timeout: 2,
+            github_probe_ttl: 60,
+        }
+    }
+
+    #[test]
+    fn test_readiness_status_depends_on_dependency_failure() {
+        let redis_down = DependencyChecks {
+            redis: dependency_check(DependencyStatus::Down),
+            github: dependency_check(DependencyStatus::Up),
+        };
+        let all_up = DependencyChecks {
+            redis: dependency_check(DependencyStatus::Up),
+            github: dependency_check(DependencyStatus::Up),
+        };
+
+        assert_eq!(
+            get_readiness_status(&redis_down, &health_settings(DependencyFailure::Degraded)),
+            ReadinessStatus::Degraded
+        );
+        assert_eq!(
+            get_readiness_status(&redis_down, &health_settings(DependencyFailure::Failing)),
+            ReadinessStatus::Failing
+        );
+        assert_eq!(
+            get_readiness_status(&all_up, &health_settings(DependencyFailure::Failing)),
+            ReadinessStatus::Ready
+        );
+    }
+}
@@ -1,2 +1,4 @@
+pub mod checks;
 mod handlers;
+pub mod models;
 pub mod router;
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LivenessStatus {
+    Alive,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LivenessResponse {
+    pub status: LivenessStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadinessStatus {
+    Ready,
+    // Some dependency is failing, but the api can still serve requests
+    Degraded,
+    Failing,
+    // The api is shutting down
+    Draining,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DependencyCheck {
+    pub status: DependencyStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // True when the result comes from a previous check
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DependencyChecks {
+    pub redis: DependencyCheck,
+    pub github: DependencyCheck,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReadinessResponse {
+    pub status: ReadinessStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checks: Option<DependencyChecks>,
+}
@@ -4,12 +4,15 @@ use axum::{routing, Router};
 
 use crate::state::AppState;
 
-use super::handlers::health_check;
+use super::handlers::{health_check, liveness, readiness};
 
 pub struct HealthCheckRouter;
 
 impl HealthCheckRouter {
     pub fn build() -> Router<Arc<AppState>> {
-        Router::new().route("/health-check", routing::get(health_check))
+        Router::new()
+            .route("/health-check", routing::get(health_check))
+            .route("/health/live", routing::get(liveness))
+            .route("/health/ready", routing::get(readiness))
     }
 }
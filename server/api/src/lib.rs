@@ -3,4 +3,6 @@ pub mod config;
 pub mod errors;
 pub mod github;
 pub mod health_check;
+pub mod reload;
+pub mod shutdown;
 pub mod state;
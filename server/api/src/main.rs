@@ -3,13 +3,17 @@ mod config;
 mod errors;
 mod github;
 mod health_check;
+mod reload;
+mod shutdown;
 mod state;
 mod telemetry;
 
 use anyhow::Error;
 use app::App;
+use std::time::Duration;
 
-use config::get_app_settings;
+use config::{get_app_environment, get_app_settings};
+use reload::{spawn_settings_reloader, SettingsHandle};
 
 use telemetry::{get_subscriber, init_subscriber};
 
@@ -24,14 +28,24 @@ async fn main() -> Result<(), Error> {
     init_subscriber(subscriber);
 
     let settings = get_app_settings().expect("Unable to get server settings");
-    let app = App::new(settings.clone()).await?;
+    let settings_handle = SettingsHandle::new(settings.clone(), get_app_environment());
+    let app = App::new(settings_handle.clone()).await?;
+
+    spawn_settings_reloader(settings_handle)?;
 
     let addr = settings.application.get_addr()?;
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
 
     tracing::info!("Server running on {}", addr);
 
-    axum::serve(listener, app.router).await?;
+    shutdown::serve(
+        listener,
+        app.router,
+        app.state.readiness.clone(),
+        Duration::from_secs(settings.application.shutdown_delay),
+        Duration::from_secs(settings.application.drain_timeout),
+    )
+    .await?;
 
     Ok(())
 }
@@ -0,0 +1,192 @@
+//! The settings are kept behind a SettingsHandle, which can be swapped atomically while the api is running. The handlers,
+//! the Github client and the cache layer read the current settings on every request, so they pick up the new values on the
+//! next request after a reload.
+//!
+//! The settings are reloaded when the process receives a SIGHUP signal, or when the settings file of the environment changes.
+//! Invalid settings are rejected, keeping the current ones. Only the `github` and `health` sections are reloaded: the rest of
+//! the settings (and the `github.http` ones) are used to build the Redis pools, the Github client, the router and its layers
+//! when the api starts, so changing them requires a restart.
+//!
+//! The environment variables are the ones of the running process, so only the changes made to the settings file are picked up.
+//! The secrets that need to be rotated without a restart, like the Github tokens, can be provided with `github.tokens_file`
+//! instead, which is read again on every reload (a change of the file alone is only picked up on SIGHUP).
+use arc_swap::ArcSwap;
+use std::{
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    task::JoinHandle,
+};
+
+use crate::config::{
+    get_environment_settings, get_settings_file_path, SettingChange, Settings, SettingsError,
+};
+
+// The interval, in seconds, to check if the settings file changed
+const SETTINGS_WATCH_INTERVAL: u64 = 5;
+const RELOADABLE_SECTIONS: [&str; 2] = ["github", "health"];
+// The settings of the reloadable sections used when the api starts
+const RESTART_REQUIRED_SECTIONS: [&str; 1] = ["github.http"];
+
+#[derive(Clone, Debug)]
+pub struct SettingsHandle {
+    settings: Arc<ArcSwap<Settings>>,
+    environment: String,
+}
+
+impl SettingsHandle {
+    pub fn new(settings: Settings, environment: impl Into<String>) -> Self {
+        SettingsHandle {
+            settings: Arc::new(ArcSwap::from_pointee(settings)),
+            environment: environment.into(),
+        }
+    }
+
+    // Returns the current settings. They are not affected by later reloads.
+    pub fn load(&self) -> Arc<Settings> {
+        self.settings.load_full()
+    }
+
+    // Loads the settings of the environment again and applies them, returning the changes.
+    pub fn reload(&self) -> Result<Vec<SettingChange>, SettingsError> {
+        let new_settings = get_environment_settings(&self.environment)?;
+
+        Ok(self.apply(new_settings))
+    }
+
+    // Replaces the reloadable sections of the current settings. The returned changes include the ones that were not applied
+    // because they require a restart.
+    pub fn apply(&self, new_settings: Settings) -> Vec<SettingChange> {
+        let current_settings = self.settings.load_full();
+        let changes = current_settings.diff(&new_settings);
+
+        let mut github_settings = new_settings.github;
+        github_settings.http = current_settings.github.http.clone();
+
+        self.settings.store(Arc::new(Settings {
+            github: github_settings,
+            health: new_settings.health,
+            ..Settings::clone(&current_settings)
+        }));
+
+        changes
+    }
+}
+
+pub fn requires_restart(change: &SettingChange) -> bool {
+    let is_in_section = |section: &&str| change.key.starts_with(&format!("{}.", section));
+
+    !RELOADABLE_SECTIONS.iter().any(is_in_section)
+        || RESTART_REQUIRED_SECTIONS.iter().any(is_in_section)
+}
+
+// Reloads the settings on SIGHUP, or when the settings file of the environment changes.
+pub fn spawn_settings_reloader(settings: SettingsHandle) -> Result<JoinHandle<()>, std::io::Error> {
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    Ok(tokio::spawn(async move {
+        let settings_file = get_settings_file_path(&settings.environment);
+        let mut modified_at = get_modified_time(&settings_file);
+        let mut watch_interval =
+            tokio::time::interval(Duration::from_secs(SETTINGS_WATCH_INTERVAL));
+
+        loop {
+            tokio::select! {
+                Some(()) = hangup.recv() => {
+                    tracing::info!("Received SIGHUP, reloading settings");
+                }
+                _ = watch_interval.tick() => {
+                    let file_modified_at = get_modified_time(&settings_file);
+
+                    if file_modified_at == modified_at {
+                        continue;
+                    }
+
+                    modified_at = file_modified_at;
+
+                    tracing::info!(
+                        settings_file = %settings_file.display(),
+                        "Settings file changed, reloading settings"
+                    );
+                }
+            }
+
+            reload_settings(&settings);
+        }
+    }))
+}
+
+fn reload_settings(settings: &SettingsHandle) {
+    let changes = match settings.reload() {
+        Ok(changes) => changes,
+        Err(err) => {
+            tracing::error!(error = %err, "Invalid settings, keeping the current ones");
+
+            return;
+        }
+    };
+
+    if changes.is_empty() {
+        tracing::info!("Settings reloaded without changes");
+    }
+
+    for change in changes {
+        if requires_restart(&change) {
+            tracing::warn!(
+                key = change.key,
+                old_value = change.old_value,
+                new_value = change.new_value,
+                "Setting changed, but it requires a restart to be applied"
+            );
+        } else {
+            tracing::info!(
+                key = change.key,
+                old_value = change.old_value,
+                new_value = change.new_value,
+                "Setting changed"
+            );
+        }
+    }
+}
+
+fn get_modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_only_replaces_reloadable_settings() {
+        let settings = get_environment_settings("test").unwrap();
+        let settings_handle = SettingsHandle::new(settings.clone(), "test");
+
+        let mut new_settings = settings.clone();
+        new_settings.github.cache_expiration_time = 30;
+        new_settings.github.http.timeout = 60;
+        new_settings.redis.pool_connection_timeout = 30;
+
+        let changes = settings_handle.apply(new_settings);
+
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.key, requires_restart(change)))
+                .collect::<Vec<_>>(),
+            vec![
+                ("github.cache_expiration_time", false),
+                ("github.http.timeout", true),
+                ("redis.pool_connection_timeout", true),
+            ]
+        );
+        assert_eq!(settings_handle.load().github.cache_expiration_time, 30);
+        assert_eq!(settings_handle.load().github.http.timeout, 30);
+        assert_eq!(settings_handle.load().redis.pool_connection_timeout, 10);
+    }
+}
@@ -0,0 +1,165 @@
+//! The api shuts down gracefully on SIGTERM or SIGINT (Ctrl+C):
+//!
+//! 1. The readiness flips to not ready, so the health check fails and the load balancer stops sending new requests.
+//! 2. The server keeps accepting connections during the shutdown delay, while the load balancer notices the failing health
+//!    check.
+//! 3. The server stops accepting connections and waits for the in-flight requests to finish, up to the drain timeout.
+//! 4. The background tasks (like the cache stats recorded by the cache layer) are flushed before exiting.
+use axum::Router;
+use axum_redis_cache::tasks::flush_background_tasks;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    net::TcpListener,
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+};
+
+// The max time, in seconds, to wait for the background tasks once the server stopped
+const BACKGROUND_TASKS_FLUSH_TIMEOUT: u64 = 10;
+
+// Tells whether the api is ready to receive requests. It stops being ready once the api starts draining.
+#[derive(Clone, Debug)]
+pub struct Readiness {
+    draining: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Readiness {
+            draining: Arc::new(watch::Sender::new(false)),
+        }
+    }
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        !*self.draining.borrow()
+    }
+
+    pub fn start_draining(&self) {
+        self.draining.send_replace(true);
+    }
+
+    pub async fn wait_for_draining(&self) {
+        let mut draining = self.draining.subscribe();
+
+        // The sender lives as long as self, so it cannot be closed while waiting
+        let _ = draining.wait_for(|draining| *draining).await;
+    }
+}
+
+// Serves the router until a shutdown signal is received, then drains the connections and flushes the background tasks.
+pub async fn serve(
+    listener: TcpListener,
+    router: Router,
+    readiness: Readiness,
+    shutdown_delay: Duration,
+    drain_timeout: Duration,
+) -> Result<(), std::io::Error> {
+    let shutdown_readiness = readiness.clone();
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        wait_for_shutdown_signal().await;
+
+        stop_being_ready(&shutdown_readiness, shutdown_delay).await;
+
+        tracing::info!(?drain_timeout, "Draining connections");
+    });
+
+    tokio::select! {
+        result = server => result?,
+        _ = async {
+            readiness.wait_for_draining().await;
+            tokio::time::sleep(shutdown_delay + drain_timeout).await;
+        } => {
+            tracing::warn!("Drain timeout elapsed, closing the remaining connections");
+        }
+    }
+
+    if !flush_background_tasks(Duration::from_secs(BACKGROUND_TASKS_FLUSH_TIMEOUT)).await {
+        tracing::warn!("Some background tasks did not finish before exiting");
+    }
+
+    tracing::info!("Server stopped");
+
+    Ok(())
+}
+
+// Flips the readiness and waits for the shutdown delay, while the server keeps serving the requests that the load
+// balancer still sends.
+async fn stop_being_ready(readiness: &Readiness, shutdown_delay: Duration) {
+    tracing::info!(?shutdown_delay, "Shutting down, no longer ready");
+
+    readiness.start_draining();
+    tokio::time::sleep(shutdown_delay).await;
+}
+
+async fn wait_for_shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %err, "Unable to listen for Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    let terminate = async {
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                tracing::error!(error = %err, "Unable to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_readiness_flips_when_draining() {
+        let readiness = Readiness::default();
+        let waiting_readiness = readiness.clone();
+
+        let waiting = tokio::spawn(async move { waiting_readiness.wait_for_draining().await });
+
+        assert!(readiness.is_ready());
+
+        readiness.start_draining();
+
+        assert!(!readiness.is_ready());
+        assert!(tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_readiness_flips_before_the_shutdown_delay() {
+        let readiness = Readiness::default();
+        let stopping_readiness = readiness.clone();
+
+        let stopping = tokio::spawn(async move {
+            stop_being_ready(&stopping_readiness, Duration::from_secs(5)).await
+        });
+
+        tokio::time::sleep(Duration::from_secs(4)).await;
+
+        assert!(!readiness.is_ready());
+        assert!(!stopping.is_finished());
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        assert!(stopping.is_finished());
+    }
+}
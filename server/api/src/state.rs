@@ -1,10 +1,21 @@
+use axum_redis_cache::{codec::CacheCipher, pool::RedisConnectionManager};
 use bb8::Pool;
-use bb8_redis::RedisConnectionManager;
 
-use crate::config::GithubSettings;
+use crate::{
+    github::{client::GithubHttpClient, graphql::GithubGraphqlClient},
+    health_check::checks::CachedCheck,
+    reload::SettingsHandle,
+    shutdown::Readiness,
+};
 
 #[derive(Clone, Debug)]
 pub struct AppState {
-    pub github_settings: GithubSettings,
+    pub settings: SettingsHandle,
+    pub github_client: GithubHttpClient,
+    pub github_graphql_client: GithubGraphqlClient,
+    pub readiness: Readiness,
+    pub github_probe: CachedCheck,
     pub redis_pool: Pool<RedisConnectionManager>,
+    pub redis_read_pool: Option<Pool<RedisConnectionManager>>,
+    pub cache_cipher: Option<CacheCipher>,
 }
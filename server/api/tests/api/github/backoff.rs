@@ -0,0 +1,413 @@
+use api::github::client::GithubApiErrorPayload;
+use chrono::{Duration, Utc};
+use redis::AsyncCommands;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::TestApp;
+
+fn rate_limit_error() -> GithubApiErrorPayload {
+    serde_json::from_str(
+        r#"{
+            "message": "Too many requests"
+        }"#,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_different_error_than_rate_limit() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories", base_url);
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/search/repositories"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(401)
+                .set_body_json(rate_limit_error())
+                .append_header("retry-after", "60"),
+        )
+        .named("Throw unauthorized error when getting repositories from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let redis_key = app.github_backoff_key("search");
+    let mut redis_conn = app.redis_connection().await;
+
+    let is_backing_off: bool = redis_conn.exists(&redis_key).await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 401);
+    assert!(!is_backing_off);
+}
+
+#[tokio::test]
+async fn test_back_off_from_the_resource_when_retry_after_is_present() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories", base_url);
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/search/repositories"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .set_body_json(rate_limit_error())
+                .append_header("retry-after", "60"),
+        )
+        .named("Throw rate limit error when getting repositories from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let redis_key = app.github_backoff_key("search");
+    let mut redis_conn = app.redis_connection().await;
+
+    let is_backing_off: bool = redis_conn.exists(&redis_key).await.unwrap();
+    let backoff_expiration_time: i64 = redis_conn.ttl(&redis_key).await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 429);
+    assert_eq!(res.headers()["retry-after"], "60");
+    assert!(is_backing_off);
+    assert_eq!(backoff_expiration_time, 60);
+}
+
+#[tokio::test]
+async fn test_back_off_exponentially_from_secondary_rate_limits() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories", base_url);
+    let client = reqwest::Client::new();
+
+    // The secondary rate limits can be reported with just a message
+    Mock::given(path("/search/repositories"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+            "message": "You have exceeded a secondary rate limit. Please wait a few minutes before you try again."
+        })))
+        .named("Throw secondary rate limit error when getting repositories from Github")
+        .expect(2)
+        .mount(&app.github_server)
+        .await;
+
+    let redis_key = app.github_backoff_key("search");
+    let mut redis_conn = app.redis_connection().await;
+    let mut backoff_expiration_times = vec![];
+
+    for _ in 0..2 {
+        let res = client
+            .get(&url)
+            .send()
+            .await
+            .expect("Failed to execute api request.");
+
+        assert_eq!(res.status().as_u16(), 429);
+
+        let backoff_expiration_time: i64 = redis_conn.ttl(&redis_key).await.unwrap();
+
+        backoff_expiration_times.push(backoff_expiration_time);
+        // The backoff is removed, so the next request is sent to Github
+        let _: () = redis_conn.del(&redis_key).await.unwrap();
+    }
+
+    assert_eq!(backoff_expiration_times, [60, 120]);
+}
+
+#[tokio::test]
+async fn test_not_back_off_when_forbidden_error_is_not_a_rate_limit() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories", base_url);
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/search/repositories"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .set_body_json(serde_json::json!({
+                    "message": "Resource not accessible by integration"
+                }))
+                .append_header("x-ratelimit-remaining", "4000"),
+        )
+        .named("Throw forbidden error when getting repositories from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let redis_key = app.github_backoff_key("search");
+    let mut redis_conn = app.redis_connection().await;
+
+    let is_backing_off: bool = redis_conn.exists(&redis_key).await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 403);
+    assert!(!is_backing_off);
+}
+
+#[tokio::test]
+async fn test_backoff_applies_to_every_route_of_the_resource() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/repos/cube-js/cube/issues"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .set_body_json(rate_limit_error())
+                .append_header("retry-after", "60"),
+        )
+        .named("Throw rate limit error when getting cube repository issues from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    // The requests to other repositories are counted against the same resource, so they are not sent to Github
+    Mock::given(path("/repos/tokio-rs/tokio/issues"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+        .named("Get tokio repository issues from Github")
+        .expect(0)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(format!(
+            "{}/api/v1/github/repositories/cube/good-first-issues?owner=cube-js",
+            base_url
+        ))
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status().as_u16(), 429);
+
+    let res = client
+        .get(format!(
+            "{}/api/v1/github/repositories/tokio/good-first-issues?owner=tokio-rs",
+            base_url
+        ))
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status().as_u16(), 429);
+
+    let retry_after: u64 = res.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+
+    assert!(retry_after > 0 && retry_after <= 60);
+    assert_eq!(body["resource"], "core");
+    assert_eq!(body["retry_after"], retry_after);
+}
+
+#[tokio::test]
+async fn test_backoff_does_not_apply_to_other_resources() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+    let client = reqwest::Client::new();
+    let mut redis_conn = app.redis_connection().await;
+
+    let _: () = redis_conn
+        .set_ex(app.github_backoff_key("search"), 60, 60)
+        .await
+        .unwrap();
+
+    Mock::given(path("/repos/cube-js/cube/issues"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+        .named("Get cube repository issues from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(format!(
+            "{}/api/v1/github/repositories/cube/good-first-issues?owner=cube-js",
+            base_url
+        ))
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn test_not_back_off_when_retry_after_equals_to_0() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories", base_url);
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/search/repositories"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .set_body_json(rate_limit_error())
+                .append_header("retry-after", "0"),
+        )
+        .named("Throw rate limit error when getting repositories from Github")
+        // Github asked to retry right away, so the request is sent until the max attempts are reached
+        .expect(3)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let redis_key = app.github_backoff_key("search");
+    let mut redis_conn = app.redis_connection().await;
+
+    let is_backing_off: bool = redis_conn.exists(&redis_key).await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 429);
+    assert!(!is_backing_off);
+}
+
+#[tokio::test]
+async fn test_back_off_one_minute_when_ratelimit_remaining_is_greater_than_0() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories", base_url);
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/search/repositories"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .set_body_json(rate_limit_error())
+                .append_header("x-ratelimit-remaining", "120"),
+        )
+        .named("Throw rate limit error when getting repositories from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let redis_key = app.github_backoff_key("search");
+    let mut redis_conn = app.redis_connection().await;
+
+    let backoff_expiration_time: i64 = redis_conn.ttl(&redis_key).await.unwrap();
+
+    // Github does not tell when to retry, so it waits at least one minute
+    assert_eq!(res.status().as_u16(), 429);
+    assert_eq!(res.headers()["retry-after"], "60");
+    assert_eq!(backoff_expiration_time, 60);
+}
+
+#[tokio::test]
+async fn test_back_off_one_minute_when_ratelimit_reset_is_equals_to_0() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories", base_url);
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/search/repositories"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .set_body_json(rate_limit_error())
+                .append_header("x-ratelimit-reset", "0"),
+        )
+        .named("Throw rate limit error when getting repositories from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let redis_key = app.github_backoff_key("search");
+    let mut redis_conn = app.redis_connection().await;
+
+    let backoff_expiration_time: i64 = redis_conn.ttl(&redis_key).await.unwrap();
+
+    // Github does not tell when to retry, so it waits at least one minute
+    assert_eq!(res.status().as_u16(), 429);
+    assert_eq!(res.headers()["retry-after"], "60");
+    assert_eq!(backoff_expiration_time, 60);
+}
+
+#[tokio::test]
+async fn test_back_off_when_ratelimit_remaining_equals_to_0_and_ratelimit_reset_greater_than_0() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories", base_url);
+    let client = reqwest::Client::new();
+    let tomorrow = Utc::now() + Duration::days(1);
+
+    Mock::given(path("/search/repositories"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .set_body_json(rate_limit_error())
+                .append_header("x-ratelimit-remaining", "0")
+                .append_header("x-ratelimit-reset", tomorrow.timestamp().to_string()),
+        )
+        .named("Throw rate limit error when getting repositories from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let redis_key = app.github_backoff_key("search");
+    let mut redis_conn = app.redis_connection().await;
+
+    let is_backing_off: bool = redis_conn.exists(&redis_key).await.unwrap();
+    let backoff_expiration_time: i64 = redis_conn.ttl(&redis_key).await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 429);
+    assert!(is_backing_off);
+    // The comparison between today and tomorrow gives as a result one second less than 24 hours
+    assert_eq!(backoff_expiration_time, 86399);
+}
@@ -0,0 +1,32 @@
+use api::config::Settings;
+use wiremock::{
+    matchers::{header, method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn test_enterprise_server_path_prefix_and_api_version() {
+    let app = TestApp::new().await;
+    let mut settings = Settings::clone(&app.state.settings.load());
+
+    settings
+        .github
+        .set_api_url(format!("{}/api/v3", app.github_server.uri()));
+    settings.github.api_version = String::from("2022-08-09");
+    app.state.settings.apply(settings);
+
+    Mock::given(path("/api/v3/rate_limit"))
+        .and(method("GET"))
+        .and(header("X-GitHub-Api-Version", "2022-08-09"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "resources": {}
+        })))
+        .named("Get rate limit from Github Enterprise Server")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    app.state.github_client.get_rate_limit().await.unwrap();
+}
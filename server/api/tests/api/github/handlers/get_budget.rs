@@ -0,0 +1,61 @@
+use api::github::models::GetGithubBudgetResponse;
+use std::time::{SystemTime, UNIX_EPOCH};
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn test_get_github_budget() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+    let reset = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+
+    Mock::given(path("/rate_limit"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("x-ratelimit-limit", "5000")
+                .insert_header("x-ratelimit-remaining", "4321")
+                .insert_header("x-ratelimit-reset", reset.to_string().as_str())
+                .insert_header("x-ratelimit-resource", "core")
+                .set_body_json(serde_json::json!({ "resources": {} })),
+        )
+        .named("Get rate limit from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    app.state.github_client.get_rate_limit().await.unwrap();
+
+    let res = reqwest::Client::new()
+        .get(format!("{}/api/v1/github/budget", base_url))
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 200);
+
+    let body: GetGithubBudgetResponse = res.json().await.unwrap();
+    let core = body
+        .resources
+        .iter()
+        .find(|budget| budget.resource == "core")
+        .unwrap();
+    let search = body
+        .resources
+        .iter()
+        .find(|budget| budget.resource == "search")
+        .unwrap();
+
+    assert_eq!(core.remaining, 4321);
+    assert_eq!(core.limit, 5000);
+    assert_eq!(core.tokens[0].reset, Some(reset));
+    assert_eq!(search.tokens[0].remaining, None);
+}
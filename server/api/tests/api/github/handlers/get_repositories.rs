@@ -2,7 +2,6 @@ use api::github::{
     client::GithubApiErrorPayload,
     models::{GetGithubRepositoriesResponse, SearchGithubRepositoriesResponseAPI},
 };
-use serial_test::serial;
 use wiremock::{
     matchers::{method, path},
     Mock, ResponseTemplate,
@@ -31,7 +30,6 @@ const MOCK_GITHUB_REPOSITORIES_RESPONSE: &str = r#"{
 }"#;
 
 #[tokio::test]
-#[serial]
 async fn test_get_github_repositories() {
     let app = TestApp::new().await;
     let base_url = app.spawn_app().await;
@@ -79,14 +77,39 @@ async fn test_get_github_repositories() {
     assert_eq!(item.open_issues_count, 0);
     assert!(item.has_issues);
     assert_eq!(item.license, None);
+}
+
+#[tokio::test]
+async fn test_get_github_repositories_allows_cross_origin_requests() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
 
-    let redis_key = "api:v1:github:repositories".to_string();
+    let url = format!("{}/api/v1/github/repositories", base_url);
+    let client = reqwest::Client::new();
 
-    app.redis_json_del(redis_key).await;
+    let mock_github_response: SearchGithubRepositoriesResponseAPI =
+        serde_json::from_str(MOCK_GITHUB_REPOSITORIES_RESPONSE).unwrap();
+
+    Mock::given(path("/search/repositories"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_github_response))
+        .named("Get repositories from Github for another origin")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .header("Origin", "https://rust-good-first-issues.com")
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["Access-Control-Allow-Origin"], "*");
 }
 
 #[tokio::test]
-#[serial]
 async fn test_get_github_repositories_from_redis() {
     let app = TestApp::new().await;
     let base_url = app.spawn_app().await;
@@ -142,14 +165,9 @@ async fn test_get_github_repositories_from_redis() {
     assert_eq!(item.open_issues_count, 0);
     assert!(item.has_issues);
     assert_eq!(item.license, None);
-
-    let redis_key = "api:v1:github:repositories".to_string();
-
-    app.redis_json_del(redis_key).await;
 }
 
 #[tokio::test]
-#[serial]
 async fn test_get_github_repositories_error() {
     let app = TestApp::new().await;
     let base_url = app.spawn_app().await;
@@ -181,14 +199,9 @@ async fn test_get_github_repositories_error() {
     let status = res.status();
 
     assert_eq!(status, 400);
-
-    let redis_key = "errors:rate_limit:api:v1:github:repositories".to_string();
-
-    app.redis_json_del(redis_key).await;
 }
 
 #[tokio::test]
-#[serial]
 async fn test_get_github_repositories_rate_limit_error() {
     let app = TestApp::new().await;
     let base_url = app.spawn_app().await;
@@ -224,8 +237,5 @@ async fn test_get_github_repositories_rate_limit_error() {
     let status = res.status();
 
     assert_eq!(status, 429);
-
-    let redis_key = "errors:rate_limit:api:v1:github:repositories".to_string();
-
-    app.redis_json_del(redis_key).await;
+    assert_eq!(res.headers()["retry-after"], "60");
 }
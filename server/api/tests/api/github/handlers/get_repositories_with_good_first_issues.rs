@@ -0,0 +1,334 @@
+use api::github::models::{GetGithubRepositoriesWithGoodFirstIssuesResponse, GithubIssueState};
+use redis::AsyncCommands;
+use serde_json::json;
+use wiremock::{
+    matchers::{body_partial_json, method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::TestApp;
+
+const MOCK_GITHUB_GRAPHQL_RESPONSE: &str = r#"{
+    "data": {
+        "rateLimit": {
+            "cost": 1,
+            "remaining": 4999
+        },
+        "search": {
+            "repositoryCount": 2,
+            "pageInfo": {
+                "hasNextPage": true,
+                "endCursor": "Y3Vyc29yOjE="
+            },
+            "nodes": [
+                {
+                    "databaseId": 1296269,
+                    "nameWithOwner": "octocat/Hello-World",
+                    "isPrivate": false,
+                    "url": "https://github.com/octocat/Hello-World",
+                    "description": "This your first repo!",
+                    "stargazerCount": 80,
+                    "hasIssuesEnabled": true,
+                    "owner": {
+                        "avatarUrl": "https://github.com/images/error/octocat_happy.gif"
+                    },
+                    "licenseInfo": {
+                        "name": "MIT License"
+                    },
+                    "openIssues": {
+                        "totalCount": 12
+                    },
+                    "goodFirstIssues": {
+                        "totalCount": 3,
+                        "nodes": [
+                            {
+                                "databaseId": 1,
+                                "title": "Found a bug",
+                                "body": "I'm having a problem with this.",
+                                "url": "https://github.com/octocat/Hello-World/issues/1347",
+                                "state": "OPEN"
+                            }
+                        ]
+                    }
+                }
+            ]
+        }
+    }
+}"#;
+
+#[tokio::test]
+async fn test_get_github_repositories_with_good_first_issues() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!(
+        "{}/api/v1/github/repositories/good-first-issues?per_page=10",
+        base_url
+    );
+    let client = reqwest::Client::new();
+
+    let mock_github_response: serde_json::Value =
+        serde_json::from_str(MOCK_GITHUB_GRAPHQL_RESPONSE).unwrap();
+
+    Mock::given(path("/graphql"))
+        .and(method("POST"))
+        .and(body_partial_json(json!({
+            "variables": {
+                "first": 10,
+                "issues": 5
+            }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_github_response))
+        .named("Get repositories with good first issues from Github GraphQL API")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let status = res.status();
+    let body: GetGithubRepositoriesWithGoodFirstIssuesResponse = res.json().await.unwrap();
+
+    assert_eq!(status, 200);
+    assert_eq!(body.total_count, 2);
+    assert_eq!(body.next_cursor, Some("Y3Vyc29yOjE=".to_string()));
+
+    let item = &body.items[0];
+
+    assert_eq!(item.repository.id, 1296269);
+    assert_eq!(
+        item.repository.url,
+        "https://github.com/octocat/Hello-World"
+    );
+    assert_eq!(item.repository.name, "octocat/Hello-World");
+    assert!(!item.repository.private);
+    assert_eq!(
+        item.repository.avatar_url,
+        "https://github.com/images/error/octocat_happy.gif"
+    );
+    assert_eq!(item.repository.stars_count, 80);
+    assert_eq!(item.repository.open_issues_count, 12);
+    assert!(item.repository.has_issues);
+    assert_eq!(item.repository.license, Some("MIT License".to_string()));
+    assert_eq!(item.good_first_issues_count, 3);
+
+    let issue = &item.good_first_issues[0];
+
+    assert_eq!(issue.id, 1);
+    assert_eq!(issue.title, "Found a bug");
+    assert_eq!(
+        issue.url,
+        "https://github.com/octocat/Hello-World/issues/1347"
+    );
+    assert_eq!(issue.state, GithubIssueState::Open);
+    assert!(issue.pull_request.is_none());
+}
+
+#[tokio::test]
+async fn test_get_github_repositories_with_good_first_issues_partial_data() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories/good-first-issues", base_url);
+    let client = reqwest::Client::new();
+
+    let mut mock_github_response: serde_json::Value =
+        serde_json::from_str(MOCK_GITHUB_GRAPHQL_RESPONSE).unwrap();
+    let nodes = mock_github_response["data"]["search"]["nodes"]
+        .as_array_mut()
+        .unwrap();
+
+    // The nodes that cannot be read are null, and the issues without a database id are skipped
+    nodes.insert(0, serde_json::Value::Null);
+    nodes[1]["goodFirstIssues"]["nodes"]
+        .as_array_mut()
+        .unwrap()
+        .push(json!({
+            "databaseId": null,
+            "title": "Found another bug",
+            "body": null,
+            "url": "https://github.com/octocat/Hello-World/issues/1348",
+            "state": "OPEN"
+        }));
+    mock_github_response["errors"] = json!([
+        {
+            "type": "FORBIDDEN",
+            "message": "Resource not accessible by integration"
+        }
+    ]);
+
+    Mock::given(path("/graphql"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_github_response))
+        .named("Get partial data from Github GraphQL API")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let status = res.status();
+    let body: GetGithubRepositoriesWithGoodFirstIssuesResponse = res.json().await.unwrap();
+
+    assert_eq!(status, 200);
+    assert_eq!(body.items.len(), 1);
+    assert_eq!(body.items[0].repository.id, 1296269);
+    assert_eq!(body.items[0].good_first_issues.len(), 1);
+    assert_eq!(body.items[0].good_first_issues[0].id, 1);
+}
+
+#[tokio::test]
+async fn test_get_github_repositories_with_good_first_issues_page_size_is_limited_by_query_cost() {
+    let app = TestApp::with_settings(|settings| {
+        settings.github.graphql.max_query_cost = 1;
+    })
+    .await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!(
+        "{}/api/v1/github/repositories/good-first-issues?per_page=100&cursor=Y3Vyc29yOjE%3D",
+        base_url
+    );
+    let client = reqwest::Client::new();
+
+    let mock_github_response: serde_json::Value =
+        serde_json::from_str(MOCK_GITHUB_GRAPHQL_RESPONSE).unwrap();
+
+    // A page of 100 repositories would cost 2 points, so only 74 are requested
+    Mock::given(path("/graphql"))
+        .and(method("POST"))
+        .and(body_partial_json(json!({
+            "variables": {
+                "first": 74,
+                "after": "Y3Vyc29yOjE="
+            }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_github_response))
+        .named("Get a page of repositories within the max query cost")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn test_get_github_repositories_with_good_first_issues_rate_limit_error() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories/good-first-issues", base_url);
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/graphql"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": null,
+            "errors": [
+                {
+                    "type": "RATE_LIMITED",
+                    "message": "API rate limit exceeded for user ID 1."
+                }
+            ]
+        })))
+        .named("Throw rate limit error when getting repositories from Github GraphQL API")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let status = res.status();
+
+    assert_eq!(status, 429);
+    assert_eq!(res.headers()["retry-after"], "60");
+
+    // The token that sent the query backs off from the GraphQL API
+    let mut redis_conn = app.redis_connection().await;
+    let backoff_expiration_time: i64 = redis_conn
+        .ttl(app.github_backoff_key("graphql"))
+        .await
+        .unwrap();
+
+    assert_eq!(backoff_expiration_time, 60);
+}
+
+#[tokio::test]
+async fn test_get_github_repositories_with_good_first_issues_error() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories/good-first-issues", base_url);
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/graphql"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "errors": [
+                {
+                    "message": "Parse error on \"}\" (RCURLY) at [1, 2]"
+                }
+            ]
+        })))
+        .named("Throw error when the Github GraphQL query fails")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 502);
+}
+
+#[tokio::test]
+async fn test_get_github_repositories_with_good_first_issues_error_status() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let url = format!("{}/api/v1/github/repositories/good-first-issues", base_url);
+    let client = reqwest::Client::new();
+
+    Mock::given(path("/graphql"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+            "message": "Bad credentials"
+        })))
+        .named("Throw unauthorized error when getting repositories from Github GraphQL API")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 401);
+    assert_eq!(
+        res.text().await.unwrap(),
+        "Github API error 401 Unauthorized: Bad credentials"
+    );
+}
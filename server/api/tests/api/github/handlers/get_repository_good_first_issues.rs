@@ -1,10 +1,13 @@
 use api::github::{
     client::GithubApiErrorPayload,
-    models::{GetGithubRepositoryGoodFirstIssuesResponse, GithubIssueAPI, GithubIssueState},
+    models::{
+        GetGithubRepositoryGoodFirstIssuesParams, GetGithubRepositoryGoodFirstIssuesPathParams,
+        GetGithubRepositoryGoodFirstIssuesResponse, GithubIssueAPI, GithubIssueState,
+    },
+    throttle::RequestUrgency,
 };
-use serial_test::serial;
 use wiremock::{
-    matchers::{method, path, query_param},
+    matchers::{header, method, path, query_param},
     Mock, ResponseTemplate,
 };
 
@@ -27,7 +30,6 @@ const MOCK_GITHUB_REPOSITORY_ISSUES_RESPONSE: &str = r#"[
 ]"#;
 
 #[tokio::test]
-#[serial]
 async fn test_get_github_repository_good_first_issues() {
     let app = TestApp::new().await;
     let base_url = app.spawn_app().await;
@@ -81,14 +83,9 @@ async fn test_get_github_repository_good_first_issues() {
         item.pull_request.unwrap().url,
         "https://github.com/octocat/Hello-World/pull/1347"
     );
-
-    let redis_key = "api:v1:github:repositories:cube:good-first-issues:owner=cube-js".to_string();
-
-    app.redis_json_del(redis_key).await;
 }
 
 #[tokio::test]
-#[serial]
 async fn test_get_github_repository_good_first_issues_from_redis() {
     let app = TestApp::new().await;
     let base_url = app.spawn_app().await;
@@ -148,14 +145,9 @@ async fn test_get_github_repository_good_first_issues_from_redis() {
         item.pull_request.unwrap().url,
         "https://github.com/octocat/Hello-World/pull/1347"
     );
-
-    let redis_key = "api:v1:github:repositories:cube:good-first-issues:owner=cube-js".to_string();
-
-    app.redis_json_del(redis_key).await;
 }
 
 #[tokio::test]
-#[serial]
 async fn test_get_github_repository_good_first_issues_error() {
     let app = TestApp::new().await;
     let base_url = app.spawn_app().await;
@@ -192,15 +184,9 @@ async fn test_get_github_repository_good_first_issues_error() {
     let status = res.status();
 
     assert_eq!(status, 400);
-
-    let redis_key =
-        "errors:rate_limit:api:v1:github:repositories:cube:good-first-issues".to_string();
-
-    app.redis_json_del(redis_key).await;
 }
 
 #[tokio::test]
-#[serial]
 async fn test_get_github_repository_good_first_issues_rate_limit_error() {
     let app = TestApp::new().await;
     let base_url = app.spawn_app().await;
@@ -241,9 +227,54 @@ async fn test_get_github_repository_good_first_issues_rate_limit_error() {
     let status = res.status();
 
     assert_eq!(status, 429);
+    assert_eq!(res.headers()["retry-after"], "60");
+}
 
-    let redis_key =
-        "errors:rate_limit:api:v1:github:repositories:cube:good-first-issues".to_string();
+#[tokio::test]
+async fn test_get_github_repository_good_first_issues_revalidates_with_etag() {
+    let app = TestApp::new().await;
+    let path_params = GetGithubRepositoryGoodFirstIssuesPathParams {
+        repo: String::from("cube"),
+    };
+    let params = GetGithubRepositoryGoodFirstIssuesParams {
+        owner: String::from("cube-js"),
+        per_page: None,
+        page: None,
+    };
+
+    let mock_response: Vec<GithubIssueAPI> =
+        serde_json::from_str(MOCK_GITHUB_REPOSITORY_ISSUES_RESPONSE).unwrap();
 
-    app.redis_json_del(redis_key).await;
+    Mock::given(path("/repos/cube-js/cube/issues"))
+        .and(method("GET"))
+        .and(header("If-None-Match", "\"issues-etag\""))
+        .respond_with(ResponseTemplate::new(304))
+        .named("Revalidate Cube repository issues from Github")
+        .with_priority(1)
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    Mock::given(path("/repos/cube-js/cube/issues"))
+        .and(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("ETag", "\"issues-etag\"")
+                .set_body_json(mock_response),
+        )
+        .named("Get Cube repository issues from Github")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    for _ in 0..2 {
+        let body = app
+            .state
+            .github_client
+            .get_repository_good_first_issues(&path_params, &params, RequestUrgency::Urgent)
+            .await
+            .unwrap();
+
+        assert_eq!(body.items.first().unwrap().title, "Found a bug");
+    }
 }
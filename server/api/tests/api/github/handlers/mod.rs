@@ -1,2 +1,4 @@
+pub mod get_budget;
 pub mod get_repositories;
+pub mod get_repositories_with_good_first_issues;
 pub mod get_repository_good_first_issues;
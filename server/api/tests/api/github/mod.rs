@@ -1,2 +1,6 @@
+pub mod app_auth;
+pub mod backoff;
+pub mod enterprise_server;
 pub mod handlers;
-pub mod middlewares;
+pub mod retry;
+pub mod tokens;
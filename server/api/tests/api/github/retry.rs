@@ -0,0 +1,90 @@
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn test_retry_transient_errors() {
+    let app = TestApp::with_settings(|settings| settings.github.retry.deadline = 60).await;
+
+    Mock::given(path("/rate_limit"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(503))
+        .named("Github is unavailable")
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    Mock::given(path("/rate_limit"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "resources": {}
+        })))
+        .named("Github is available again")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    app.state.github_client.get_rate_limit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_retry_up_to_max_attempts() {
+    let app = TestApp::with_settings(|settings| {
+        settings.github.retry.max_attempts = 2;
+        settings.github.retry.deadline = 60;
+    })
+    .await;
+
+    Mock::given(path("/rate_limit"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(502).set_body_json(serde_json::json!({
+            "message": "Server Error"
+        })))
+        .named("Github keeps failing")
+        .expect(2)
+        .mount(&app.github_server)
+        .await;
+
+    let result = app.state.github_client.get_rate_limit().await;
+
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Github API error 502 Bad Gateway: Server Error"
+    );
+}
+
+#[tokio::test]
+async fn test_not_retry_when_retry_after_exceeds_the_deadline() {
+    let app = TestApp::with_settings(|settings| settings.github.retry.deadline = 1).await;
+
+    Mock::given(path("/rate_limit"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(503).append_header("retry-after", "30"))
+        .named("Github is unavailable for a while")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    assert!(app.state.github_client.get_rate_limit().await.is_err());
+}
+
+#[tokio::test]
+async fn test_not_retry_client_errors() {
+    let app = TestApp::new().await;
+
+    Mock::given(path("/rate_limit"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+            "message": "Not Found"
+        })))
+        .named("Github endpoint not found")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    assert!(app.state.github_client.get_rate_limit().await.is_err());
+}
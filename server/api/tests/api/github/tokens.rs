@@ -0,0 +1,52 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use wiremock::{
+    matchers::{header, method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::TestApp;
+
+fn rate_limit_response(remaining: u64, reset: u64) -> ResponseTemplate {
+    ResponseTemplate::new(200)
+        .insert_header("x-ratelimit-remaining", remaining.to_string().as_str())
+        .insert_header("x-ratelimit-reset", reset.to_string().as_str())
+        .insert_header("x-ratelimit-resource", "core")
+        .set_body_json(serde_json::json!({ "resources": {} }))
+}
+
+#[tokio::test]
+async fn test_exhausted_token_is_not_used_until_reset() {
+    let app = TestApp::with_settings(|settings| {
+        settings
+            .github
+            .set_tokens(vec![String::from("token-a"), String::from("token-b")])
+    })
+    .await;
+    let reset = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+
+    Mock::given(path("/rate_limit"))
+        .and(method("GET"))
+        .and(header("Authorization", "Bearer token-a"))
+        .respond_with(rate_limit_response(0, reset))
+        .named("Exhaust the first token")
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    Mock::given(path("/rate_limit"))
+        .and(method("GET"))
+        .and(header("Authorization", "Bearer token-b"))
+        .respond_with(rate_limit_response(4999, reset))
+        .named("Use the second token")
+        .expect(2)
+        .mount(&app.github_server)
+        .await;
+
+    for _ in 0..3 {
+        app.state.github_client.get_rate_limit().await.unwrap();
+    }
+}
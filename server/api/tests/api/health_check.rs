@@ -1,3 +1,11 @@
+use api::health_check::models::{
+    DependencyStatus, LivenessResponse, LivenessStatus, ReadinessResponse, ReadinessStatus,
+};
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
 use crate::helpers::TestApp;
 
 #[tokio::test]
@@ -16,3 +24,129 @@ async fn test_health_check_returns_ok_response() {
 
     assert_eq!(res.status(), 200);
 }
+
+#[tokio::test]
+async fn test_health_check_returns_service_unavailable_while_draining() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    app.state.readiness.start_draining();
+
+    let url = format!("{}/health-check", base_url);
+    let client = reqwest::Client::new();
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 503);
+}
+
+#[tokio::test]
+async fn test_liveness_returns_alive() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    let res = reqwest::get(format!("{}/health/live", base_url))
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 200);
+
+    let body: LivenessResponse = res.json().await.unwrap();
+
+    assert_eq!(body.status, LivenessStatus::Alive);
+}
+
+#[tokio::test]
+async fn test_readiness_checks_redis_and_github() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rate_limit"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+        .expect(1)
+        .mount(&app.github_server)
+        .await;
+
+    let client = reqwest::Client::new();
+    let url = format!("{}/health/ready", base_url);
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 200);
+
+    let body: ReadinessResponse = res.json().await.unwrap();
+    let checks = body.checks.unwrap();
+
+    assert_eq!(body.status, ReadinessStatus::Ready);
+    assert_eq!(checks.redis.status, DependencyStatus::Up);
+    assert_eq!(checks.github.status, DependencyStatus::Up);
+    assert!(!checks.github.cached);
+
+    // The Github probe is cached, so the mock server only receives one request
+    let body: ReadinessResponse = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.")
+        .json()
+        .await
+        .unwrap();
+
+    assert!(body.checks.unwrap().github.cached);
+}
+
+#[tokio::test]
+async fn test_readiness_is_failing_when_github_token_is_invalid() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    Mock::given(method("GET"))
+        .and(path("/rate_limit"))
+        .respond_with(
+            ResponseTemplate::new(401)
+                .set_body_json(serde_json::json!({ "message": "Bad credentials" })),
+        )
+        .mount(&app.github_server)
+        .await;
+
+    let res = reqwest::get(format!("{}/health/ready", base_url))
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 503);
+
+    let body: ReadinessResponse = res.json().await.unwrap();
+    let github = body.checks.unwrap().github;
+
+    assert_eq!(body.status, ReadinessStatus::Failing);
+    assert_eq!(github.status, DependencyStatus::Down);
+    assert!(github.error.unwrap().contains("Bad credentials"));
+}
+
+#[tokio::test]
+async fn test_readiness_returns_draining_while_shutting_down() {
+    let app = TestApp::new().await;
+    let base_url = app.spawn_app().await;
+
+    app.state.readiness.start_draining();
+
+    let res = reqwest::get(format!("{}/health/ready", base_url))
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(res.status(), 503);
+
+    let body: ReadinessResponse = res.json().await.unwrap();
+
+    assert_eq!(body.status, ReadinessStatus::Draining);
+    assert!(body.checks.is_none());
+}
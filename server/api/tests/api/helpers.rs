@@ -1,71 +1,76 @@
 use api::{
     app::App,
-    config::{get_app_settings, Settings},
+    config::{get_environment_settings, Settings},
+    github::tokens::GithubToken,
+    reload::SettingsHandle,
+    state::AppState,
 };
 use axum::Router;
+use axum_redis_cache::{
+    pool::RedisConnectionManager,
+    testing::{self, TestNamespace},
+};
 use bb8::{Pool, PooledConnection};
-use bb8_redis::RedisConnectionManager;
-use redis::JsonAsyncCommands;
+use std::sync::Arc;
 use wiremock::MockServer;
 
 pub struct TestApp {
-    pub settings: Settings,
     pub redis_pool: Pool<RedisConnectionManager>,
     pub github_server: MockServer,
     pub router: Router,
+    pub state: Arc<AppState>,
+    // Prefix of every key saved by the app, so the tests do not share keys. They are deleted when the app is dropped.
+    pub namespace: TestNamespace,
 }
 
 impl TestApp {
     pub async fn new() -> Self {
-        let mut settings = get_app_settings().expect("Unable to get server settings");
+        Self::with_settings(|_| {}).await
+    }
+
+    // Creates the app with the test settings, changed by the given function.
+    pub async fn with_settings(configure: impl FnOnce(&mut Settings)) -> Self {
+        let mut settings = get_environment_settings("test").expect("Unable to get server settings");
         let github_server = MockServer::start().await;
+        let namespace = TestNamespace::connect(settings.redis.url.clone()).await;
 
-        settings.application.set_port(0);
         settings.github.set_api_url(github_server.uri());
+        settings.redis.key_prefix = Some(namespace.prefix());
+        configure(&mut settings);
 
-        let app = App::new(settings.clone()).await.unwrap();
+        let app = App::new(SettingsHandle::new(settings, "test"))
+            .await
+            .unwrap();
 
         TestApp {
-            settings,
             redis_pool: app.state.redis_pool.clone(),
             github_server,
             router: app.router,
+            state: app.state,
+            namespace,
         }
     }
 
     pub async fn spawn_app(&self) -> String {
-        let address = self
-            .settings
-            .application
-            .get_addr()
-            .expect("Unable to get http address for running the tests");
-
-        let listener = tokio::net::TcpListener::bind(&address)
-            .await
-            .expect("Unable to create a tcp listener");
-
-        let base_url = listener.local_addr().unwrap();
-        let router = self.router.clone();
-
-        tokio::spawn(async move {
-            axum::serve(listener, router)
-                .await
-                .expect("Error running the test server");
-        });
-
-        format!("http://{}", base_url)
+        testing::spawn_app(self.router.clone()).await
     }
 
-    pub async fn redis_connection(&self) -> PooledConnection<RedisConnectionManager> {
+    pub async fn redis_connection(&self) -> PooledConnection<'_, RedisConnectionManager> {
         self.redis_pool
             .get()
             .await
             .expect("Unable to get redis connection")
     }
 
-    pub async fn redis_json_del(&self, key: String) {
-        let mut redis_connection = self.redis_connection().await;
+    // Returns the key of the backoff of the first Github token of the settings, for the given rate limit resource.
+    pub fn github_backoff_key(&self, resource: &str) -> String {
+        let token = GithubToken::new(self.state.settings.load().github.get_tokens().remove(0));
 
-        let _: () = redis_connection.json_del(key, "$").await.unwrap();
+        format!(
+            "{{{}:github:tokens}}:backoff:{}:{}",
+            self.namespace.prefix(),
+            resource,
+            token.id
+        )
     }
 }
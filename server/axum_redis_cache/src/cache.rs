@@ -0,0 +1,413 @@
+//! RedisCache is a handle to cache values from a handler, when caching the whole response with the RedisCacheLayer is not enough.
+//! For example, to cache just an expensive call made by the handler.
+//!
+//! The values are saved as RedisJSON documents, using the same codec as the RedisCacheLayer, so they can be encrypted at rest by
+//! providing a CacheCipher. The keys are namespaced with a prefix, which is `cache` by default, joined with a colon (:).
+//!
+//! The handle can be put into the state of the router. When the state implements `FromRef` for RedisCache, it can also be used
+//! directly as an extractor.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use axum::{extract::FromRef, Json};
+//! use axum_redis_cache::cache::RedisCache;
+//! use std::time::Duration;
+//! # use axum_redis_cache::pool::RedisConnectionManager;
+//! # use serde::{Deserialize, Serialize};
+//! #
+//! # type ApiError = axum_redis_cache::errors::RedisUtilsError;
+//! #
+//! # #[derive(Serialize, Deserialize)]
+//! # struct Stats {
+//! #     repositories: u64,
+//! # }
+//! #
+//! # async fn compute_repository_stats() -> Result<Stats, ApiError> {
+//! #     Ok(Stats { repositories: 0 })
+//! # }
+//!
+//! #[derive(Clone)]
+//! struct AppState {
+//!     cache: RedisCache,
+//! }
+//!
+//! impl FromRef<AppState> for RedisCache {
+//!     fn from_ref(state: &AppState) -> Self {
+//!         state.cache.clone()
+//!     }
+//! }
+//!
+//! async fn handler(cache: RedisCache) -> Result<Json<Stats>, ApiError> {
+//!     let stats = cache
+//!         .get_or_insert_with("stats:repositories", Duration::from_secs(600), || {
+//!             compute_repository_stats()
+//!         })
+//!         .await?;
+//!
+//!     Ok(Json(stats))
+//! }
+//!
+//! # let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+//! # let redis_pool = bb8::Pool::builder().build_unchecked(redis_manager);
+//! let state = AppState {
+//!     cache: RedisCache::new(redis_pool.clone()).with_prefix(String::from("api")),
+//! };
+//! ```
+//!
+//! `get_or_insert_with` fails open: when Redis is not available or the saved value cannot be decoded, the value is computed and
+//! returned anyway, so only the errors of the provided function are returned.
+//!
+//! The `Cached<T>` extractor reads the value cached for the request, under the Redis key of its uri (see ExtractRedisKey), so the
+//! handler only computes it when it is missing:
+//!
+//! ```rust,no_run
+//! # use axum::Json;
+//! # use axum_redis_cache::cache::{Cached, RedisCache};
+//! # use serde::{Deserialize, Serialize};
+//! # use std::time::Duration;
+//! #
+//! # type ApiError = axum_redis_cache::errors::RedisUtilsError;
+//! #
+//! # #[derive(Serialize, Deserialize)]
+//! # struct Stats {
+//! #     repositories: u64,
+//! # }
+//! #
+//! # async fn compute_repository_stats() -> Result<Stats, ApiError> {
+//! #     Ok(Stats { repositories: 0 })
+//! # }
+//! #
+//! async fn handler(cached: Cached<Stats>, cache: RedisCache) -> Result<Json<Stats>, ApiError> {
+//!     if let Some(stats) = cached.value {
+//!         return Ok(Json(stats));
+//!     }
+//!
+//!     let stats = compute_repository_stats().await?;
+//!
+//!     cache.set(&cached.key, &stats, Duration::from_secs(600)).await?;
+//!
+//!     Ok(Json(stats))
+//! }
+//! ```
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, StatusCode},
+};
+use bb8::Pool;
+use redis::{AsyncCommands, JsonAsyncCommands};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{convert::Infallible, future::Future, time::Duration};
+
+use super::{
+    codec::{CacheCipher, CacheCodec},
+    errors::RedisUtilsError,
+    extractors::{ExtractRedisKey, REDIS_KEY_DELIMITER},
+    pool::RedisConnectionManager,
+};
+
+#[cfg(feature = "test-util")]
+use super::testing::MemoryStore;
+
+const DEFAULT_CACHE_PREFIX: &str = "cache";
+const REDIS_PATH: &str = "$";
+
+#[derive(Clone, Debug)]
+pub struct RedisCache {
+    backend: CacheBackend,
+    prefix: String,
+    codec: CacheCodec,
+}
+
+#[derive(Clone, Debug)]
+enum CacheBackend {
+    Redis {
+        redis_pool: Pool<RedisConnectionManager>,
+        read_pool: Option<Pool<RedisConnectionManager>>,
+    },
+    #[cfg(feature = "test-util")]
+    Memory(MemoryStore),
+}
+
+impl RedisCache {
+    pub fn new(redis_pool: Pool<RedisConnectionManager>) -> Self {
+        RedisCache {
+            backend: CacheBackend::Redis {
+                redis_pool,
+                read_pool: None,
+            },
+            prefix: DEFAULT_CACHE_PREFIX.to_string(),
+            codec: CacheCodec::Json,
+        }
+    }
+
+    /// Creates a cache that keeps the values in memory instead of Redis, following the same semantics (key namespacing,
+    /// codecs and expiration), so the handlers using it can be tested without a Redis server.
+    #[cfg(feature = "test-util")]
+    pub fn in_memory() -> Self {
+        RedisCache {
+            backend: CacheBackend::Memory(MemoryStore::default()),
+            prefix: DEFAULT_CACHE_PREFIX.to_string(),
+            codec: CacheCodec::Json,
+        }
+    }
+
+    pub fn with_prefix(self, prefix: String) -> Self {
+        RedisCache { prefix, ..self }
+    }
+
+    /// Reads the values from this pool (usually connected to the replicas), while the writes still use the main pool.
+    pub fn with_read_pool(mut self, read_pool: Pool<RedisConnectionManager>) -> Self {
+        if let CacheBackend::Redis {
+            read_pool: backend_read_pool,
+            ..
+        } = &mut self.backend
+        {
+            *backend_read_pool = Some(read_pool);
+        }
+
+        self
+    }
+
+    pub fn with_encryption(self, cipher: CacheCipher) -> Self {
+        RedisCache {
+            codec: CacheCodec::Encrypted(cipher),
+            ..self
+        }
+    }
+
+    /// Returns the value saved under the key, or None when the key does not exist.
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, RedisUtilsError> {
+        let redis_key = self.redis_key(key);
+
+        match &self.backend {
+            CacheBackend::Redis {
+                redis_pool,
+                read_pool,
+            } => {
+                let mut redis_conn = read_pool
+                    .as_ref()
+                    .unwrap_or(redis_pool)
+                    .get()
+                    .await
+                    .map_err(RedisUtilsError::RedisConnection)?;
+
+                let raw_json: Option<String> = redis_conn
+                    .json_get(&redis_key, REDIS_PATH)
+                    .await
+                    .map_err(RedisUtilsError::Redis)?;
+
+                raw_json
+                    .map(|raw_json| {
+                        self.codec
+                            .decode_redis_json(&redis_key, REDIS_PATH, &raw_json)
+                    })
+                    .transpose()
+            }
+            #[cfg(feature = "test-util")]
+            CacheBackend::Memory(store) => store
+                .get(&redis_key)
+                .map(|document| self.codec.decode(&redis_key, document))
+                .transpose(),
+        }
+    }
+
+    /// Saves the value under the key, replacing the previous one. The value expires after the ttl.
+    pub async fn set<T: Serialize>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<(), RedisUtilsError> {
+        let redis_key = self.redis_key(key);
+        let document = self.codec.encode(&redis_key, value)?;
+
+        match &self.backend {
+            CacheBackend::Redis { redis_pool, .. } => {
+                let mut redis_conn = redis_pool
+                    .get()
+                    .await
+                    .map_err(RedisUtilsError::RedisConnection)?;
+
+                redis::pipe()
+                    .atomic()
+                    .json_set(&redis_key, REDIS_PATH, &document)
+                    .map_err(RedisUtilsError::Redis)?
+                    .ignore()
+                    .pexpire(&redis_key, ttl.as_millis() as i64)
+                    .ignore()
+                    .query_async::<()>(&mut *redis_conn)
+                    .await
+                    .map_err(RedisUtilsError::Redis)
+            }
+            #[cfg(feature = "test-util")]
+            CacheBackend::Memory(store) => {
+                store.set(redis_key, document, ttl);
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Deletes the value saved under the key. Returns false when the key did not exist.
+    pub async fn delete(&self, key: &str) -> Result<bool, RedisUtilsError> {
+        let redis_key = self.redis_key(key);
+
+        match &self.backend {
+            CacheBackend::Redis { redis_pool, .. } => {
+                let mut redis_conn = redis_pool
+                    .get()
+                    .await
+                    .map_err(RedisUtilsError::RedisConnection)?;
+
+                let deleted: u64 = redis_conn
+                    .del(redis_key)
+                    .await
+                    .map_err(RedisUtilsError::Redis)?;
+
+                Ok(deleted > 0)
+            }
+            #[cfg(feature = "test-util")]
+            CacheBackend::Memory(store) => Ok(store.delete(&redis_key)),
+        }
+    }
+
+    /// Returns the time left before the value saved under the key expires, or None when the key does not exist or does
+    /// not expire.
+    pub async fn ttl(&self, key: &str) -> Result<Option<Duration>, RedisUtilsError> {
+        let redis_key = self.redis_key(key);
+
+        match &self.backend {
+            CacheBackend::Redis { redis_pool, .. } => {
+                let mut redis_conn = redis_pool
+                    .get()
+                    .await
+                    .map_err(RedisUtilsError::RedisConnection)?;
+
+                // PTTL returns a negative value when the key does not exist or does not expire
+                let ttl: i64 = redis_conn
+                    .pttl(redis_key)
+                    .await
+                    .map_err(RedisUtilsError::Redis)?;
+
+                Ok(u64::try_from(ttl).ok().map(Duration::from_millis))
+            }
+            #[cfg(feature = "test-util")]
+            CacheBackend::Memory(store) => Ok(store.ttl(&redis_key)),
+        }
+    }
+
+    /// Returns the value saved under the key. When there is no value, it is computed with the provided function and saved
+    /// with the ttl. Errors returned by the function are not cached.
+    pub async fn get_or_insert_with<T, E, F, Fut>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        f: F,
+    ) -> Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        match self.get(key).await {
+            Ok(Some(value)) => return Ok(value),
+            Ok(None) => {}
+            Err(err) => {
+                tracing::warn!(
+                    redis_key = self.redis_key(key),
+                    error = %err,
+                    "Unable to read cached value, computing it"
+                );
+            }
+        }
+
+        let value = f().await?;
+
+        if let Err(err) = self.set(key, &value, ttl).await {
+            tracing::error!(
+                redis_key = self.redis_key(key),
+                error = %err,
+                "Unable to save cached value"
+            );
+        }
+
+        Ok(value)
+    }
+
+    fn redis_key(&self, key: &str) -> String {
+        [self.prefix.as_str(), key].join(REDIS_KEY_DELIMITER)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RedisCache
+where
+    RedisCache: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(_: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(RedisCache::from_ref(state))
+    }
+}
+
+/// Extracts the value cached for the request, using its Redis key as the key of the RedisCache of the state. The value is None
+/// when it is not cached, or when it cannot be read, as the reads fail open like `get_or_insert_with`.
+#[derive(Debug)]
+pub struct Cached<T> {
+    pub key: String,
+    pub value: Option<T>,
+}
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for Cached<T>
+where
+    RedisCache: FromRef<S>,
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ExtractRedisKey(key) = ExtractRedisKey::from_request_parts(parts, state).await?;
+        let cache = RedisCache::from_ref(state);
+
+        let value = match cache.get(&key).await {
+            Ok(value) => value,
+            Err(err) => {
+                tracing::warn!(
+                    redis_key = cache.redis_key(&key),
+                    error = %err,
+                    "Unable to read cached value"
+                );
+
+                None
+            }
+        };
+
+        Ok(Cached { key, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_keys_are_namespaced() {
+        let manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+        let redis_pool = Pool::builder().build_unchecked(manager);
+
+        let cache = RedisCache::new(redis_pool.clone());
+        let prefixed_cache = RedisCache::new(redis_pool).with_prefix(String::from("github"));
+
+        assert_eq!(cache.redis_key("stats"), "cache:stats");
+        assert_eq!(
+            prefixed_cache.redis_key("repositories:stats"),
+            "github:repositories:stats"
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Captures the body of the responses that are saved on Redis, like the ones cached by the RedisCacheLayer or the ones stored
+//! by the IdempotencyLayer.
+//!
+//! The body is only buffered when its length is known and it fits within the max body size. Otherwise, it is returned untouched,
+//! so it can be streamed to the client as it comes from the handler.
+use axum::body::{Body, Bytes, HttpBody};
+use http_body_util::BodyExt;
+
+pub(crate) enum CapturedBody {
+    // The body was buffered in memory
+    Buffered(Bytes),
+    // The body could not be buffered, so it needs to be streamed without saving it on Redis
+    Streamed(Body),
+}
+
+pub(crate) async fn capture_body(
+    body: Body,
+    max_body_size: Option<usize>,
+    redis_key: &str,
+) -> Result<CapturedBody, axum::Error> {
+    match body.size_hint().exact() {
+        Some(size) if exceeds_max_body_size(size, max_body_size) => {
+            tracing::debug!(
+                redis_key,
+                body_size = size,
+                max_body_size,
+                "Response body exceeds the max size, streaming it without saving it on Redis"
+            );
+
+            return Ok(CapturedBody::Streamed(body));
+        }
+        Some(size) => {
+            tracing::debug!(redis_key, body_size = size, "Saving response body on Redis");
+        }
+        None => {
+            tracing::debug!(
+                redis_key,
+                "Response body does not have a known length, streaming it without saving it on Redis"
+            );
+
+            return Ok(CapturedBody::Streamed(body));
+        }
+    }
+
+    let collected = body.collect().await?;
+
+    Ok(CapturedBody::Buffered(collected.to_bytes()))
+}
+
+fn exceeds_max_body_size(size: u64, max_body_size: Option<usize>) -> bool {
+    match max_body_size {
+        Some(max_body_size) => size > max_body_size as u64,
+        None => false,
+    }
+}
@@ -0,0 +1,392 @@
+//! CacheCodec converts the values saved on Redis from and to JSON. It is used by the RedisCacheLayer to store and load cached responses.
+//!
+//! By default, values are saved as plain JSON documents. When a CacheCipher is provided, the JSON document is encrypted with AES-256-GCM
+//! and saved within an envelope that contains the id of the key used to encrypt it:
+//!
+//! ```json
+//! {
+//!     "kid": "2024-06",
+//!     "nonce": "<base64>",
+//!     "ciphertext": "<base64>"
+//! }
+//! ```
+//!
+//! The Redis key of the entry is used as additional authenticated data, so an encrypted value cannot be moved to a different key.
+//!
+//! Keys can be rotated by creating a new CacheCipher with the new key as the active one, and registering the previous keys
+//! with `with_decryption_key`. Entries encrypted with the previous keys can still be read until they expire, while new entries
+//! are always encrypted with the active key.
+//!
+//! ```rust
+//! use axum_redis_cache::codec::CacheCipher;
+//!
+//! let cipher = CacheCipher::new("2024-06", &[1; 32])
+//!     .unwrap()
+//!     .with_decryption_key("2024-01", &[0; 32])
+//!     .unwrap();
+//! ```
+use aes_gcm::{
+    aead::{Aead, AeadCore, OsRng, Payload},
+    Aes256Gcm, Key, KeyInit, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+use super::errors::RedisUtilsError;
+
+const ENCRYPTION_KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Clone)]
+pub struct CacheCipher {
+    active_key_id: String,
+    keys: Arc<HashMap<String, Aes256Gcm>>,
+}
+
+impl CacheCipher {
+    /// Creates a cipher that encrypts new entries with the given key. The key must be 32 bytes long.
+    pub fn new(key_id: impl Into<String>, key: &[u8]) -> Result<Self, RedisUtilsError> {
+        let key_id = key_id.into();
+        let mut keys = HashMap::new();
+
+        keys.insert(key_id.clone(), build_aes_cipher(&key_id, key)?);
+
+        Ok(CacheCipher {
+            active_key_id: key_id,
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// Creates a cipher from a base64 encoded key, which is the format used to provide keys from configuration.
+    pub fn from_base64(key_id: impl Into<String>, key: &str) -> Result<Self, RedisUtilsError> {
+        let key_id = key_id.into();
+        let key = decode_base64_key(&key_id, key)?;
+
+        CacheCipher::new(key_id, &key)
+    }
+
+    /// Registers a key that is only used to decrypt existing entries. This is useful while rotating keys.
+    /// Fails when the cipher already has a key with the same id.
+    pub fn with_decryption_key(
+        self,
+        key_id: impl Into<String>,
+        key: &[u8],
+    ) -> Result<Self, RedisUtilsError> {
+        let key_id = key_id.into();
+
+        if self.keys.contains_key(&key_id) {
+            return Err(RedisUtilsError::DuplicateEncryptionKey(key_id));
+        }
+
+        let mut keys = (*self.keys).clone();
+
+        keys.insert(key_id.clone(), build_aes_cipher(&key_id, key)?);
+
+        Ok(CacheCipher {
+            keys: Arc::new(keys),
+            ..self
+        })
+    }
+
+    /// Same as `with_decryption_key`, but the key is base64 encoded.
+    pub fn with_base64_decryption_key(
+        self,
+        key_id: impl Into<String>,
+        key: &str,
+    ) -> Result<Self, RedisUtilsError> {
+        let key_id = key_id.into();
+        let key = decode_base64_key(&key_id, key)?;
+
+        self.with_decryption_key(key_id, &key)
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key_id
+    }
+
+    fn encrypt(
+        &self,
+        redis_key: &str,
+        plaintext: &[u8],
+    ) -> Result<EncryptedEntry, RedisUtilsError> {
+        let cipher = &self.keys[&self.active_key_id];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: redis_key.as_bytes(),
+                },
+            )
+            .map_err(|_| RedisUtilsError::Encryption(self.active_key_id.clone()))?;
+
+        Ok(EncryptedEntry {
+            kid: self.active_key_id.clone(),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    fn decrypt(&self, redis_key: &str, entry: &EncryptedEntry) -> Result<Vec<u8>, RedisUtilsError> {
+        let decryption_error = || RedisUtilsError::Decryption(entry.kid.clone());
+
+        let cipher = self.keys.get(&entry.kid).ok_or_else(decryption_error)?;
+        let nonce = BASE64
+            .decode(&entry.nonce)
+            .map_err(|_| decryption_error())?;
+        let ciphertext = BASE64
+            .decode(&entry.ciphertext)
+            .map_err(|_| decryption_error())?;
+
+        if nonce.len() != NONCE_LENGTH {
+            return Err(decryption_error());
+        }
+
+        cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: redis_key.as_bytes(),
+                },
+            )
+            .map_err(|_| decryption_error())
+    }
+}
+
+// The keys are never printed, just the key ids.
+impl Debug for CacheCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CacheCipher")
+            .field("active_key_id", &self.active_key_id)
+            .field("key_ids", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+// The envelope saved on Redis when the encryption is enabled.
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedEntry {
+    kid: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum CacheCodec {
+    #[default]
+    Json,
+    Encrypted(CacheCipher),
+}
+
+impl CacheCodec {
+    pub fn new(cipher: Option<CacheCipher>) -> Self {
+        match cipher {
+            Some(cipher) => CacheCodec::Encrypted(cipher),
+            None => CacheCodec::Json,
+        }
+    }
+
+    /// Converts a value into the JSON document that is saved on Redis under the given key.
+    pub fn encode<T: Serialize>(
+        &self,
+        redis_key: &str,
+        value: &T,
+    ) -> Result<serde_json::Value, RedisUtilsError> {
+        match self {
+            CacheCodec::Json => serde_json::to_value(value).map_err(RedisUtilsError::Serialization),
+            CacheCodec::Encrypted(cipher) => {
+                let plaintext =
+                    serde_json::to_vec(value).map_err(RedisUtilsError::Serialization)?;
+                let entry = cipher.encrypt(redis_key, &plaintext)?;
+
+                serde_json::to_value(entry).map_err(RedisUtilsError::Serialization)
+            }
+        }
+    }
+
+    /// Converts the JSON document saved on Redis under the given key back into a value.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        redis_key: &str,
+        document: serde_json::Value,
+    ) -> Result<T, RedisUtilsError> {
+        match self {
+            CacheCodec::Json => {
+                serde_json::from_value(document).map_err(RedisUtilsError::Serialization)
+            }
+            CacheCodec::Encrypted(cipher) => {
+                let entry: EncryptedEntry =
+                    serde_json::from_value(document).map_err(RedisUtilsError::Serialization)?;
+                let plaintext = cipher.decrypt(redis_key, &entry)?;
+
+                serde_json::from_slice(&plaintext).map_err(RedisUtilsError::Serialization)
+            }
+        }
+    }
+
+    /// Decodes the raw string returned by JSON.GET. When the path is a JSONPath (it starts with '$'), Redis returns an array
+    /// with all the matches, so just the first one is used.
+    pub fn decode_redis_json<T: DeserializeOwned>(
+        &self,
+        redis_key: &str,
+        redis_path: &str,
+        raw: &str,
+    ) -> Result<T, RedisUtilsError> {
+        let document: serde_json::Value =
+            serde_json::from_str(raw).map_err(RedisUtilsError::Serialization)?;
+
+        let document = match document {
+            serde_json::Value::Array(mut matches) if redis_path.starts_with('$') => {
+                if matches.is_empty() {
+                    return Err(RedisUtilsError::MissingValue(redis_key.to_string()));
+                }
+
+                matches.swap_remove(0)
+            }
+            document => document,
+        };
+
+        self.decode(redis_key, document)
+    }
+}
+
+fn build_aes_cipher(key_id: &str, key: &[u8]) -> Result<Aes256Gcm, RedisUtilsError> {
+    if key.len() != ENCRYPTION_KEY_LENGTH {
+        return Err(RedisUtilsError::InvalidEncryptionKey(key_id.to_string()));
+    }
+
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)))
+}
+
+fn decode_base64_key(key_id: &str, key: &str) -> Result<Vec<u8>, RedisUtilsError> {
+    BASE64
+        .decode(key.trim())
+        .map_err(|_| RedisUtilsError::InvalidEncryptionKey(key_id.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestValue {
+        message: String,
+    }
+
+    fn test_value() -> TestValue {
+        TestValue {
+            message: String::from("Test value"),
+        }
+    }
+
+    #[test]
+    fn test_json_codec_roundtrip() {
+        let codec = CacheCodec::Json;
+
+        let document = codec.encode("key", &test_value()).unwrap();
+        let value: TestValue = codec.decode("key", document.clone()).unwrap();
+
+        assert_eq!(document, serde_json::json!({ "message": "Test value" }));
+        assert_eq!(value, test_value());
+    }
+
+    #[test]
+    fn test_encrypted_codec_roundtrip() {
+        let codec = CacheCodec::new(Some(CacheCipher::new("current", &[1; 32]).unwrap()));
+
+        let document = codec.encode("key", &test_value()).unwrap();
+        let value: TestValue = codec.decode("key", document.clone()).unwrap();
+
+        assert_eq!(document["kid"], "current");
+        assert!(!document.to_string().contains("Test value"));
+        assert_eq!(value, test_value());
+    }
+
+    #[test]
+    fn test_encrypted_codec_decodes_entries_from_rotated_keys() {
+        let previous_codec = CacheCodec::new(Some(CacheCipher::new("previous", &[0; 32]).unwrap()));
+        let codec = CacheCodec::new(Some(
+            CacheCipher::new("current", &[1; 32])
+                .unwrap()
+                .with_decryption_key("previous", &[0; 32])
+                .unwrap(),
+        ));
+
+        let document = previous_codec.encode("key", &test_value()).unwrap();
+        let value: TestValue = codec.decode("key", document).unwrap();
+
+        assert_eq!(value, test_value());
+        assert_eq!(
+            codec.encode("key", &test_value()).unwrap()["kid"],
+            "current"
+        );
+    }
+
+    #[test]
+    fn test_encrypted_codec_fails_with_unknown_key_id() {
+        let previous_codec = CacheCodec::new(Some(CacheCipher::new("previous", &[0; 32]).unwrap()));
+        let codec = CacheCodec::new(Some(CacheCipher::new("current", &[1; 32]).unwrap()));
+
+        let document = previous_codec.encode("key", &test_value()).unwrap();
+        let result = codec.decode::<TestValue>("key", document);
+
+        assert!(matches!(result, Err(RedisUtilsError::Decryption(kid)) if kid == "previous"));
+    }
+
+    #[test]
+    fn test_encrypted_codec_fails_when_entry_is_moved_to_another_key() {
+        let codec = CacheCodec::new(Some(CacheCipher::new("current", &[1; 32]).unwrap()));
+
+        let document = codec.encode("key", &test_value()).unwrap();
+        let result = codec.decode::<TestValue>("another-key", document);
+
+        assert!(matches!(result, Err(RedisUtilsError::Decryption(_))));
+    }
+
+    #[test]
+    fn test_encrypted_codec_fails_with_plain_entries() {
+        let codec = CacheCodec::new(Some(CacheCipher::new("current", &[1; 32]).unwrap()));
+
+        let document = CacheCodec::Json.encode("key", &test_value()).unwrap();
+        let result = codec.decode::<TestValue>("key", document);
+
+        assert!(matches!(result, Err(RedisUtilsError::Serialization(_))));
+    }
+
+    #[test]
+    fn test_cipher_rejects_keys_with_invalid_length() {
+        let result = CacheCipher::new("current", &[1; 16]);
+
+        assert!(
+            matches!(result, Err(RedisUtilsError::InvalidEncryptionKey(kid)) if kid == "current")
+        );
+    }
+
+    #[test]
+    fn test_cipher_rejects_duplicate_key_ids() {
+        let result = CacheCipher::new("current", &[1; 32])
+            .unwrap()
+            .with_decryption_key("previous", &[0; 32])
+            .unwrap()
+            .with_decryption_key("current", &[2; 32]);
+
+        assert!(
+            matches!(result, Err(RedisUtilsError::DuplicateEncryptionKey(kid)) if kid == "current")
+        );
+    }
+
+    #[test]
+    fn test_decode_redis_json_uses_first_jsonpath_match() {
+        let codec = CacheCodec::Json;
+
+        let value: TestValue = codec
+            .decode_redis_json("key", "$", r#"[{"message":"Test value"}]"#)
+            .unwrap();
+
+        assert_eq!(value, test_value());
+    }
+}
@@ -8,6 +8,23 @@ use redis::RedisError;
 pub enum RedisUtilsError {
     Redis(RedisError),
     RedisConnection(bb8::RunError<redis::RedisError>),
+    Serialization(serde_json::Error),
+    // The id of the key that failed to encrypt the value
+    Encryption(String),
+    // The id of the key the entry was encrypted with
+    Decryption(String),
+    // The id of the key that does not have a valid format
+    InvalidEncryptionKey(String),
+    // The id of the key that was registered more than once
+    DuplicateEncryptionKey(String),
+    // The Redis key that does not contain any value
+    MissingValue(String),
+    // The Redis key used by the Lua script that returned an unexpected result
+    UnexpectedScriptResult(String),
+    // The reason why the options of a rate limit are not valid
+    InvalidRateLimit(String),
+    // The lease time of a lock or a semaphore, which is shorter than a millisecond
+    InvalidLeaseTime(std::time::Duration),
 }
 
 impl std::fmt::Display for RedisUtilsError {
@@ -24,10 +41,43 @@ impl std::fmt::Display for RedisUtilsError {
 
                 write!(f, "{}", error_msg)
             }
+            RedisUtilsError::Serialization(err) => {
+                write!(f, "Serialization error: {}", err)
+            }
+            RedisUtilsError::Encryption(key_id) => {
+                write!(f, "Unable to encrypt value with key {}", key_id)
+            }
+            RedisUtilsError::Decryption(key_id) => {
+                write!(f, "Unable to decrypt value with key {}", key_id)
+            }
+            RedisUtilsError::InvalidEncryptionKey(key_id) => {
+                write!(f, "Invalid encryption key {}", key_id)
+            }
+            RedisUtilsError::DuplicateEncryptionKey(key_id) => {
+                write!(f, "Duplicate encryption key id {}", key_id)
+            }
+            RedisUtilsError::MissingValue(key) => {
+                write!(f, "Missing value on Redis key {}", key)
+            }
+            RedisUtilsError::UnexpectedScriptResult(key) => {
+                write!(f, "Unexpected Lua script result for Redis key {}", key)
+            }
+            RedisUtilsError::InvalidRateLimit(reason) => {
+                write!(f, "Invalid rate limit: {}", reason)
+            }
+            RedisUtilsError::InvalidLeaseTime(lease_time) => {
+                write!(
+                    f,
+                    "Invalid lease time {:?}: it must be at least 1ms",
+                    lease_time
+                )
+            }
         }
     }
 }
 
+impl std::error::Error for RedisUtilsError {}
+
 impl IntoResponse for RedisUtilsError {
     fn into_response(self) -> Response {
         let err_message = self.to_string();
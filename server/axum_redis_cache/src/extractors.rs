@@ -1,6 +1,6 @@
 //! ExtractRedisKey is an Axum extractor that extracts a Redis key from the request path and query parameters. This extractor is used within the RedisCacheLayer to generate a key for the cache.
 //!
-//! The key is constructed by concatenating the path and query parameters. Query parameters are sorted alphabetically in order to ensure the same query parameters result in the same key,
+//! The key is constructed by concatenating the path segments and query parameters. Query parameters are sorted alphabetically in order to ensure the same query parameters result in the same key,
 //! independently of the order they were provided in the request.
 //!
 //! It separates the path segments and query parameters by a colon (:).
@@ -13,18 +13,221 @@
 //!
 //! api:v1:users:age=30:name=John
 //!
+//! The path segments and query parameters are percent-decoded (and `+` is decoded as a space in the query) before building the key, so
+//! `?q=a%20b` and `?q=a+b` share the same key. Empty path segments and query parameters are skipped. Then, the `%`, `:` and `=`
+//! characters are percent-encoded again, so a segment or a parameter can never be confused with the delimiters of the key.
+//!
 //! When the request does contain nor path neither query parameters, it returns a 400 Bad Request error as the key would be empty.
 //!
+//! By default, the values of a repeated query parameter are all kept in the order they were provided. The key generation can be tuned with
+//! KeyNormalization, which can be provided to the RedisCacheLayer with `with_key_normalization` or added as a request extension:
+//!
+//! ```rust,no_run
+//! use axum_redis_cache::extractors::{DuplicateParams, KeyNormalization, ParamNormalization};
+//! # use axum_redis_cache::{middlewares::RedisCacheLayerBuilder, pool::RedisConnectionManager};
+//! #
+//! # type UsersResponse = serde_json::Value;
+//! #
+//! # let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+//! # let redis_pool = bb8::Pool::builder().build_unchecked(redis_manager);
+//!
+//! let key_normalization = KeyNormalization::new()
+//!     .with_duplicate_params(DuplicateParams::Last)
+//!     .with_param("page", ParamNormalization::Integer)
+//!     .with_param("utm_source", ParamNormalization::Ignore);
+//!
+//! let layer = RedisCacheLayerBuilder::new(redis_pool.clone())
+//!     .with_key_normalization(key_normalization)
+//!     .build::<UsersResponse>();
+//! ```
+//!
+//! With the previous configuration, `?page=01&utm_source=mail` and `?page=1` share the same key.
+//!
 use axum::{
     async_trait,
-    extract::{FromRequestParts, OriginalUri},
-    http::{request::Parts, StatusCode},
+    extract::{FromRequestParts, MatchedPath, OriginalUri, RawPathParams},
+    http::{request::Parts, StatusCode, Uri},
     RequestPartsExt,
 };
+use itertools::Itertools;
+use percent_encoding::percent_decode_str;
+use std::collections::HashMap;
+
+use super::namespace::{namespace_key, route_namespace, RouteNamespace};
+
+pub(crate) const REDIS_KEY_DELIMITER: &str = ":";
+
+/// How the values of a query parameter provided more than once are used to build the key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateParams {
+    /// Keeps every value, in the order they were provided.
+    #[default]
+    KeepAll,
+    /// Keeps just the first value.
+    First,
+    /// Keeps just the last value.
+    Last,
+}
+
+/// How the value of a query parameter is normalized before building the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamNormalization {
+    /// Parses the value as an integer, so `01` and `1` are the same value. Values that are not integers are kept as they are.
+    Integer,
+    /// Parses the value as a boolean, accepting true/false, 1/0, yes/no and on/off. Other values are kept as they are.
+    Boolean,
+    /// Ignores the case of the value.
+    CaseInsensitive,
+    /// Removes the parameter from the key, for parameters that do not change the response.
+    Ignore,
+}
+
+impl ParamNormalization {
+    fn normalize(&self, value: &str) -> Option<String> {
+        match self {
+            ParamNormalization::Integer => Some(
+                value
+                    .trim()
+                    .parse::<i64>()
+                    .map(|value| value.to_string())
+                    .unwrap_or_else(|_| value.to_string()),
+            ),
+            ParamNormalization::Boolean => {
+                let normalized = match value.trim().to_lowercase().as_str() {
+                    "true" | "1" | "yes" | "on" => "true",
+                    "false" | "0" | "no" | "off" => "false",
+                    _ => value,
+                };
+
+                Some(normalized.to_string())
+            }
+            ParamNormalization::CaseInsensitive => Some(value.to_lowercase()),
+            ParamNormalization::Ignore => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct KeyNormalization {
+    duplicate_params: DuplicateParams,
+    params: HashMap<String, ParamNormalization>,
+}
 
-use itertools::{sorted, Itertools};
+impl KeyNormalization {
+    pub fn new() -> Self {
+        KeyNormalization::default()
+    }
+
+    pub fn with_duplicate_params(self, duplicate_params: DuplicateParams) -> Self {
+        KeyNormalization {
+            duplicate_params,
+            ..self
+        }
+    }
 
-const REDIS_KEY_DELIMITER: &str = ":";
+    pub fn with_param(
+        mut self,
+        name: impl Into<String>,
+        normalization: ParamNormalization,
+    ) -> Self {
+        self.params.insert(name.into(), normalization);
+
+        self
+    }
+
+    /// Builds the Redis key of the uri. Returns None when the key would be empty.
+    pub fn redis_key(&self, uri: &Uri) -> Option<String> {
+        let path_segments = uri
+            .path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| escape_key_part(&percent_decode_str(segment).decode_utf8_lossy()));
+
+        let query_params = self.formatted_query_params(uri.query().unwrap_or(""));
+
+        let redis_key = path_segments.chain(query_params).join(REDIS_KEY_DELIMITER);
+
+        if redis_key.is_empty() {
+            return None;
+        }
+
+        Some(redis_key)
+    }
+
+    /// Builds the Redis key of a request to a route of a namespace, using the values of the path parameters instead of the whole path.
+    /// The values must be percent-decoded, like the ones of axum's RawPathParams, so they are keyed like the segments of `redis_key`.
+    pub fn route_key<'a>(
+        &self,
+        namespace: &str,
+        path_params: impl Iterator<Item = &'a str>,
+        query: Option<&str>,
+    ) -> String {
+        let path_params = path_params.map(escape_key_part);
+        let query_params = self.formatted_query_params(query.unwrap_or(""));
+
+        std::iter::once(namespace_key(namespace))
+            .chain(path_params)
+            .chain(query_params)
+            .join(REDIS_KEY_DELIMITER)
+    }
+
+    fn formatted_query_params(&self, query: &str) -> impl Iterator<Item = String> {
+        self.query_params(query)
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", escape_key_part(&name), escape_key_part(&value)))
+    }
+
+    // Decodes and normalizes the query parameters, sorted by name. The values of a repeated parameter keep their relative order.
+    fn query_params(&self, query: &str) -> Vec<(String, String)> {
+        let mut params: Vec<(String, String)> = Vec::new();
+
+        for (name, value) in form_urlencoded::parse(query.as_bytes()) {
+            let value = match self.params.get(name.as_ref()) {
+                Some(normalization) => normalization.normalize(&value),
+                None => Some(value.into_owned()),
+            };
+
+            let Some(value) = value else {
+                continue;
+            };
+
+            match self.duplicate_params {
+                DuplicateParams::KeepAll => params.push((name.into_owned(), value)),
+                DuplicateParams::First => {
+                    if !params.iter().any(|(param_name, _)| *param_name == name) {
+                        params.push((name.into_owned(), value));
+                    }
+                }
+                DuplicateParams::Last => {
+                    params.retain(|(param_name, _)| *param_name != name);
+                    params.push((name.into_owned(), value));
+                }
+            }
+        }
+
+        params.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        params
+    }
+}
+
+// Escapes the characters used as delimiters of the key, and the escape character itself.
+pub(crate) fn escape_key_part(part: &str) -> String {
+    part.replace('%', "%25")
+        .replace(':', "%3A")
+        .replace('=', "%3D")
+}
+
+/// Request extension with a prefix for the Redis keys built by the extractors, so the keys of different applications (or
+/// tests) sharing a Redis server do not collide. With a route namespace, the prefix is part of the namespace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyPrefix(pub String);
+
+impl KeyPrefix {
+    fn apply(&self, key: &str) -> String {
+        [self.0.as_str(), key].join(REDIS_KEY_DELIMITER)
+    }
+}
 
 pub struct ExtractRedisKey(pub String);
 
@@ -36,30 +239,70 @@ where
     type Rejection = (StatusCode, &'static str);
 
     async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
-        let original_uri = parts.extract::<OriginalUri>().await.unwrap();
+        let OriginalUri(original_uri) = parts.extract::<OriginalUri>().await.unwrap();
 
-        let formatted_path = original_uri
-            .path()
-            .to_string()
-            .replace("/", REDIS_KEY_DELIMITER);
-        let query_params = original_uri.query().unwrap_or("");
-        let sorted_params = sorted(query_params.split("&")).join(REDIS_KEY_DELIMITER);
+        let redis_key = match parts.extensions.get::<KeyNormalization>() {
+            Some(key_normalization) => key_normalization.redis_key(&original_uri),
+            None => KeyNormalization::default().redis_key(&original_uri),
+        };
 
-        let mut redis_key = [formatted_path, sorted_params].join(REDIS_KEY_DELIMITER);
+        let redis_key = match parts.extensions.get::<KeyPrefix>() {
+            Some(key_prefix) => redis_key.map(|redis_key| key_prefix.apply(&redis_key)),
+            None => redis_key,
+        };
 
-        if redis_key.starts_with(REDIS_KEY_DELIMITER) {
-            redis_key.remove(0);
+        match redis_key {
+            Some(redis_key) => Ok(ExtractRedisKey(redis_key)),
+            None => Err((StatusCode::BAD_REQUEST, "Invalid key")),
         }
+    }
+}
 
-        if redis_key.ends_with(REDIS_KEY_DELIMITER) {
-            redis_key.pop();
-        }
+/// Extracts the Redis key of a request using the namespace of its route (see the namespace module). The namespace is taken from the
+/// RouteNamespace request extension, and derived from the route template when there is no extension.
+pub struct ExtractRouteKey {
+    pub namespace: String,
+    pub key: String,
+}
 
-        if redis_key.is_empty() {
-            return Err((StatusCode::BAD_REQUEST, "Invalid key"));
-        }
+#[async_trait]
+impl<S> FromRequestParts<S> for ExtractRouteKey
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
 
-        Ok(ExtractRedisKey(redis_key))
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let matched_path = parts
+            .extract::<MatchedPath>()
+            .await
+            .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid key"))?;
+        let path_params = parts
+            .extract::<RawPathParams>()
+            .await
+            .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid key"))?;
+        let OriginalUri(original_uri) = parts.extract::<OriginalUri>().await.unwrap();
+
+        let namespace = match parts.extensions.get::<RouteNamespace>() {
+            Some(RouteNamespace::Named(name)) => name.clone(),
+            _ => route_namespace(matched_path.as_str()),
+        };
+        let namespace = match parts.extensions.get::<KeyPrefix>() {
+            Some(key_prefix) => key_prefix.apply(&namespace),
+            None => namespace,
+        };
+
+        let path_params = path_params.iter().map(|(_, value)| value);
+        let key = match parts.extensions.get::<KeyNormalization>() {
+            Some(key_normalization) => {
+                key_normalization.route_key(&namespace, path_params, original_uri.query())
+            }
+            None => {
+                KeyNormalization::default().route_key(&namespace, path_params, original_uri.query())
+            }
+        };
+
+        Ok(ExtractRouteKey { namespace, key })
     }
 }
 
@@ -181,4 +424,177 @@ mod tests {
         assert_eq!(status, StatusCode::OK);
         assert_eq!(body, "api:v1:test:age=30:name=John");
     }
+
+    fn redis_key(uri: &str) -> Option<String> {
+        KeyNormalization::default().redis_key(&uri.parse().unwrap())
+    }
+
+    #[test]
+    fn test_query_is_percent_decoded() {
+        assert_eq!(redis_key("/search?q=a%20b"), redis_key("/search?q=a+b"));
+        assert_eq!(redis_key("/search?q=a%20b").unwrap(), "search:q=a b");
+    }
+
+    #[test]
+    fn test_empty_query_params_are_skipped() {
+        assert_eq!(redis_key("/test?x=1&").unwrap(), "test:x=1");
+        assert_eq!(redis_key("/test?&x=1").unwrap(), "test:x=1");
+        assert_eq!(redis_key("/test/?").unwrap(), "test");
+    }
+
+    #[test]
+    fn test_delimiters_are_escaped() {
+        assert_eq!(
+            redis_key("/users/a:b?filter=x%3Dy").unwrap(),
+            "users:a%3Ab:filter=x%3Dy"
+        );
+        assert_ne!(redis_key("/test?a=b:c=d"), redis_key("/test?a=b&c=d"));
+        assert_eq!(redis_key("/users/a%3Ab"), redis_key("/users/a:b"));
+    }
+
+    #[test]
+    fn test_duplicate_params() {
+        let uri: Uri = "/test?tag=b&page=1&tag=a".parse().unwrap();
+
+        assert_eq!(
+            redis_key("/test?tag=b&page=1&tag=a").unwrap(),
+            "test:page=1:tag=b:tag=a"
+        );
+        assert_eq!(
+            KeyNormalization::new()
+                .with_duplicate_params(DuplicateParams::First)
+                .redis_key(&uri)
+                .unwrap(),
+            "test:page=1:tag=b"
+        );
+        assert_eq!(
+            KeyNormalization::new()
+                .with_duplicate_params(DuplicateParams::Last)
+                .redis_key(&uri)
+                .unwrap(),
+            "test:page=1:tag=a"
+        );
+    }
+
+    #[test]
+    fn test_typed_param_normalization() {
+        let key_normalization = KeyNormalization::new()
+            .with_param("page", ParamNormalization::Integer)
+            .with_param("open", ParamNormalization::Boolean)
+            .with_param("lang", ParamNormalization::CaseInsensitive)
+            .with_param("utm_source", ParamNormalization::Ignore);
+
+        let key = |uri: &str| key_normalization.redis_key(&uri.parse().unwrap());
+
+        assert_eq!(
+            key("/issues?page=01&open=yes&lang=Rust&utm_source=mail").unwrap(),
+            "issues:lang=rust:open=true:page=1"
+        );
+        assert_eq!(key("/issues?page=first").unwrap(), "issues:page=first");
+    }
+
+    #[tokio::test]
+    async fn test_route_key_uses_route_params() {
+        async fn handler_with_extract_route_key(key: ExtractRouteKey) -> String {
+            format!("{} {}", key.namespace, key.key)
+        }
+
+        let app = Router::new().nest(
+            "/api/v1",
+            Router::new().route(
+                "/repositories/:repo/good-first-issues",
+                get(handler_with_extract_route_key),
+            ),
+        );
+
+        let res = app
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/api/v1/repositories/cube/good-first-issues?page=1&owner=cube-js")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let res_body = res.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(res_body.to_vec()).unwrap();
+
+        assert_eq!(
+            body,
+            "api:v1:repositories:$repo:good-first-issues {api:v1:repositories:$repo:good-first-issues}:cube:owner=cube-js:page=1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_route_key_params_are_percent_decoded() {
+        async fn handler_with_extract_route_key(key: ExtractRouteKey) -> String {
+            key.key
+        }
+
+        let app = Router::new().route(
+            "/repositories/:repo/good-first-issues",
+            get(handler_with_extract_route_key),
+        );
+
+        let mut keys = Vec::new();
+
+        for uri in [
+            "/repositories/foo-bar/good-first-issues",
+            "/repositories/foo%2Dbar/good-first-issues",
+            "/repositories/foo%3Abar/good-first-issues",
+            "/repositories/foo%252Dbar/good-first-issues",
+        ] {
+            let res = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let res_body = res.into_body().collect().await.unwrap().to_bytes();
+
+            keys.push(String::from_utf8(res_body.to_vec()).unwrap());
+        }
+
+        assert_eq!(keys[0], "{repositories:$repo:good-first-issues}:foo-bar");
+        assert_eq!(keys[1], keys[0]);
+        // Decoded delimiters are escaped, and the values are decoded only once
+        assert_eq!(keys[2], "{repositories:$repo:good-first-issues}:foo%3Abar");
+        assert_eq!(
+            keys[3],
+            "{repositories:$repo:good-first-issues}:foo%252Dbar"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_prefixed() {
+        async fn handler_with_keys(
+            redis_key: ExtractRedisKey,
+            route_key: ExtractRouteKey,
+        ) -> String {
+            format!("{} {}", redis_key.0, route_key.key)
+        }
+
+        let app = Router::new()
+            .route("/repositories/:repo", get(handler_with_keys))
+            .layer(axum::Extension(KeyPrefix(String::from("test:1"))));
+
+        let res = app
+            .oneshot(
+                Request::builder()
+                    .uri("/repositories/cube?page=1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let res_body = res.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(res_body.to_vec()).unwrap();
+
+        assert_eq!(
+            body,
+            "test:1:repositories:cube:page=1 {test:1:repositories:$repo}:cube:page=1"
+        );
+    }
 }
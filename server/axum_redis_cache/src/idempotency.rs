@@ -0,0 +1,744 @@
+//! IdempotencyLayer is a middleware that makes the mutating endpoints safe to retry, following the `Idempotency-Key` header semantics.
+//!
+//! When a request contains an `Idempotency-Key` header, the middleware saves on Redis a fingerprint of the request (client, method,
+//! path, query and body) before calling the handler. Once the handler finishes, its response is saved on Redis under the same key, so
+//! any retry with the same key gets the same response without calling the handler again:
+//!
+//! - If the first request with that key is still running, the middleware returns a 409 Conflict response.
+//! - If the key was used with a different request, the middleware returns a 422 Unprocessable Entity response.
+//! - Otherwise, the saved response is returned with an `Idempotent-Replayed: true` header.
+//!
+//! Responses with a server error, or whose body cannot be captured (see RedisCacheOptions::max_body_size), are not saved. In that case,
+//! the key is released so the client can retry the request.
+//!
+//! The keys are scoped by client, identified with a RateLimitKey (the IP address of the connection by default), so a client
+//! sending the same `Idempotency-Key` as another one never gets its response. Requests whose client cannot be identified are
+//! passed through to the handler.
+//!
+//! Requests without the header, or using a safe method (GET, HEAD, OPTIONS and TRACE), are passed through to the handler.
+//! When Redis cannot be reached, requests are also passed through to the handler. When the saved entry of a key cannot be read,
+//! the middleware returns a 500 Internal Server Error response instead, as the first request may have already been handled.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use axum::{http::HeaderName, routing::post, Router};
+//! use axum_redis_cache::{idempotency::IdempotencyLayerBuilder, rate_limit::RateLimitKey};
+//! # use axum_redis_cache::pool::RedisConnectionManager;
+//! #
+//! # async fn create_subscription() {}
+//! #
+//! # let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+//! # let redis_pool = bb8::Pool::builder().build_unchecked(redis_manager);
+//!
+//! let app: Router = Router::new()
+//!     .route("/api/v1/subscriptions", post(create_subscription))
+//!     .layer(
+//!         IdempotencyLayerBuilder::new(redis_pool.clone())
+//!             .with_key(RateLimitKey::ApiKey(HeaderName::from_static("x-api-key")))
+//!             .with_expiration_time(86400)
+//!             .build(),
+//!     );
+//! ```
+use axum::{
+    body::{self, Body},
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bb8::{Pool, PooledConnection};
+use futures_util::future::BoxFuture;
+use redis::{AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use super::{
+    capture::{capture_body, CapturedBody},
+    codec::{CacheCipher, CacheCodec},
+    errors::RedisUtilsError,
+    extractors::escape_key_part,
+    pool::RedisConnectionManager,
+    rate_limit::RateLimitKey,
+};
+
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+const DEFAULT_IDEMPOTENCY_PREFIX: &str = "idempotency";
+const DEFAULT_EXPIRATION_TIME: u64 = 86400;
+
+// KEYS[1]: idempotency key. ARGV[1]: in progress entry, ARGV[2]: completed entry, ARGV[3]: expiration time in seconds.
+const COMPLETE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[3])
+    return 1
+end
+
+return 0
+"#;
+
+// KEYS[1]: idempotency key. ARGV[1]: in progress entry.
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+
+return 0
+"#;
+const DEFAULT_LOCK_EXPIRATION_TIME: u64 = 60;
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+#[derive(Clone, Debug)]
+pub struct IdempotencyOptions {
+    /// The time, in seconds, the responses are kept on Redis.
+    pub expiration_time: u64,
+    /// The max time, in seconds, a request can be running. After that time, the key is released so the request can be retried.
+    pub lock_expiration_time: u64,
+    /// The max size, in bytes, of the request and response bodies. Bigger requests are rejected with a 413 Payload Too Large
+    /// response, while bigger responses are returned without saving them.
+    pub max_body_size: Option<usize>,
+    /// The value used to identify the clients. Every client has its own idempotency keys.
+    pub key: RateLimitKey,
+    /// The prefix of the Redis keys.
+    pub prefix: String,
+    /// The cipher used to encrypt the saved responses. When it is None, responses are saved as plain JSON.
+    pub cipher: Option<CacheCipher>,
+}
+
+#[derive(Clone, Debug)]
+pub struct IdempotencyLayerBuilder {
+    options: IdempotencyOptions,
+    redis_pool: Pool<RedisConnectionManager>,
+}
+
+impl IdempotencyLayerBuilder {
+    pub fn new(redis_pool: Pool<RedisConnectionManager>) -> Self {
+        IdempotencyLayerBuilder {
+            redis_pool,
+            options: IdempotencyOptions {
+                expiration_time: DEFAULT_EXPIRATION_TIME,
+                lock_expiration_time: DEFAULT_LOCK_EXPIRATION_TIME,
+                max_body_size: Some(DEFAULT_MAX_BODY_SIZE),
+                key: RateLimitKey::ClientIp,
+                prefix: DEFAULT_IDEMPOTENCY_PREFIX.to_string(),
+                cipher: None,
+            },
+        }
+    }
+
+    pub fn with_expiration_time(self, expiration_time: u64) -> Self {
+        IdempotencyLayerBuilder {
+            options: IdempotencyOptions {
+                expiration_time,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_lock_expiration_time(self, lock_expiration_time: u64) -> Self {
+        IdempotencyLayerBuilder {
+            options: IdempotencyOptions {
+                lock_expiration_time,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_max_body_size(self, max_body_size: usize) -> Self {
+        IdempotencyLayerBuilder {
+            options: IdempotencyOptions {
+                max_body_size: Some(max_body_size),
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_key(self, key: RateLimitKey) -> Self {
+        IdempotencyLayerBuilder {
+            options: IdempotencyOptions {
+                key,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_prefix(self, prefix: String) -> Self {
+        IdempotencyLayerBuilder {
+            options: IdempotencyOptions {
+                prefix,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_encryption(self, cipher: CacheCipher) -> Self {
+        IdempotencyLayerBuilder {
+            options: IdempotencyOptions {
+                cipher: Some(cipher),
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn build(self) -> IdempotencyLayer {
+        IdempotencyLayer {
+            redis_pool: self.redis_pool,
+            options: self.options,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IdempotencyLayer {
+    options: IdempotencyOptions,
+    redis_pool: Pool<RedisConnectionManager>,
+}
+
+impl<S> Layer<S> for IdempotencyLayer {
+    type Service = IdempotencyMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IdempotencyMiddleware {
+            inner,
+            redis_pool: self.redis_pool.clone(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IdempotencyMiddleware<S> {
+    inner: S,
+    redis_pool: Pool<RedisConnectionManager>,
+    options: IdempotencyOptions,
+}
+
+impl<S> Service<Request> for IdempotencyMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let redis_pool = self.redis_pool.clone();
+        let options = self.options.clone();
+
+        // The request body needs to be read before calling the handler, so the service that was driven to readiness is taken
+        // and replaced by a clone.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let idempotency_key = match get_idempotency_key(&req) {
+                Ok(Some(key)) => key,
+                Ok(None) => return inner.call(req).await,
+                Err(status) => {
+                    return Ok((status, "Invalid Idempotency-Key header").into_response());
+                }
+            };
+
+            let (parts, body) = req.into_parts();
+
+            let Some(client_key) = options.key.extract(&parts) else {
+                tracing::debug!("Unable to identify the client, skipping idempotency check");
+
+                return inner.call(Request::from_parts(parts, body)).await;
+            };
+
+            let body_limit = options.max_body_size.unwrap_or(usize::MAX);
+
+            let body_bytes = match body::to_bytes(body, body_limit).await {
+                Ok(bytes) => bytes,
+                Err(_) => {
+                    return Ok((StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large")
+                        .into_response());
+                }
+            };
+
+            let fingerprint = get_request_fingerprint(
+                &client_key,
+                &parts.method,
+                &parts.uri.to_string(),
+                &body_bytes,
+            );
+            let request = Request::from_parts(parts, Body::from(body_bytes));
+
+            let mut redis_conn = match redis_pool.get().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    tracing::warn!(
+                        error = %err,
+                        "Unable to connect to Redis, skipping idempotency check"
+                    );
+
+                    return inner.call(request).await;
+                }
+            };
+
+            let redis_key = format!(
+                "{}:{}:{}",
+                options.prefix,
+                escape_key_part(&client_key),
+                escape_key_part(&idempotency_key)
+            );
+            let mut store = IdempotencyStore::new(&mut redis_conn, &redis_key, &options);
+
+            let existing_entry = match store.acquire(&fingerprint).await {
+                Ok(existing_entry) => existing_entry,
+                Err(AcquireError::Unavailable(err)) => {
+                    tracing::warn!(
+                        redis_key,
+                        error = %err,
+                        "Unable to save idempotency key, skipping idempotency check"
+                    );
+
+                    return inner.call(request).await;
+                }
+                // The key exists, so the request may have already been handled and it cannot be handled again
+                Err(AcquireError::UnreadableEntry(err)) => {
+                    tracing::error!(
+                        redis_key,
+                        error = %err,
+                        "Unable to read idempotency entry"
+                    );
+
+                    return Ok((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Unable to read the saved response of the Idempotency-Key",
+                    )
+                        .into_response());
+                }
+            };
+
+            if let Some(entry) = existing_entry {
+                return Ok(entry.into_response(&fingerprint));
+            }
+
+            let res = inner.call(request).await?;
+
+            Ok(store.complete(&fingerprint, res).await)
+        })
+    }
+}
+
+// Returns the Idempotency-Key of the request. Requests with safe methods are not checked, as they can be retried without side effects.
+fn get_idempotency_key(req: &Request) -> Result<Option<String>, StatusCode> {
+    if matches!(
+        *req.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    ) {
+        return Ok(None);
+    }
+
+    let Some(value) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH => {
+            Ok(Some(key.to_string()))
+        }
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+fn get_request_fingerprint(client_key: &str, method: &Method, uri: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+
+    hasher.update(client_key.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(method.as_str().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(uri.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+
+    format!("{:x}", hasher.finalize())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+enum IdempotencyEntry {
+    InProgress {
+        fingerprint: String,
+        // Identifies the request holding the key, so only that request can complete or release it
+        #[serde(default)]
+        owner: String,
+    },
+    Completed {
+        fingerprint: String,
+        response: SavedResponse,
+    },
+}
+
+impl IdempotencyEntry {
+    fn into_response(self, fingerprint: &str) -> Response {
+        match self {
+            IdempotencyEntry::InProgress {
+                fingerprint: saved_fingerprint,
+                ..
+            }
+            | IdempotencyEntry::Completed {
+                fingerprint: saved_fingerprint,
+                ..
+            } if saved_fingerprint != fingerprint => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used with a different request",
+            )
+                .into_response(),
+            IdempotencyEntry::InProgress { .. } => (
+                StatusCode::CONFLICT,
+                "A request with the same Idempotency-Key is still running",
+            )
+                .into_response(),
+            IdempotencyEntry::Completed { response, .. } => response.into_response(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    // Base64 encoded, as the body is not necessarily valid UTF-8
+    body: String,
+}
+
+impl SavedResponse {
+    fn new(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> Self {
+        SavedResponse {
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    value
+                        .to_str()
+                        .ok()
+                        .map(|value| (name.to_string(), value.to_string()))
+                })
+                .collect(),
+            body: BASE64.encode(body),
+        }
+    }
+
+    fn into_response(self) -> Response {
+        let Ok(body) = BASE64.decode(&self.body) else {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Unable to replay the saved response",
+            )
+                .into_response();
+        };
+
+        let mut res = Response::new(Body::from(body));
+
+        *res.status_mut() = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+
+        for (name, value) in self.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                res.headers_mut().append(name, value);
+            }
+        }
+
+        res.headers_mut()
+            .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+
+        res
+    }
+}
+
+// The reasons why the key could not be acquired.
+#[derive(Debug)]
+enum AcquireError {
+    // The key could not be saved, so the request can be handled without checking it
+    Unavailable(RedisUtilsError),
+    // The key exists but its entry could not be read or decoded
+    UnreadableEntry(RedisUtilsError),
+}
+
+// Reads and writes the idempotency entries of a key.
+struct IdempotencyStore<'a, 'b> {
+    redis_conn: &'a mut PooledConnection<'b, RedisConnectionManager>,
+    redis_key: &'a str,
+    options: &'a IdempotencyOptions,
+    // The in progress entry saved by this request, once the key is acquired
+    in_progress_entry: Option<String>,
+}
+
+impl<'a, 'b> IdempotencyStore<'a, 'b> {
+    fn new(
+        redis_conn: &'a mut PooledConnection<'b, RedisConnectionManager>,
+        redis_key: &'a str,
+        options: &'a IdempotencyOptions,
+    ) -> Self {
+        IdempotencyStore {
+            redis_conn,
+            redis_key,
+            options,
+            in_progress_entry: None,
+        }
+    }
+
+    fn encode(&self, entry: &IdempotencyEntry) -> Result<String, RedisUtilsError> {
+        let codec = CacheCodec::new(self.options.cipher.clone());
+
+        Ok(codec.encode(self.redis_key, entry)?.to_string())
+    }
+
+    // Saves the key as in progress. When the key already exists, it returns the saved entry instead.
+    async fn acquire(
+        &mut self,
+        fingerprint: &str,
+    ) -> Result<Option<IdempotencyEntry>, AcquireError> {
+        let entry = self
+            .encode(&IdempotencyEntry::InProgress {
+                fingerprint: fingerprint.to_string(),
+                owner: uuid::Uuid::new_v4().to_string(),
+            })
+            .map_err(AcquireError::UnreadableEntry)?;
+
+        let acquired: bool = redis::cmd("SET")
+            .arg(self.redis_key)
+            .arg(&entry)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.options.lock_expiration_time)
+            .query_async::<Option<String>>(&mut **self.redis_conn)
+            .await
+            .map_err(|err| AcquireError::Unavailable(RedisUtilsError::Redis(err)))?
+            .is_some();
+
+        if acquired {
+            self.in_progress_entry = Some(entry);
+
+            return Ok(None);
+        }
+
+        self.get_saved_entry(fingerprint)
+            .await
+            .map_err(AcquireError::UnreadableEntry)
+    }
+
+    async fn get_saved_entry(
+        &mut self,
+        fingerprint: &str,
+    ) -> Result<Option<IdempotencyEntry>, RedisUtilsError> {
+        let saved_entry: Option<String> = self
+            .redis_conn
+            .get(self.redis_key)
+            .await
+            .map_err(RedisUtilsError::Redis)?;
+
+        // The key could expire between both commands. In that case, the request is considered to be still running.
+        let Some(saved_entry) = saved_entry else {
+            return Ok(Some(IdempotencyEntry::InProgress {
+                fingerprint: fingerprint.to_string(),
+                owner: String::new(),
+            }));
+        };
+
+        let codec = CacheCodec::new(self.options.cipher.clone());
+        let document: serde_json::Value =
+            serde_json::from_str(&saved_entry).map_err(RedisUtilsError::Serialization)?;
+
+        codec.decode(self.redis_key, document).map(Some)
+    }
+
+    // Saves the response of the handler, so it can be replayed. When the response cannot be saved, the key is released.
+    // The response is only saved while the key still holds the in progress entry of this request: when the key expired
+    // during the request, another request may have acquired it, and its entry must not be overwritten.
+    async fn complete(mut self, fingerprint: &str, res: Response) -> Response {
+        if res.status().is_server_error() {
+            self.release().await;
+
+            return res;
+        }
+
+        let (parts, body) = res.into_parts();
+
+        let bytes = match capture_body(body, self.options.max_body_size, self.redis_key).await {
+            Ok(CapturedBody::Buffered(bytes)) => bytes,
+            Ok(CapturedBody::Streamed(body)) => {
+                self.release().await;
+
+                return Response::from_parts(parts, body);
+            }
+            Err(err) => {
+                self.release().await;
+
+                return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+            }
+        };
+
+        let entry = IdempotencyEntry::Completed {
+            fingerprint: fingerprint.to_string(),
+            response: SavedResponse::new(parts.status, &parts.headers, &bytes),
+        };
+
+        let result = match self.encode(&entry) {
+            Ok(entry) => Script::new(COMPLETE_SCRIPT)
+                .key(self.redis_key)
+                .arg(self.in_progress_entry.as_deref().unwrap_or_default())
+                .arg(entry)
+                .arg(self.options.expiration_time)
+                .invoke_async::<bool>(&mut **self.redis_conn)
+                .await
+                .map_err(RedisUtilsError::Redis),
+            Err(err) => Err(err),
+        };
+
+        match result {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::warn!(
+                    redis_key = self.redis_key,
+                    "Idempotency key expired before the response was saved"
+                );
+            }
+            Err(err) => {
+                tracing::error!(
+                    redis_key = self.redis_key,
+                    error = %err,
+                    "Unable to save idempotent response"
+                );
+
+                self.release().await;
+            }
+        }
+
+        Response::from_parts(parts, Body::from(bytes))
+    }
+
+    // Deletes the key, unless it no longer holds the in progress entry of this request.
+    async fn release(&mut self) {
+        let result = Script::new(RELEASE_SCRIPT)
+            .key(self.redis_key)
+            .arg(self.in_progress_entry.as_deref().unwrap_or_default())
+            .invoke_async::<()>(&mut **self.redis_conn)
+            .await;
+
+        if let Err(err) = result {
+            tracing::error!(
+                redis_key = self.redis_key,
+                error = %err,
+                "Unable to release idempotency key"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_fingerprint_depends_on_client_method_uri_and_body() {
+        let client = "ip:127.0.0.1";
+        let fingerprint = get_request_fingerprint(client, &Method::POST, "/api/v1/test", b"{}");
+
+        assert_eq!(
+            fingerprint,
+            get_request_fingerprint(client, &Method::POST, "/api/v1/test", b"{}")
+        );
+        assert_ne!(
+            fingerprint,
+            get_request_fingerprint("ip:127.0.0.2", &Method::POST, "/api/v1/test", b"{}")
+        );
+        assert_ne!(
+            fingerprint,
+            get_request_fingerprint(client, &Method::PUT, "/api/v1/test", b"{}")
+        );
+        assert_ne!(
+            fingerprint,
+            get_request_fingerprint(client, &Method::POST, "/api/v1/other", b"{}")
+        );
+        assert_ne!(
+            fingerprint,
+            get_request_fingerprint(client, &Method::POST, "/api/v1/test", b"{\"a\":1}")
+        );
+    }
+
+    #[test]
+    fn test_idempotency_key_is_ignored_on_safe_methods() {
+        let req = Request::builder()
+            .method(Method::GET)
+            .header(IDEMPOTENCY_KEY_HEADER, "key")
+            .body(Body::empty())
+            .unwrap();
+
+        assert!(matches!(get_idempotency_key(&req), Ok(None)));
+    }
+
+    #[test]
+    fn test_invalid_idempotency_key_is_rejected() {
+        let req = Request::builder()
+            .method(Method::POST)
+            .header(IDEMPOTENCY_KEY_HEADER, "a".repeat(256))
+            .body(Body::empty())
+            .unwrap();
+
+        let status = get_idempotency_key(&req).unwrap_err();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_in_progress_entry_responses() {
+        let entry = || IdempotencyEntry::InProgress {
+            fingerprint: String::from("fingerprint"),
+            owner: String::from("owner"),
+        };
+
+        assert_eq!(
+            entry().into_response("fingerprint").status(),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            entry().into_response("other").status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[test]
+    fn test_completed_entry_is_replayed() {
+        let mut headers = HeaderMap::new();
+
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+
+        let entry = IdempotencyEntry::Completed {
+            fingerprint: String::from("fingerprint"),
+            response: SavedResponse::new(StatusCode::CREATED, &headers, b"{}"),
+        };
+
+        let res = entry.into_response("fingerprint");
+
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            "application/json"
+        );
+        assert_eq!(
+            res.headers().get(IDEMPOTENT_REPLAYED_HEADER).unwrap(),
+            "true"
+        );
+    }
+}
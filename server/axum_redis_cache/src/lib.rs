@@ -1,3 +1,14 @@
+pub mod cache;
+mod capture;
+pub mod codec;
 pub mod errors;
 pub mod extractors;
+pub mod idempotency;
+pub mod lock;
 pub mod middlewares;
+pub mod namespace;
+pub mod pool;
+pub mod rate_limit;
+pub mod tasks;
+#[cfg(feature = "test-util")]
+pub mod testing;
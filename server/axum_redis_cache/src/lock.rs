@@ -0,0 +1,627 @@
+//! RedisLock and RedisSemaphore coordinate work between the replicas of a service using the same Redis pool as the cache layer.
+//!
+//! - RedisLock is a mutual exclusion lock, following the single instance variant of the Redlock algorithm: the lock is a key with a
+//!   random value that expires after the lease time, so a crashed holder can not keep it forever.
+//! - RedisSemaphore limits the number of holders of a resource to a number of permits. The holders are saved on a sorted set with the
+//!   time their lease expires, so expired permits are reclaimed by the next acquisition.
+//!
+//! Both return a RedisLease, which includes a fencing token. The fencing token of a resource always increases with every acquisition,
+//! so the services protected by the lock can reject the writes of a holder whose lease expired while it was paused.
+//!
+//! Leases can be renewed manually, or in the background when the lock is created with `with_auto_renewal`. Leases are released when
+//! calling `release` or, as a best effort, when dropped. Renewals and releases only apply while the lease is still owned by the holder,
+//! so a holder never releases a lease acquired by someone else after its own one expired.
+//!
+//! The lock and the fencing token keys use the name of the resource as a hash tag, so both are saved on the same Redis Cluster node.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use axum_redis_cache::lock::{RedisLock, RedisSemaphore};
+//! use std::time::Duration;
+//! # use axum_redis_cache::{errors::RedisUtilsError, pool::RedisConnectionManager};
+//! #
+//! # async fn refresh_repositories(_fencing_token: u64) {}
+//! # async fn call_github() {}
+//! #
+//! # async fn run() -> Result<(), RedisUtilsError> {
+//! # let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+//! # let redis_pool = bb8::Pool::builder().build_unchecked(redis_manager);
+//!
+//! let lock = RedisLock::new(redis_pool.clone(), "refresh-repositories")
+//!     .with_lease_time(Duration::from_secs(30))
+//!     .with_auto_renewal();
+//!
+//! if let Some(lease) = lock.try_acquire().await? {
+//!     refresh_repositories(lease.fencing_token()).await;
+//!     lease.release().await?;
+//! }
+//!
+//! let semaphore = RedisSemaphore::new(redis_pool.clone(), "github-api", 10);
+//!
+//! if let Some(permit) = semaphore.acquire(Duration::from_secs(5)).await? {
+//!     call_github().await;
+//!     permit.release().await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use bb8::Pool;
+use redis::Script;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use tokio::{task::JoinHandle, time::Instant};
+
+use super::{
+    errors::RedisUtilsError, pool::RedisConnectionManager, tasks::spawn_background_task_on,
+};
+
+const DEFAULT_LOCK_PREFIX: &str = "lock";
+const DEFAULT_SEMAPHORE_PREFIX: &str = "semaphore";
+const DEFAULT_LEASE_TIME: Duration = Duration::from_secs(30);
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(100);
+// Redis expires the keys with a precision of milliseconds
+const MIN_LEASE_TIME: Duration = Duration::from_millis(1);
+// Redlock accounts for the clock drift between the client and Redis by reducing the validity of the lease.
+const CLOCK_DRIFT_FACTOR: f64 = 0.01;
+const CLOCK_DRIFT_MIN: Duration = Duration::from_millis(2);
+
+// KEYS[1]: lock key, KEYS[2]: fencing token key. ARGV[1]: holder id, ARGV[2]: lease time in milliseconds.
+const LOCK_ACQUIRE_SCRIPT: &str = r#"
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+    return redis.call('INCR', KEYS[2])
+end
+
+return 0
+"#;
+
+// KEYS[1]: lock key. ARGV[1]: holder id, ARGV[2]: lease time in milliseconds.
+const LOCK_RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+
+return 0
+"#;
+
+// KEYS[1]: lock key. ARGV[1]: holder id.
+const LOCK_RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+
+return 0
+"#;
+
+// KEYS[1]: sorted set with the holders, KEYS[2]: fencing token key. ARGV[1]: holder id, ARGV[2]: lease time in milliseconds,
+// ARGV[3]: number of permits.
+const SEMAPHORE_ACQUIRE_SCRIPT: &str = r#"
+local lease = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now)
+
+if redis.call('ZCARD', KEYS[1]) >= tonumber(ARGV[3]) then
+    return 0
+end
+
+redis.call('ZADD', KEYS[1], now + lease, ARGV[1])
+
+-- The set expires with the lease of its last holder, so a shorter lease does not expire the other holders
+if redis.call('PTTL', KEYS[1]) < lease then
+    redis.call('PEXPIRE', KEYS[1], lease)
+end
+
+return redis.call('INCR', KEYS[2])
+"#;
+
+// KEYS[1]: sorted set with the holders. ARGV[1]: holder id, ARGV[2]: lease time in milliseconds.
+const SEMAPHORE_RENEW_SCRIPT: &str = r#"
+local lease = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local expires_at = tonumber(redis.call('ZSCORE', KEYS[1], ARGV[1]))
+
+if not expires_at or expires_at <= now then
+    redis.call('ZREM', KEYS[1], ARGV[1])
+
+    return 0
+end
+
+redis.call('ZADD', KEYS[1], 'XX', now + lease, ARGV[1])
+
+if redis.call('PTTL', KEYS[1]) < lease then
+    redis.call('PEXPIRE', KEYS[1], lease)
+end
+
+return 1
+"#;
+
+// KEYS[1]: sorted set with the holders. ARGV[1]: holder id.
+const SEMAPHORE_RELEASE_SCRIPT: &str = r#"
+return redis.call('ZREM', KEYS[1], ARGV[1])
+"#;
+
+#[derive(Clone, Debug)]
+pub struct RedisLock {
+    redis_pool: Pool<RedisConnectionManager>,
+    name: String,
+    prefix: String,
+    lease_time: Duration,
+    retry_delay: Duration,
+    auto_renewal: bool,
+}
+
+impl RedisLock {
+    pub fn new(redis_pool: Pool<RedisConnectionManager>, name: impl Into<String>) -> Self {
+        RedisLock {
+            redis_pool,
+            name: name.into(),
+            prefix: DEFAULT_LOCK_PREFIX.to_string(),
+            lease_time: DEFAULT_LEASE_TIME,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            auto_renewal: false,
+        }
+    }
+
+    pub fn with_prefix(self, prefix: String) -> Self {
+        RedisLock { prefix, ..self }
+    }
+
+    /// The time the lease is held for. It must be at least 1ms, otherwise acquiring the lock fails.
+    pub fn with_lease_time(self, lease_time: Duration) -> Self {
+        RedisLock { lease_time, ..self }
+    }
+
+    /// The time to wait between acquisition attempts when using `acquire`.
+    pub fn with_retry_delay(self, retry_delay: Duration) -> Self {
+        RedisLock {
+            retry_delay,
+            ..self
+        }
+    }
+
+    /// Renews the lease in the background every third of the lease time, until it is released or lost.
+    pub fn with_auto_renewal(self) -> Self {
+        RedisLock {
+            auto_renewal: true,
+            ..self
+        }
+    }
+
+    /// Tries to acquire the lock once. Returns None when the lock is held by someone else.
+    pub async fn try_acquire(&self) -> Result<Option<RedisLease>, RedisUtilsError> {
+        validate_lease_time(self.lease_time)?;
+
+        let (redis_key, fencing_key) = get_resource_keys(&self.prefix, &self.name);
+
+        let lease = LeaseState {
+            redis_pool: self.redis_pool.clone(),
+            redis_key,
+            holder: uuid::Uuid::new_v4().to_string(),
+            lease_time: self.lease_time,
+            renew_script: Script::new(LOCK_RENEW_SCRIPT),
+            release_script: Script::new(LOCK_RELEASE_SCRIPT),
+            valid_until: Mutex::new(Instant::now()),
+        };
+
+        let script = Script::new(LOCK_ACQUIRE_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+
+        invocation
+            .key(&lease.redis_key)
+            .key(&fencing_key)
+            .arg(&lease.holder)
+            .arg(lease.lease_millis());
+
+        let fencing_token = lease.acquire(&mut invocation).await?;
+
+        Ok(fencing_token
+            .map(|fencing_token| RedisLease::new(lease, fencing_token, self.auto_renewal)))
+    }
+
+    /// Tries to acquire the lock until it succeeds or the timeout is reached. Returns None when the timeout is reached.
+    pub async fn acquire(&self, timeout: Duration) -> Result<Option<RedisLease>, RedisUtilsError> {
+        retry_until(timeout, self.retry_delay, || self.try_acquire()).await
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RedisSemaphore {
+    redis_pool: Pool<RedisConnectionManager>,
+    name: String,
+    permits: u64,
+    prefix: String,
+    lease_time: Duration,
+    retry_delay: Duration,
+    auto_renewal: bool,
+}
+
+impl RedisSemaphore {
+    pub fn new(
+        redis_pool: Pool<RedisConnectionManager>,
+        name: impl Into<String>,
+        permits: u64,
+    ) -> Self {
+        RedisSemaphore {
+            redis_pool,
+            name: name.into(),
+            permits,
+            prefix: DEFAULT_SEMAPHORE_PREFIX.to_string(),
+            lease_time: DEFAULT_LEASE_TIME,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            auto_renewal: false,
+        }
+    }
+
+    pub fn with_prefix(self, prefix: String) -> Self {
+        RedisSemaphore { prefix, ..self }
+    }
+
+    /// The time the lease is held for. It must be at least 1ms, otherwise acquiring a permit fails.
+    pub fn with_lease_time(self, lease_time: Duration) -> Self {
+        RedisSemaphore { lease_time, ..self }
+    }
+
+    /// The time to wait between acquisition attempts when using `acquire`.
+    pub fn with_retry_delay(self, retry_delay: Duration) -> Self {
+        RedisSemaphore {
+            retry_delay,
+            ..self
+        }
+    }
+
+    /// Renews the lease in the background every third of the lease time, until it is released or lost.
+    pub fn with_auto_renewal(self) -> Self {
+        RedisSemaphore {
+            auto_renewal: true,
+            ..self
+        }
+    }
+
+    /// Tries to acquire a permit once. Returns None when all the permits are in use.
+    pub async fn try_acquire(&self) -> Result<Option<RedisLease>, RedisUtilsError> {
+        validate_lease_time(self.lease_time)?;
+
+        let (redis_key, fencing_key) = get_resource_keys(&self.prefix, &self.name);
+
+        let lease = LeaseState {
+            redis_pool: self.redis_pool.clone(),
+            redis_key,
+            holder: uuid::Uuid::new_v4().to_string(),
+            lease_time: self.lease_time,
+            renew_script: Script::new(SEMAPHORE_RENEW_SCRIPT),
+            release_script: Script::new(SEMAPHORE_RELEASE_SCRIPT),
+            valid_until: Mutex::new(Instant::now()),
+        };
+
+        let script = Script::new(SEMAPHORE_ACQUIRE_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+
+        invocation
+            .key(&lease.redis_key)
+            .key(&fencing_key)
+            .arg(&lease.holder)
+            .arg(lease.lease_millis())
+            .arg(self.permits);
+
+        let fencing_token = lease.acquire(&mut invocation).await?;
+
+        Ok(fencing_token
+            .map(|fencing_token| RedisLease::new(lease, fencing_token, self.auto_renewal)))
+    }
+
+    /// Tries to acquire a permit until it succeeds or the timeout is reached. Returns None when the timeout is reached.
+    pub async fn acquire(&self, timeout: Duration) -> Result<Option<RedisLease>, RedisUtilsError> {
+        retry_until(timeout, self.retry_delay, || self.try_acquire()).await
+    }
+}
+
+/// A lease on a RedisLock or on a permit of a RedisSemaphore.
+#[derive(Debug)]
+pub struct RedisLease {
+    state: Arc<LeaseState>,
+    fencing_token: u64,
+    renewal: Option<JoinHandle<()>>,
+    released: bool,
+}
+
+impl RedisLease {
+    fn new(state: LeaseState, fencing_token: u64, auto_renewal: bool) -> Self {
+        let state = Arc::new(state);
+        let renewal = auto_renewal.then(|| spawn_renewal(state.clone()));
+
+        RedisLease {
+            state,
+            fencing_token,
+            renewal,
+            released: false,
+        }
+    }
+
+    /// The fencing token of the lease. Every acquisition of the same resource gets a bigger token.
+    pub fn fencing_token(&self) -> u64 {
+        self.fencing_token
+    }
+
+    /// Returns false once the lease could have expired, taking into account the clock drift, or when a renewal found out it was lost.
+    pub fn is_valid(&self) -> bool {
+        self.state.is_valid()
+    }
+
+    /// Extends the lease by the lease time. Returns false when the lease was already lost.
+    pub async fn renew(&self) -> Result<bool, RedisUtilsError> {
+        self.state.renew().await
+    }
+
+    /// Releases the lease. Returns false when the lease was already lost.
+    pub async fn release(mut self) -> Result<bool, RedisUtilsError> {
+        self.released = true;
+
+        if let Some(renewal) = self.renewal.take() {
+            renewal.abort();
+        }
+
+        self.state.release().await
+    }
+}
+
+impl Drop for RedisLease {
+    fn drop(&mut self) {
+        if let Some(renewal) = self.renewal.take() {
+            renewal.abort();
+        }
+
+        if self.released {
+            return;
+        }
+
+        // Without a runtime, the lease is released once it expires
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let state = self.state.clone();
+
+            spawn_background_task_on(&handle, async move {
+                if let Err(err) = state.release().await {
+                    tracing::warn!(
+                        redis_key = state.redis_key,
+                        error = %err,
+                        "Unable to release dropped lease"
+                    );
+                }
+            });
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LeaseState {
+    redis_pool: Pool<RedisConnectionManager>,
+    redis_key: String,
+    holder: String,
+    lease_time: Duration,
+    renew_script: Script,
+    release_script: Script,
+    valid_until: Mutex<Instant>,
+}
+
+impl LeaseState {
+    fn lease_millis(&self) -> u64 {
+        self.lease_time.as_millis() as u64
+    }
+
+    fn set_valid_until(&self, instant: Instant) {
+        *self
+            .valid_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = instant;
+    }
+
+    fn is_valid(&self) -> bool {
+        Instant::now()
+            < *self
+                .valid_until
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Runs the acquisition script, which returns the fencing token, or 0 when the resource is not available.
+    async fn acquire(
+        &self,
+        invocation: &mut redis::ScriptInvocation<'_>,
+    ) -> Result<Option<u64>, RedisUtilsError> {
+        let started_at = Instant::now();
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RedisUtilsError::RedisConnection)?;
+
+        let fencing_token: u64 = invocation
+            .invoke_async(&mut *redis_conn)
+            .await
+            .map_err(RedisUtilsError::Redis)?;
+
+        if fencing_token == 0 {
+            return Ok(None);
+        }
+
+        self.set_valid_until(get_valid_until(started_at, self.lease_time));
+
+        Ok(Some(fencing_token))
+    }
+
+    async fn renew(&self) -> Result<bool, RedisUtilsError> {
+        let started_at = Instant::now();
+        let renewed = self.invoke(&self.renew_script).await?;
+
+        if renewed {
+            self.set_valid_until(get_valid_until(started_at, self.lease_time));
+        } else {
+            self.set_valid_until(started_at);
+        }
+
+        Ok(renewed)
+    }
+
+    async fn release(&self) -> Result<bool, RedisUtilsError> {
+        let released = self.invoke(&self.release_script).await?;
+
+        self.set_valid_until(Instant::now());
+
+        Ok(released)
+    }
+
+    async fn invoke(&self, script: &Script) -> Result<bool, RedisUtilsError> {
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RedisUtilsError::RedisConnection)?;
+
+        let result: i64 = script
+            .key(&self.redis_key)
+            .arg(&self.holder)
+            .arg(self.lease_millis())
+            .invoke_async(&mut *redis_conn)
+            .await
+            .map_err(RedisUtilsError::Redis)?;
+
+        Ok(result == 1)
+    }
+}
+
+fn spawn_renewal(state: Arc<LeaseState>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let interval = state.lease_time / 3;
+
+        loop {
+            tokio::time::sleep(interval).await;
+
+            match state.renew().await {
+                Ok(true) => {}
+                Ok(false) => {
+                    tracing::warn!(
+                        redis_key = state.redis_key,
+                        "Lease lost, stopping its renewal"
+                    );
+
+                    break;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        redis_key = state.redis_key,
+                        error = %err,
+                        "Unable to renew lease"
+                    );
+
+                    if !state.is_valid() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+async fn retry_until<F, Fut>(
+    timeout: Duration,
+    retry_delay: Duration,
+    mut try_acquire: F,
+) -> Result<Option<RedisLease>, RedisUtilsError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<RedisLease>, RedisUtilsError>>,
+{
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        if let Some(lease) = try_acquire().await? {
+            return Ok(Some(lease));
+        }
+
+        let now = Instant::now();
+
+        if now >= deadline {
+            return Ok(None);
+        }
+
+        tokio::time::sleep(retry_delay.min(deadline - now)).await;
+    }
+}
+
+// The name is used as a hash tag, so both keys are saved on the same Redis Cluster node and can be used in the same script.
+fn get_resource_keys(prefix: &str, name: &str) -> (String, String) {
+    let redis_key = format!("{}:{{{}}}", prefix, name);
+    let fencing_key = format!("{}:fencing", redis_key);
+
+    (redis_key, fencing_key)
+}
+
+fn validate_lease_time(lease_time: Duration) -> Result<(), RedisUtilsError> {
+    if lease_time < MIN_LEASE_TIME {
+        return Err(RedisUtilsError::InvalidLeaseTime(lease_time));
+    }
+
+    Ok(())
+}
+
+fn get_valid_until(started_at: Instant, lease_time: Duration) -> Instant {
+    let drift = lease_time.mul_f64(CLOCK_DRIFT_FACTOR) + CLOCK_DRIFT_MIN;
+
+    started_at + lease_time.saturating_sub(drift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_keys_share_hash_tag() {
+        let (redis_key, fencing_key) = get_resource_keys("lock", "refresh");
+
+        assert_eq!(redis_key, "lock:{refresh}");
+        assert_eq!(fencing_key, "lock:{refresh}:fencing");
+    }
+
+    #[tokio::test]
+    async fn test_lease_time_must_be_at_least_one_millisecond() {
+        let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+        let redis_pool = Pool::builder().build_unchecked(redis_manager);
+
+        let lock_result = RedisLock::new(redis_pool.clone(), "refresh")
+            .with_lease_time(Duration::ZERO)
+            .try_acquire()
+            .await;
+        let semaphore_result = RedisSemaphore::new(redis_pool, "refresh", 2)
+            .with_lease_time(Duration::from_micros(500))
+            .try_acquire()
+            .await;
+
+        assert!(matches!(
+            lock_result,
+            Err(RedisUtilsError::InvalidLeaseTime(lease_time)) if lease_time.is_zero()
+        ));
+        assert!(matches!(
+            semaphore_result,
+            Err(RedisUtilsError::InvalidLeaseTime(_))
+        ));
+    }
+
+    #[test]
+    fn test_valid_until_accounts_for_clock_drift() {
+        let started_at = Instant::now();
+
+        assert_eq!(
+            get_valid_until(started_at, Duration::from_secs(10)),
+            started_at + Duration::from_millis(9898)
+        );
+        assert_eq!(
+            get_valid_until(started_at, Duration::from_millis(1)),
+            started_at
+        );
+    }
+}
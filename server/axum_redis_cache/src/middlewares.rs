@@ -10,84 +10,125 @@
 //!
 //! You can add the RedisCacheLayer to your handler by using RedisCacheLayerBuilder like in the following example.
 //!
-//! ```
-//! use axum::{handler::get, Router};
-//! use axum_redis_utils::middlewares::{RedisCacheLayer, RedisCacheOptions};
-//! use std::sync::Arc;
-//! use axum_redis_utils::middlewares::RedisCacheLayer;
-//! use bb8_redis::RedisConnectionManager;
+//! ```rust,no_run
+//! use axum::{handler::Handler, routing::get, Json, Router};
+//! use axum_redis_cache::{middlewares::RedisCacheLayerBuilder, pool::RedisConnectionManager};
+//! use redis_macros::FromRedisValue;
+//! use serde::{Deserialize, Serialize};
 //!
-//! #[derive(Clone)]
-//! struct State {
-//!    pub redis_pool: Pool<RedisConnectionManager>,
+//! #[derive(Clone, Debug, Serialize, Deserialize, FromRedisValue)]
+//! struct UsersResponse {
+//!     users: Vec<String>,
 //! }
 //!
-//! let redis_pool = bb8::Pool::builder().build(redis_manager).await.unwrap();
+//! async fn handler() -> Json<UsersResponse> {
+//!     Json(UsersResponse { users: vec![] })
+//! }
 //!
-//! let state = Arc::new(State { redis_pool });
+//! # async fn run() {
+//! let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+//! let redis_pool = bb8::Pool::builder().build(redis_manager).await.unwrap();
 //!
-//! let app = Router::new()
-//!    .route(
-//!       "/api/v1/users",
-//!      get(handler.layer(RedisCacheLayer::<RedisCacheResponseValue
-//! >::new(state.redis_pool.clone())))
-//!   )
-//!   .with_state(state);
+//! let app: Router = Router::new().route(
+//!     "/api/v1/users",
+//!     get(handler.layer(RedisCacheLayerBuilder::new(redis_pool.clone()).build::<UsersResponse>())),
+//! );
+//! # }
 //! ```
 //!
-//! ResponsType needs to be a type that implements Deserialize, Serialize and FromRedisValue.
+//! The cached type (UsersResponse above) needs to implement Clone, Deserialize, Serialize and FromRedisValue.
 //!
 //! The middleware can be configured from the RedisCacheLayerBuilder using the methods with the prefix 'with', which
 //! allows you to set just the properties that you need.
 //!
-//!
-//! ```rust
-//! use axum::{handler::get, Router};
-//! use axum_redis_utils::middlewares::{RedisCacheLayerBuilder, RedisCacheOptions};
-//! use std::sync::Arc;
-//! use bb8_redis::RedisConnectionManager;
-//!
-//! #[derive(Clone)]
-//! struct State {
-//!    pub redis_pool: Pool<RedisConnectionManager>,
-//! }
-//!
-//! let redis_pool = bb8::Pool::builder().build(redis_manager).await.unwrap();
-//!
-//! let state = Arc::new(State { redis_pool });
-//!
-//! let app = Router::new()
-//!     .route(
-//!         "/api/v1/users",
-//!         get(handler.layer(
-//!             RedisCacheLayerBuilder::new(redis_pool.clone())
-//!                 .with_expiration_time(600)
-//!                 .with_path("users")
-//!                 .build(),
-//!         )),
-//!     )
-//!   .with_state(state);
+//! ```rust,no_run
+//! # use axum::{handler::Handler, routing::get, Json, Router};
+//! # use axum_redis_cache::{middlewares::RedisCacheLayerBuilder, pool::RedisConnectionManager};
+//! # use redis_macros::FromRedisValue;
+//! # use serde::{Deserialize, Serialize};
+//! #
+//! # #[derive(Clone, Debug, Serialize, Deserialize, FromRedisValue)]
+//! # struct UsersResponse {
+//! #     users: Vec<String>,
+//! # }
+//! #
+//! # async fn handler() -> Json<UsersResponse> {
+//! #     Json(UsersResponse { users: vec![] })
+//! # }
+//! #
+//! # let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+//! # let redis_pool = bb8::Pool::builder().build_unchecked(redis_manager);
+//! let app: Router = Router::new().route(
+//!     "/api/v1/users",
+//!     get(handler.layer(
+//!         RedisCacheLayerBuilder::new(redis_pool.clone())
+//!             .with_expiration_time(600)
+//!             .with_max_body_size(512 * 1024)
+//!             .build::<UsersResponse>(),
+//!     )),
+//! );
 //! ```
 //!
 //! But default the values of the RedisCacheOptions are the following ones:
 //!
 //! ```rust
+//! # use axum_redis_cache::{extractors::KeyNormalization, middlewares::RedisCacheOptions, namespace::NamespaceBudget};
+//! #
+//! # let _ =
 //! RedisCacheOptions {
 //!     expiration_time: None,
 //!     path: Some(String::from("$")),
+//!     max_body_size: Some(1024 * 1024),
+//!     cipher: None,
+//!     key_normalization: KeyNormalization::default(),
+//!     namespace: None,
+//!     namespace_budget: NamespaceBudget::default(),
+//!     key_prefix: None,
 //! }
+//! # ;
+//! ```
+//!
+//! Only handler responses with a known length that does not exceed `max_body_size` are buffered and saved on Redis. Any other response
+//! (bigger than the limit or without a known length, like a streamed body) is passed through to the client untouched and it is not cached.
+//!
+//! The expiration time can be changed per request by inserting a CacheExpirationTime extension from an outer middleware, which
+//! replaces the one of the layer.
+//!
+//! Responses can be grouped in a namespace with `with_namespace` or `with_route_namespace`. Then, the Redis key is built from the namespace
+//! and the route parameters, and the namespace keeps an index of its entries and the number of hits and misses, so its entries can be
+//! inspected or invalidated together with CacheNamespace (see the namespace module).
+//!
+//! The keys can be prefixed with `with_key_prefix`, so several applications (or tests) can share a Redis server. When the responses
+//! are grouped in a namespace, the prefix is part of its name.
+//!
+//! Cached responses can be encrypted at rest by providing a CacheCipher with `with_encryption`. Entries that cannot be decrypted
+//! (for example, because their key was removed from the cipher) are treated as cache misses and replaced by the handler response.
+//!
+//! ```rust,no_run
+//! use axum_redis_cache::codec::CacheCipher;
+//! # use axum_redis_cache::{middlewares::RedisCacheLayerBuilder, pool::RedisConnectionManager};
+//! #
+//! # type UsersResponse = serde_json::Value;
+//! #
+//! # let encryption_key = std::env::var("CACHE_ENCRYPTION_KEY").unwrap();
+//! # let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+//! # let redis_pool = bb8::Pool::builder().build_unchecked(redis_manager);
+//!
+//! let cipher = CacheCipher::from_base64("2024-06", &encryption_key).unwrap();
+//!
+//! let layer = RedisCacheLayerBuilder::new(redis_pool.clone())
+//!     .with_encryption(cipher)
+//!     .build::<UsersResponse>();
 //! ```
 use axum::{
     body::Body,
     extract::Request,
-    http::{HeaderMap, HeaderValue, StatusCode},
+    http::{request::Parts, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json, RequestPartsExt,
 };
 use bb8::{Pool, PooledConnection};
-use bb8_redis::RedisConnectionManager;
 use futures_util::future::BoxFuture;
-use http_body_util::BodyExt;
 use redis::{AsyncCommands, FromRedisValue, JsonAsyncCommands};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
@@ -97,9 +138,17 @@ use std::{
 };
 use tower::{Layer, Service};
 
-use super::{errors::RedisUtilsError, extractors::ExtractRedisKey};
+use super::{
+    capture::{capture_body, CapturedBody},
+    codec::{CacheCipher, CacheCodec},
+    errors::RedisUtilsError,
+    extractors::{ExtractRedisKey, ExtractRouteKey, KeyNormalization, KeyPrefix},
+    namespace::{record_entry, record_lookup, NamespaceBudget, RouteNamespace},
+    pool::RedisConnectionManager,
+};
 
 const DEFAULT_REDIS_PATH: &str = "$";
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
 
 #[derive(Clone, Debug)]
 pub struct RedisCacheOptions {
@@ -107,22 +156,49 @@ pub struct RedisCacheOptions {
     pub expiration_time: Option<i64>,
     /// The redis path where the JSON object is going to be saved. It uses the root by default.
     pub path: Option<String>,
+    /// The max size, in bytes, of a response body that can be cached. Bigger responses are streamed to the client without being cached.
+    /// When it is None, any response with a known length is cached.
+    pub max_body_size: Option<usize>,
+    /// The cipher used to encrypt the cached responses. When it is None, responses are saved as plain JSON.
+    pub cipher: Option<CacheCipher>,
+    /// How the request path and query parameters are normalized to build the Redis key.
+    pub key_normalization: KeyNormalization,
+    /// The namespace of the cached responses. When it is None, the Redis key is built from the whole request path.
+    pub namespace: Option<RouteNamespace>,
+    /// The limits of the namespace. They are only applied when there is a namespace.
+    pub namespace_budget: NamespaceBudget,
+    /// The prefix of the Redis keys, joined with a colon (:).
+    pub key_prefix: Option<String>,
 }
 
+/// Request extension that replaces the expiration time of the layer, in seconds, for the response of the request. It allows
+/// changing the expiration time at runtime (for example, when it is reloaded from the settings), by inserting it from an
+/// outer middleware.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheExpirationTime(pub i64);
+
 //
 #[derive(Clone, Debug)]
 pub struct RedisCacheLayerBuilder {
     options: RedisCacheOptions,
     redis_pool: Pool<RedisConnectionManager>,
+    read_pool: Option<Pool<RedisConnectionManager>>,
 }
 
 impl RedisCacheLayerBuilder {
     pub fn new(redis_pool: Pool<RedisConnectionManager>) -> Self {
         RedisCacheLayerBuilder {
             redis_pool,
+            read_pool: None,
             options: RedisCacheOptions {
                 expiration_time: None,
                 path: Some(DEFAULT_REDIS_PATH.to_string()),
+                max_body_size: Some(DEFAULT_MAX_BODY_SIZE),
+                cipher: None,
+                key_normalization: KeyNormalization::default(),
+                namespace: None,
+                namespace_budget: NamespaceBudget::default(),
+                key_prefix: None,
             },
         }
     }
@@ -147,9 +223,93 @@ impl RedisCacheLayerBuilder {
         }
     }
 
+    pub fn with_max_body_size(self, max_body_size: usize) -> Self {
+        RedisCacheLayerBuilder {
+            options: RedisCacheOptions {
+                max_body_size: Some(max_body_size),
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_encryption(self, cipher: CacheCipher) -> Self {
+        RedisCacheLayerBuilder {
+            options: RedisCacheOptions {
+                cipher: Some(cipher),
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_key_normalization(self, key_normalization: KeyNormalization) -> Self {
+        RedisCacheLayerBuilder {
+            options: RedisCacheOptions {
+                key_normalization,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    /// Saves the responses under the given namespace, using the values of the path parameters instead of the whole path
+    /// to build the Redis key (see the namespace module).
+    pub fn with_namespace(self, namespace: impl Into<String>) -> Self {
+        RedisCacheLayerBuilder {
+            options: RedisCacheOptions {
+                namespace: Some(RouteNamespace::Named(namespace.into())),
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    /// Like `with_namespace`, but the namespace is derived from the route template.
+    pub fn with_route_namespace(self) -> Self {
+        RedisCacheLayerBuilder {
+            options: RedisCacheOptions {
+                namespace: Some(RouteNamespace::Template),
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    /// Limits the number of entries and bytes of the namespace, evicting the least recently used entries when they are exceeded.
+    pub fn with_namespace_budget(self, namespace_budget: NamespaceBudget) -> Self {
+        RedisCacheLayerBuilder {
+            options: RedisCacheOptions {
+                namespace_budget,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_key_prefix(self, key_prefix: impl Into<String>) -> Self {
+        RedisCacheLayerBuilder {
+            options: RedisCacheOptions {
+                key_prefix: Some(key_prefix.into()),
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    /// Sets the pool used to read the cached responses, like a pool connected to the Redis replicas. Responses are
+    /// always saved using the main pool.
+    pub fn with_read_pool(self, read_pool: Pool<RedisConnectionManager>) -> Self {
+        RedisCacheLayerBuilder {
+            read_pool: Some(read_pool),
+            ..self
+        }
+    }
+
     pub fn build<RedisCacheResponseValue>(self) -> RedisCacheLayer<RedisCacheResponseValue> {
         RedisCacheLayer {
             redis_pool: self.redis_pool,
+            read_pool: self.read_pool,
             options: self.options,
             phantom_data: PhantomData,
         }
@@ -160,6 +320,7 @@ impl RedisCacheLayerBuilder {
 pub struct RedisCacheLayer<RedisCacheResponseValue> {
     options: RedisCacheOptions,
     redis_pool: Pool<RedisConnectionManager>,
+    read_pool: Option<Pool<RedisConnectionManager>>,
     phantom_data: PhantomData<RedisCacheResponseValue>,
 }
 
@@ -173,6 +334,7 @@ where
         RedisCacheMiddleware {
             inner,
             redis_pool: self.redis_pool.clone(),
+            read_pool: self.read_pool.clone(),
             options: self.options.clone(),
             phantom_data: PhantomData,
         }
@@ -183,6 +345,7 @@ where
 pub struct RedisCacheMiddleware<S, RedisCacheResponseValue> {
     inner: S,
     redis_pool: Pool<RedisConnectionManager>,
+    read_pool: Option<Pool<RedisConnectionManager>>,
     options: RedisCacheOptions,
     phantom_data: PhantomData<RedisCacheResponseValue>,
 }
@@ -206,23 +369,29 @@ where
     fn call(&mut self, req: Request) -> Self::Future {
         let (mut parts, body) = req.into_parts();
         let redis_pool = self.redis_pool.clone();
-        let options = self.options.clone();
+        let read_pool = self.read_pool.clone();
+        let mut options = self.options.clone();
+
+        if let Some(CacheExpirationTime(expiration_time)) = parts.extensions.get() {
+            options.expiration_time = Some(*expiration_time);
+        }
 
         let request = Request::from_parts(parts.clone(), body);
 
         let future = self.inner.call(request);
 
         Box::pin(async move {
-            let ExtractRedisKey(redis_key) = match parts.extract::<ExtractRedisKey>().await {
-                Ok(key) => key,
-                Err((_, _)) => {
+            let (redis_key, namespace) = match extract_cache_key(&mut parts, &options).await {
+                Some(cache_key) => cache_key,
+                None => {
                     let res: Response = future.await?;
 
                     return Ok(res);
                 }
             };
 
-            let mut redis_conn = match redis_pool.get().await {
+            // Cached responses are looked up using the read pool when there is one, and the main pool otherwise.
+            let mut lookup_conn = match read_pool.as_ref().unwrap_or(&redis_pool).get().await {
                 Ok(conn) => conn,
                 Err(_) => {
                     let res: Response = future.await?;
@@ -231,13 +400,35 @@ where
                 }
             };
 
-            if redis_conn.exists(&redis_key).await.unwrap_or(false) {
+            if lookup_conn.exists(&redis_key).await.unwrap_or(false) {
                 let redis_response_builder =
-                    RedisResponseBuilder::new(redis_conn, &redis_key, &options);
+                    RedisResponseBuilder::new(&mut lookup_conn, &redis_key, &options);
 
-                return Ok(redis_response_builder
+                // An entry that cannot be read (for example, because it cannot be decrypted) is considered a cache miss,
+                // so it is replaced by the handler response.
+                match redis_response_builder
                     .build::<RedisCacheResponseValue>()
-                    .await);
+                    .await
+                {
+                    Ok(res) => {
+                        if let Some(namespace) = &namespace {
+                            record_lookup(redis_pool.clone(), namespace, &redis_key, true);
+                        }
+
+                        return Ok(res);
+                    }
+                    Err(err) => {
+                        tracing::warn!(
+                            redis_key,
+                            error = %err,
+                            "Unable to read cached response, calling the handler"
+                        );
+                    }
+                }
+            }
+
+            if let Some(namespace) = &namespace {
+                record_lookup(redis_pool.clone(), namespace, &redis_key, false);
             }
 
             let res: Response = future.await?;
@@ -248,9 +439,21 @@ where
                 return Ok(res);
             }
 
+            let mut redis_conn = match read_pool {
+                Some(_) => match redis_pool.get().await {
+                    Ok(conn) => conn,
+                    Err(_) => return Ok(res),
+                },
+                None => lookup_conn,
+            };
+
             // It builds the response from the handler and saves it to Redis before returning it.
-            let handler_response_builder =
-                HandlerResponseBuilder::new(redis_conn, &redis_key, &options);
+            let handler_response_builder = HandlerResponseBuilder::new(
+                &mut redis_conn,
+                &redis_key,
+                namespace.as_deref(),
+                &options,
+            );
 
             Ok(handler_response_builder
                 .build::<RedisCacheResponseValue>(res)
@@ -259,16 +462,44 @@ where
     }
 }
 
+// Extracts the Redis key of the request, and its namespace when the layer uses one.
+async fn extract_cache_key(
+    parts: &mut Parts,
+    options: &RedisCacheOptions,
+) -> Option<(String, Option<String>)> {
+    parts.extensions.insert(options.key_normalization.clone());
+
+    if let Some(key_prefix) = &options.key_prefix {
+        parts.extensions.insert(KeyPrefix(key_prefix.clone()));
+    }
+
+    match &options.namespace {
+        Some(namespace) => {
+            parts.extensions.insert(namespace.clone());
+
+            let ExtractRouteKey { namespace, key } =
+                parts.extract::<ExtractRouteKey>().await.ok()?;
+
+            Some((key, Some(namespace)))
+        }
+        None => {
+            let ExtractRedisKey(key) = parts.extract::<ExtractRedisKey>().await.ok()?;
+
+            Some((key, None))
+        }
+    }
+}
+
 // Builds the middleware response based on the data coming from Redis cache
-struct RedisResponseBuilder<'a> {
-    redis_conn: PooledConnection<'a, RedisConnectionManager>,
+struct RedisResponseBuilder<'a, 'b> {
+    redis_conn: &'a mut PooledConnection<'b, RedisConnectionManager>,
     redis_key: &'a str,
     options: &'a RedisCacheOptions,
 }
 
-impl<'a> RedisResponseBuilder<'a> {
+impl<'a, 'b> RedisResponseBuilder<'a, 'b> {
     fn new(
-        redis_conn: PooledConnection<'a, RedisConnectionManager>,
+        redis_conn: &'a mut PooledConnection<'b, RedisConnectionManager>,
         redis_key: &'a str,
         options: &'a RedisCacheOptions,
     ) -> Self {
@@ -301,19 +532,21 @@ impl<'a> RedisResponseBuilder<'a> {
         RedisCacheResponseValue: DeserializeOwned + FromRedisValue + Serialize + Debug + Send + Sync,
     >(
         mut self,
-    ) -> Response {
+    ) -> Result<Response, RedisUtilsError> {
         let redis_path = self
             .options
             .path
             .clone()
             .unwrap_or(DEFAULT_REDIS_PATH.to_string());
+        let raw_json: String = self
+            .redis_conn
+            .json_get(self.redis_key, &redis_path)
+            .await
+            .map_err(RedisUtilsError::Redis)?;
+
+        let codec = CacheCodec::new(self.options.cipher.clone());
         let res: RedisCacheResponseValue =
-            match self.redis_conn.json_get(self.redis_key, redis_path).await {
-                Ok(json) => json,
-                Err(err) => {
-                    return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
-                }
-            };
+            codec.decode_redis_json(self.redis_key, &redis_path, &raw_json)?;
 
         let mut headers: HeaderMap<HeaderValue> = HeaderMap::new();
 
@@ -321,27 +554,30 @@ impl<'a> RedisResponseBuilder<'a> {
 
         self.set_cache_headers(&mut headers, expiration_time);
 
-        (StatusCode::OK, headers, Json(res)).into_response()
+        Ok((StatusCode::OK, headers, Json(res)).into_response())
     }
 }
 
 // Builds the middleware response based on the data coming from a handler.
 // It saves the response within redis before sending it back through the middleware chain.
-struct HandlerResponseBuilder<'a> {
-    redis_conn: PooledConnection<'a, RedisConnectionManager>,
+struct HandlerResponseBuilder<'a, 'b> {
+    redis_conn: &'a mut PooledConnection<'b, RedisConnectionManager>,
     redis_key: &'a str,
+    namespace: Option<&'a str>,
     options: &'a RedisCacheOptions,
 }
 
-impl<'a> HandlerResponseBuilder<'a> {
+impl<'a, 'b> HandlerResponseBuilder<'a, 'b> {
     fn new(
-        redis_conn: PooledConnection<'a, RedisConnectionManager>,
+        redis_conn: &'a mut PooledConnection<'b, RedisConnectionManager>,
         redis_key: &'a str,
+        namespace: Option<&'a str>,
         options: &'a RedisCacheOptions,
     ) -> Self {
         HandlerResponseBuilder {
             redis_conn,
             redis_key,
+            namespace,
             options,
         }
     }
@@ -361,9 +597,12 @@ impl<'a> HandlerResponseBuilder<'a> {
             .clone()
             .unwrap_or(DEFAULT_REDIS_PATH.to_string());
 
+        let codec = CacheCodec::new(self.options.cipher.clone());
+        let document = codec.encode(key, &value)?;
+
         // Save response to Redis
         self.redis_conn
-            .json_set::<&str, &str, RedisCacheResponseValue, ()>(key, &redis_path, &value)
+            .json_set::<&str, &str, serde_json::Value, ()>(key, &redis_path, &document)
             .await
             .map_err(RedisUtilsError::Redis)?;
 
@@ -375,6 +614,26 @@ impl<'a> HandlerResponseBuilder<'a> {
                 .map_err(RedisUtilsError::Redis)?;
         }
 
+        if let Some(namespace) = self.namespace {
+            let evicted = record_entry(
+                &mut **self.redis_conn,
+                namespace,
+                key,
+                document.to_string().len(),
+                expiration_time,
+                &self.options.namespace_budget,
+            )
+            .await?;
+
+            if evicted > 0 {
+                tracing::debug!(
+                    namespace,
+                    evicted,
+                    "Evicted cached responses from namespace"
+                );
+            }
+        }
+
         Ok(())
     }
 
@@ -386,19 +645,14 @@ impl<'a> HandlerResponseBuilder<'a> {
     ) -> Response {
         let (parts, body) = res.into_parts();
 
-        let bytes = match body.collect().await {
-            Ok(collected) => collected.to_bytes(),
-            Err(err) => {
-                return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
-            }
-        };
-        let res_json_str = match String::from_utf8(bytes.to_vec()) {
-            Ok(json_str) => json_str,
+        let bytes = match capture_body(body, self.options.max_body_size, self.redis_key).await {
+            Ok(CapturedBody::Buffered(bytes)) => bytes,
+            Ok(CapturedBody::Streamed(body)) => return Response::from_parts(parts, body),
             Err(err) => {
                 return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
             }
         };
-        let res_body: RedisCacheResponseValue = match serde_json::from_str(&res_json_str) {
+        let res_body: RedisCacheResponseValue = match serde_json::from_slice(&bytes) {
             Ok(body) => body,
             Err(err) => {
                 return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
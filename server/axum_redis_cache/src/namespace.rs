@@ -0,0 +1,405 @@
+//! Cache namespaces group the entries saved by the RedisCacheLayer for the same route, so they can be inspected and invalidated together
+//! without scanning the whole Redis keyspace.
+//!
+//! When the RedisCacheLayer is configured with a namespace, the key of each entry is built from the namespace, the values of the path
+//! parameters and the query parameters, instead of the whole path. For example, the following request to the route
+//! `/repositories/:repo/good-first-issues`, using the namespace `gfi`:
+//!
+//! GET <https://domain.com/repositories/cube/good-first-issues?owner=cube-js&page=1>
+//!
+//! is saved under the key:
+//!
+//! {gfi}:cube:owner=cube-js:page=1
+//!
+//! The namespace can be named with `with_namespace`, or derived from the route template with `with_route_namespace`. In that case,
+//! the path segments of the template are joined with a colon and the path parameters are written as `$name`. For the previous route,
+//! the namespace would be `repositories:$repo:good-first-issues`.
+//!
+//! Every namespace keeps an index with its entries and when they expire (`{namespace}#index`), when they were last read (`{namespace}#lru`),
+//! their size (`{namespace}#sizes`) and the stats of the namespace (`{namespace}#stats`). The namespace is used as a hash tag, so all the keys
+//! of a namespace are saved on the same Redis Cluster node.
+//!
+//! A namespace can be limited to a max number of entries and a max number of bytes with NamespaceBudget. When a new entry exceeds any of
+//! these limits, the least recently used entries of the namespace are evicted, so a client requesting many different pages can only evict
+//! the entries of the same namespace, and the pages that are read often stay cached. The size of an entry is the size of its key and its
+//! JSON document, which is an approximation of the memory used by Redis.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use axum_redis_cache::namespace::CacheNamespace;
+//! # use axum_redis_cache::{errors::RedisUtilsError, middlewares::RedisCacheLayerBuilder, namespace::NamespaceBudget, pool::RedisConnectionManager};
+//! #
+//! # type GoodFirstIssuesResponse = serde_json::Value;
+//! #
+//! # async fn run() -> Result<(), RedisUtilsError> {
+//! # let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+//! # let redis_pool = bb8::Pool::builder().build_unchecked(redis_manager);
+//!
+//! let layer = RedisCacheLayerBuilder::new(redis_pool.clone())
+//!     .with_namespace("gfi")
+//!     .with_namespace_budget(NamespaceBudget {
+//!         max_keys: Some(1000),
+//!         max_bytes: Some(50 * 1024 * 1024),
+//!     })
+//!     .build::<GoodFirstIssuesResponse>();
+//!
+//! let namespace = CacheNamespace::new(redis_pool.clone(), "gfi");
+//!
+//! let stats = namespace.stats().await?;
+//! let deleted_entries = namespace.invalidate().await?;
+//! # Ok(())
+//! # }
+//! ```
+use bb8::Pool;
+use redis::Script;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{
+    errors::RedisUtilsError, extractors::escape_key_part, pool::RedisConnectionManager,
+    tasks::spawn_background_task,
+};
+
+const NAMESPACE_INDEX: &str = "index";
+const NAMESPACE_LRU: &str = "lru";
+const NAMESPACE_SIZES: &str = "sizes";
+const NAMESPACE_STATS: &str = "stats";
+const HITS_FIELD: &str = "hits";
+const MISSES_FIELD: &str = "misses";
+const BYTES_FIELD: &str = "bytes";
+const EVICTIONS_FIELD: &str = "evictions";
+
+// The entries share the hash tag of the namespace keys, so they are on the same node even though they are not declared
+// as keys of the scripts.
+//
+// KEYS[1]: index, KEYS[2]: lru, KEYS[3]: sizes, KEYS[4]: stats. ARGV[1]: entry key, ARGV[2]: expiration score, ARGV[3]: now in
+// milliseconds, ARGV[4]: entry size, ARGV[5]: max keys, ARGV[6]: max bytes. A limit of 0 means there is no limit.
+const RECORD_ENTRY_SCRIPT: &str = r#"
+local now = tonumber(ARGV[3])
+local max_keys = tonumber(ARGV[5])
+local max_bytes = tonumber(ARGV[6])
+
+local function remove_entry(key)
+    local size = tonumber(redis.call('HGET', KEYS[3], key)) or 0
+
+    redis.call('ZREM', KEYS[1], key)
+    redis.call('ZREM', KEYS[2], key)
+    redis.call('HDEL', KEYS[3], key)
+    redis.call('HINCRBY', KEYS[4], 'bytes', -size)
+end
+
+for _, key in ipairs(redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', '(' .. now)) do
+    remove_entry(key)
+end
+
+local previous_size = tonumber(redis.call('HGET', KEYS[3], ARGV[1])) or 0
+
+redis.call('HSET', KEYS[3], ARGV[1], ARGV[4])
+redis.call('HINCRBY', KEYS[4], 'bytes', tonumber(ARGV[4]) - previous_size)
+redis.call('ZADD', KEYS[1], ARGV[2], ARGV[1])
+redis.call('ZADD', KEYS[2], now, ARGV[1])
+
+local evicted = 0
+
+while true do
+    local keys = redis.call('ZCARD', KEYS[1])
+    local bytes = tonumber(redis.call('HGET', KEYS[4], 'bytes')) or 0
+
+    if not ((max_keys > 0 and keys > max_keys) or (max_bytes > 0 and bytes > max_bytes)) then
+        break
+    end
+
+    local oldest = redis.call('ZRANGE', KEYS[2], 0, 0)
+
+    if #oldest == 0 then
+        break
+    end
+
+    redis.call('DEL', oldest[1])
+    remove_entry(oldest[1])
+    evicted = evicted + 1
+end
+
+if evicted > 0 then
+    redis.call('HINCRBY', KEYS[4], 'evictions', evicted)
+end
+
+return evicted
+"#;
+
+// KEYS[1]: index, KEYS[2]: lru, KEYS[3]: sizes, KEYS[4]: stats.
+const INVALIDATE_SCRIPT: &str = r#"
+local keys = redis.call('ZRANGE', KEYS[1], 0, -1)
+local deleted = 0
+
+for i = 1, #keys, 500 do
+    deleted = deleted + redis.call('DEL', unpack(keys, i, math.min(i + 499, #keys)))
+end
+
+redis.call('DEL', KEYS[1], KEYS[2], KEYS[3])
+redis.call('HDEL', KEYS[4], 'bytes')
+
+return deleted
+"#;
+
+/// How the namespace of the cached entries of a route is chosen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RouteNamespace {
+    /// Derives the namespace from the route template.
+    Template,
+    /// Uses the given name as namespace.
+    Named(String),
+}
+
+/// The limits of a namespace. When they are exceeded, the least recently used entries of the namespace are evicted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NamespaceBudget {
+    /// The max number of entries of the namespace.
+    pub max_keys: Option<u64>,
+    /// The max number of bytes used by the entries of the namespace.
+    pub max_bytes: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct NamespaceStats {
+    /// The number of entries that did not expire yet.
+    pub entries: u64,
+    /// The number of bytes used by the entries, including the ones that expired since the last entry was saved.
+    pub bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// The number of entries evicted to keep the namespace within its budget.
+    pub evictions: u64,
+}
+
+/// A handle to inspect and invalidate the entries of a namespace.
+#[derive(Clone, Debug)]
+pub struct CacheNamespace {
+    redis_pool: Pool<RedisConnectionManager>,
+    name: String,
+}
+
+impl CacheNamespace {
+    pub fn new(redis_pool: Pool<RedisConnectionManager>, name: impl Into<String>) -> Self {
+        CacheNamespace {
+            redis_pool,
+            name: name.into(),
+        }
+    }
+
+    /// Creates the handle of the namespace derived from a route template, like `/repositories/:repo/good-first-issues`.
+    pub fn from_route(redis_pool: Pool<RedisConnectionManager>, route: &str) -> Self {
+        CacheNamespace::new(redis_pool, route_namespace(route))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the key used by the namespace to save some data about itself, like `{namespace}#name`. These keys are not
+    /// part of the entries of the namespace, so they are not removed when the namespace is invalidated.
+    pub fn meta_key(&self, name: &str) -> String {
+        namespace_meta_key(&self.name, name)
+    }
+
+    /// Returns the keys of the entries of the namespace that did not expire yet.
+    pub async fn keys(&self) -> Result<Vec<String>, RedisUtilsError> {
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RedisUtilsError::RedisConnection)?;
+
+        redis::cmd("ZRANGEBYSCORE")
+            .arg(self.meta_key(NAMESPACE_INDEX))
+            .arg(get_now_millis())
+            .arg("+inf")
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(RedisUtilsError::Redis)
+    }
+
+    pub async fn stats(&self) -> Result<NamespaceStats, RedisUtilsError> {
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RedisUtilsError::RedisConnection)?;
+
+        type StatsFields = (Option<u64>, Option<u64>, Option<u64>, Option<u64>);
+
+        let (entries, (bytes, hits, misses, evictions)): (u64, StatsFields) = redis::pipe()
+            .cmd("ZCOUNT")
+            .arg(self.meta_key(NAMESPACE_INDEX))
+            .arg(get_now_millis())
+            .arg("+inf")
+            .cmd("HMGET")
+            .arg(self.meta_key(NAMESPACE_STATS))
+            .arg(BYTES_FIELD)
+            .arg(HITS_FIELD)
+            .arg(MISSES_FIELD)
+            .arg(EVICTIONS_FIELD)
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(RedisUtilsError::Redis)?;
+
+        Ok(NamespaceStats {
+            entries,
+            bytes: bytes.unwrap_or(0),
+            hits: hits.unwrap_or(0),
+            misses: misses.unwrap_or(0),
+            evictions: evictions.unwrap_or(0),
+        })
+    }
+
+    /// Deletes all the entries of the namespace. Returns the number of deleted entries.
+    pub async fn invalidate(&self) -> Result<u64, RedisUtilsError> {
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(RedisUtilsError::RedisConnection)?;
+
+        Script::new(INVALIDATE_SCRIPT)
+            .key(self.meta_key(NAMESPACE_INDEX))
+            .key(self.meta_key(NAMESPACE_LRU))
+            .key(self.meta_key(NAMESPACE_SIZES))
+            .key(self.meta_key(NAMESPACE_STATS))
+            .invoke_async(&mut *redis_conn)
+            .await
+            .map_err(RedisUtilsError::Redis)
+    }
+}
+
+/// Derives the namespace of a route template. For example, `/repositories/:repo/good-first-issues` is converted into
+/// `repositories:$repo:good-first-issues`.
+pub fn route_namespace(route: &str) -> String {
+    route
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| match segment.strip_prefix([':', '*']) {
+            Some(param) => format!("${}", escape_key_part(param)),
+            None => escape_key_part(segment),
+        })
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+// The namespace is wrapped in braces to be used as hash tag.
+pub(crate) fn namespace_key(namespace: &str) -> String {
+    format!("{{{}}}", namespace)
+}
+
+/// Returns the key used by a namespace to save some data about itself, like `{namespace}#name`.
+pub fn namespace_meta_key(namespace: &str, name: &str) -> String {
+    format!("{}#{}", namespace_key(namespace), name)
+}
+
+// Adds a saved entry to the index of its namespace, removing the entries that already expired and evicting the least
+// recently used ones when the namespace exceeds its budget. Returns the number of evicted entries.
+pub(crate) async fn record_entry<C: redis::aio::ConnectionLike>(
+    redis_conn: &mut C,
+    namespace: &str,
+    redis_key: &str,
+    size: usize,
+    expiration_time: Option<i64>,
+    budget: &NamespaceBudget,
+) -> Result<u64, RedisUtilsError> {
+    let now = get_now_millis();
+    let score = match expiration_time {
+        Some(expiration_time) => (now + expiration_time.max(0) as u64 * 1000).to_string(),
+        None => String::from("+inf"),
+    };
+
+    Script::new(RECORD_ENTRY_SCRIPT)
+        .key(namespace_meta_key(namespace, NAMESPACE_INDEX))
+        .key(namespace_meta_key(namespace, NAMESPACE_LRU))
+        .key(namespace_meta_key(namespace, NAMESPACE_SIZES))
+        .key(namespace_meta_key(namespace, NAMESPACE_STATS))
+        .arg(redis_key)
+        .arg(score)
+        .arg(now)
+        .arg(size + redis_key.len())
+        .arg(budget.max_keys.unwrap_or(0))
+        .arg(budget.max_bytes.unwrap_or(0))
+        .invoke_async(redis_conn)
+        .await
+        .map_err(RedisUtilsError::Redis)
+}
+
+// Counts a hit or a miss of the namespace. A hit also marks the entry as recently used. It runs in the background, so it does
+// not delay the response.
+pub(crate) fn record_lookup(
+    redis_pool: Pool<RedisConnectionManager>,
+    namespace: &str,
+    redis_key: &str,
+    hit: bool,
+) {
+    let stats_key = namespace_meta_key(namespace, NAMESPACE_STATS);
+    let lru_key = namespace_meta_key(namespace, NAMESPACE_LRU);
+    let redis_key = redis_key.to_string();
+    let field = if hit { HITS_FIELD } else { MISSES_FIELD };
+
+    spawn_background_task(async move {
+        let mut pipeline = redis::pipe();
+
+        pipeline
+            .cmd("HINCRBY")
+            .arg(&stats_key)
+            .arg(field)
+            .arg(1)
+            .ignore();
+
+        if hit {
+            pipeline
+                .cmd("ZADD")
+                .arg(&lru_key)
+                .arg("XX")
+                .arg(get_now_millis())
+                .arg(&redis_key)
+                .ignore();
+        }
+
+        let result = match redis_pool.get().await {
+            Ok(mut redis_conn) => pipeline
+                .query_async::<()>(&mut *redis_conn)
+                .await
+                .map_err(RedisUtilsError::Redis),
+            Err(err) => Err(RedisUtilsError::RedisConnection(err)),
+        };
+
+        if let Err(err) = result {
+            tracing::debug!(redis_key = stats_key, error = %err, "Unable to update namespace stats");
+        }
+    });
+}
+
+fn get_now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_namespace() {
+        assert_eq!(
+            route_namespace("/api/v1/github/repositories/:repo/good-first-issues"),
+            "api:v1:github:repositories:$repo:good-first-issues"
+        );
+        assert_eq!(route_namespace("/files/*path"), "files:$path");
+        assert_eq!(route_namespace("/"), "");
+    }
+
+    #[test]
+    fn test_namespace_keys_share_hash_tag() {
+        assert_eq!(namespace_key("gfi"), "{gfi}");
+        assert_eq!(namespace_meta_key("gfi", NAMESPACE_INDEX), "{gfi}#index");
+        assert_eq!(namespace_meta_key("gfi", NAMESPACE_STATS), "{gfi}#stats");
+    }
+}
@@ -0,0 +1,344 @@
+//! RedisConnectionManager is a bb8 connection manager that supports the different ways Redis can be deployed:
+//!
+//! - Standalone: a single Redis server, using one url like `redis://127.0.0.1:6379`.
+//! - Cluster: a Redis Cluster, using the urls of one or more of its nodes.
+//! - Sentinel: a primary/replica set managed by Redis Sentinel, using the urls of the sentinels and the name of the service.
+//!
+//! TLS is enabled by using `rediss://` urls. When the sentinels are reached through TLS, the primary and the replicas are reached through
+//! TLS as well.
+//!
+//! Cluster and Sentinel managers can be created for the primary or the replica nodes. A pool built with a replica manager can be
+//! provided to the RedisCacheLayer, so the cached responses are read from the replicas while the writes still go to the primary.
+//!
+//! The connections of a Sentinel primary manager check the role of their node when they are checked out of the pool, so the connections
+//! to the old primary are replaced after a failover.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use axum_redis_cache::pool::{RedisConnectionManager, RedisNodeRole};
+//! # use axum_redis_cache::middlewares::RedisCacheLayerBuilder;
+//! #
+//! # type UsersResponse = serde_json::Value;
+//! #
+//! # async fn run() {
+//!
+//! let urls = vec!["redis://sentinel-1:26379", "redis://sentinel-2:26379"];
+//!
+//! let redis_manager = RedisConnectionManager::sentinel(urls.clone(), "mymaster", RedisNodeRole::Primary).unwrap();
+//! let redis_pool = bb8::Pool::builder().build(redis_manager).await.unwrap();
+//!
+//! let redis_replica_manager = RedisConnectionManager::sentinel(urls, "mymaster", RedisNodeRole::Replica).unwrap();
+//! let redis_replica_pool = bb8::Pool::builder().build(redis_replica_manager).await.unwrap();
+//!
+//! let layer = RedisCacheLayerBuilder::new(redis_pool)
+//!     .with_read_pool(redis_replica_pool)
+//!     .build::<UsersResponse>();
+//! # }
+//! ```
+use axum::async_trait;
+use redis::{
+    aio::{ConnectionLike, MultiplexedConnection},
+    cluster::{ClusterClient, ClusterClientBuilder},
+    cluster_async::ClusterConnection,
+    sentinel::{SentinelClient, SentinelNodeConnectionInfo, SentinelServerType},
+    Client, Cmd, ConnectionAddr, ErrorKind, FromRedisValue, IntoConnectionInfo, Pipeline,
+    RedisError, RedisFuture, TlsMode, Value,
+};
+use std::{fmt::Debug, sync::Arc};
+use tokio::sync::Mutex;
+
+/// The kind of node the connections of a RedisConnectionManager are made to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RedisNodeRole {
+    Primary,
+    Replica,
+}
+
+enum RedisClient {
+    Standalone(Client),
+    Cluster(ClusterClient),
+    // SentinelClient needs a mutable reference to create connections, as it caches the connections to the sentinels.
+    Sentinel(Mutex<SentinelClient>),
+}
+
+#[derive(Clone)]
+pub struct RedisConnectionManager {
+    client: Arc<RedisClient>,
+    description: String,
+    // Sentinel connections are made to the node that was the primary when they were created. After a failover, that node
+    // becomes a replica but still answers PING, so the role of the connections is checked before using them.
+    check_primary_role: bool,
+}
+
+impl RedisConnectionManager {
+    /// Creates a manager for a standalone Redis server.
+    pub fn new<T: IntoConnectionInfo>(info: T) -> Result<Self, RedisError> {
+        let client = Client::open(info.into_connection_info()?)?;
+
+        Ok(RedisConnectionManager {
+            client: Arc::new(RedisClient::Standalone(client)),
+            description: String::from("standalone"),
+            check_primary_role: false,
+        })
+    }
+
+    /// Creates a manager for a Redis Cluster. When the role is Replica, read-only commands are sent to the replicas.
+    pub fn cluster<T: IntoConnectionInfo>(
+        nodes: Vec<T>,
+        role: RedisNodeRole,
+    ) -> Result<Self, RedisError> {
+        let mut builder = ClusterClientBuilder::new(nodes);
+
+        if role == RedisNodeRole::Replica {
+            builder = builder.read_from_replicas();
+        }
+
+        Ok(RedisConnectionManager {
+            client: Arc::new(RedisClient::Cluster(builder.build()?)),
+            description: format!("cluster ({:?})", role),
+            check_primary_role: false,
+        })
+    }
+
+    /// Creates a manager for a primary/replica set managed by Redis Sentinel. The credentials and the database of the
+    /// first sentinel url are used to connect to the primary and the replicas.
+    pub fn sentinel<T: IntoConnectionInfo>(
+        sentinels: Vec<T>,
+        service_name: impl Into<String>,
+        role: RedisNodeRole,
+    ) -> Result<Self, RedisError> {
+        let sentinels = sentinels
+            .into_iter()
+            .map(|sentinel| sentinel.into_connection_info())
+            .collect::<Result<Vec<_>, RedisError>>()?;
+
+        let node_connection_info = match sentinels.first() {
+            Some(sentinel) => SentinelNodeConnectionInfo {
+                tls_mode: get_tls_mode(&sentinel.addr),
+                redis_connection_info: Some(sentinel.redis.clone()),
+            },
+            None => {
+                return Err(RedisError::from((
+                    ErrorKind::InvalidClientConfig,
+                    "At least one sentinel is required",
+                )));
+            }
+        };
+        let service_name = service_name.into();
+        let server_type = match role {
+            RedisNodeRole::Primary => SentinelServerType::Master,
+            RedisNodeRole::Replica => SentinelServerType::Replica,
+        };
+
+        let client = SentinelClient::build(
+            sentinels,
+            service_name.clone(),
+            Some(node_connection_info),
+            server_type,
+        )?;
+
+        Ok(RedisConnectionManager {
+            client: Arc::new(RedisClient::Sentinel(Mutex::new(client))),
+            description: format!("sentinel {} ({:?})", service_name, role),
+            check_primary_role: role == RedisNodeRole::Primary,
+        })
+    }
+}
+
+impl Debug for RedisConnectionManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisConnectionManager")
+            .field("client", &self.description)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl bb8::ManageConnection for RedisConnectionManager {
+    type Connection = RedisConnection;
+    type Error = RedisError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        match self.client.as_ref() {
+            RedisClient::Standalone(client) => client
+                .get_multiplexed_async_connection()
+                .await
+                .map(RedisConnection::Single),
+            RedisClient::Cluster(client) => client
+                .get_async_connection()
+                .await
+                .map(RedisConnection::Cluster),
+            RedisClient::Sentinel(client) => client
+                .lock()
+                .await
+                .get_async_connection()
+                .await
+                .map(RedisConnection::Single),
+        }
+    }
+
+    async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        let pong: String = redis::cmd("PING").query_async(conn).await?;
+
+        if pong != "PONG" {
+            return Err((ErrorKind::ResponseError, "ping request").into());
+        }
+
+        if self.check_primary_role {
+            let role: Value = redis::cmd("ROLE").query_async(conn).await?;
+
+            check_primary_role(&role)?;
+        }
+
+        Ok(())
+    }
+
+    fn has_broken(&self, _: &mut Self::Connection) -> bool {
+        false
+    }
+}
+
+/// A connection to any of the Redis deployments supported by RedisConnectionManager. Standalone and Sentinel connections are
+/// multiplexed connections to a single node, while Cluster connections route every command to the right node.
+pub enum RedisConnection {
+    Single(MultiplexedConnection),
+    Cluster(ClusterConnection),
+}
+
+impl ConnectionLike for RedisConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            RedisConnection::Single(conn) => conn.req_packed_command(cmd),
+            RedisConnection::Cluster(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            RedisConnection::Single(conn) => conn.req_packed_commands(cmd, offset, count),
+            RedisConnection::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            RedisConnection::Single(conn) => conn.get_db(),
+            RedisConnection::Cluster(conn) => conn.get_db(),
+        }
+    }
+}
+
+// The first element of the ROLE reply is the role of the node: master, slave or sentinel.
+fn check_primary_role(role: &Value) -> Result<(), RedisError> {
+    let role = match role {
+        Value::Array(values) => values.first().map(String::from_redis_value),
+        _ => None,
+    };
+
+    match role {
+        Some(Ok(role)) if role == "master" => Ok(()),
+        _ => Err((ErrorKind::ReadOnly, "the node is not the primary anymore").into()),
+    }
+}
+
+fn get_tls_mode(addr: &ConnectionAddr) -> Option<TlsMode> {
+    match addr {
+        ConnectionAddr::TcpTls { insecure: true, .. } => Some(TlsMode::Insecure),
+        ConnectionAddr::TcpTls { .. } => Some(TlsMode::Secure),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_tls_mode_from_urls() {
+        let plain = "redis://127.0.0.1:26379".into_connection_info().unwrap();
+        let secure = "rediss://127.0.0.1:26379".into_connection_info().unwrap();
+        let insecure = "rediss://127.0.0.1:26379/#insecure"
+            .into_connection_info()
+            .unwrap();
+
+        assert!(get_tls_mode(&plain.addr).is_none());
+        assert!(get_tls_mode(&secure.addr) == Some(TlsMode::Secure));
+        assert!(get_tls_mode(&insecure.addr) == Some(TlsMode::Insecure));
+    }
+
+    #[test]
+    fn test_sentinel_requires_at_least_one_sentinel() {
+        let result = RedisConnectionManager::sentinel(
+            Vec::<String>::new(),
+            "mymaster",
+            RedisNodeRole::Primary,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_only_primary_nodes_pass_the_role_check() {
+        let primary = Value::Array(vec![
+            Value::BulkString(b"master".to_vec()),
+            Value::Int(3129659),
+            Value::Array(vec![]),
+        ]);
+        let replica = Value::Array(vec![
+            Value::BulkString(b"slave".to_vec()),
+            Value::BulkString(b"127.0.0.1".to_vec()),
+            Value::Int(9538),
+            Value::BulkString(b"connected".to_vec()),
+            Value::Int(3129659),
+        ]);
+
+        assert!(check_primary_role(&primary).is_ok());
+        assert_eq!(
+            check_primary_role(&replica).unwrap_err().kind(),
+            ErrorKind::ReadOnly
+        );
+        assert!(check_primary_role(&Value::Nil).is_err());
+    }
+
+    #[test]
+    fn test_sentinel_primary_connections_check_their_role() {
+        let primary = RedisConnectionManager::sentinel(
+            vec!["redis://127.0.0.1:26379"],
+            "mymaster",
+            RedisNodeRole::Primary,
+        )
+        .unwrap();
+        let replica = RedisConnectionManager::sentinel(
+            vec!["redis://127.0.0.1:26379"],
+            "mymaster",
+            RedisNodeRole::Replica,
+        )
+        .unwrap();
+
+        assert!(primary.check_primary_role);
+        assert!(!replica.check_primary_role);
+        assert!(
+            !RedisConnectionManager::new("redis://127.0.0.1:6379")
+                .unwrap()
+                .check_primary_role
+        );
+    }
+
+    #[test]
+    fn test_cluster_manager_description() {
+        let manager =
+            RedisConnectionManager::cluster(vec!["redis://127.0.0.1:7000"], RedisNodeRole::Replica)
+                .unwrap();
+
+        assert_eq!(
+            format!("{:?}", manager),
+            "RedisConnectionManager { client: \"cluster (Replica)\" }"
+        );
+    }
+}
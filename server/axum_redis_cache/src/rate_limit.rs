@@ -0,0 +1,743 @@
+//! RateLimitLayer is a middleware that limits the number of requests a client can make, keeping the state of every client on Redis.
+//!
+//! Every algorithm is implemented as a Lua script, so checking and updating the state of a client is atomic, even when the
+//! requests are handled by different instances of the server:
+//!
+//! - FixedWindow: allows `limit` requests on every window of time. The window starts with the first request of the client.
+//! - SlidingWindow: allows `limit` requests on the last `window` of time, counting the timestamp of every request.
+//! - TokenBucket: allows bursts of up to `capacity` requests, refilling the bucket at `refill_rate` tokens per second.
+//!
+//! Clients are identified by their IP address by default. They can also be identified by an API key sent on a header, or by any
+//! custom key extracted from the request.
+//!
+//! Every response contains the `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers. When a client exceeds the limit,
+//! the middleware returns a 429 Too Many Requests response with a `Retry-After` header, without calling the handler.
+//!
+//! When the client key cannot be extracted or Redis is not available, the request is not limited.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use axum::{http::HeaderName, routing::get, Router};
+//! use axum_redis_cache::rate_limit::{RateLimitAlgorithm, RateLimitKey, RateLimitLayerBuilder};
+//! use std::time::Duration;
+//! # use axum_redis_cache::{errors::RedisUtilsError, pool::RedisConnectionManager};
+//! #
+//! # async fn handler() {}
+//! #
+//! # fn main() -> Result<(), RedisUtilsError> {
+//! # let redis_manager = RedisConnectionManager::new("redis://127.0.0.1:6379").unwrap();
+//! # let redis_pool = bb8::Pool::builder().build_unchecked(redis_manager);
+//!
+//! let app: Router = Router::new()
+//!     .route("/api/v1/users", get(handler))
+//!     .layer(
+//!         RateLimitLayerBuilder::new(
+//!             redis_pool.clone(),
+//!             RateLimitAlgorithm::SlidingWindow {
+//!                 limit: 100,
+//!                 window: Duration::from_secs(60),
+//!             },
+//!         )
+//!         .with_key(RateLimitKey::ApiKey(HeaderName::from_static("x-api-key")))
+//!         .build()?,
+//!     );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The ClientIp key uses the address of the connection, so the router needs to be served with
+//! `into_make_service_with_connect_info::<SocketAddr>()`. When the server runs behind a proxy, ForwardedClientIp uses
+//! the last address of the `X-Forwarded-For` header instead, which is the one appended by the proxy. The addresses before it are
+//! provided by the client, so they can be spoofed.
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{request::Parts, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bb8::Pool;
+use futures_util::future::BoxFuture;
+use redis::Script;
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Debug,
+    net::SocketAddr,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tower::{Layer, Service};
+
+use super::{errors::RedisUtilsError, pool::RedisConnectionManager};
+
+const DEFAULT_RATE_LIMIT_PREFIX: &str = "rate_limit";
+const RATE_LIMIT_LIMIT_HEADER: &str = "ratelimit-limit";
+const RATE_LIMIT_REMAINING_HEADER: &str = "ratelimit-remaining";
+const RATE_LIMIT_RESET_HEADER: &str = "ratelimit-reset";
+const RETRY_AFTER_HEADER: &str = "retry-after";
+
+// KEYS[1]: counter key. ARGV[1]: limit, ARGV[2]: window in milliseconds.
+const FIXED_WINDOW_SCRIPT: &str = r#"
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+
+local count = redis.call('INCR', KEYS[1])
+local ttl = redis.call('PTTL', KEYS[1])
+
+if ttl < 0 then
+    redis.call('PEXPIRE', KEYS[1], window)
+    ttl = window
+end
+
+if count > limit then
+    return {0, 0, ttl, ttl}
+end
+
+return {1, limit - count, ttl, 0}
+"#;
+
+// KEYS[1]: sorted set with the timestamps of the requests. ARGV[1]: limit, ARGV[2]: window in milliseconds,
+// ARGV[3]: unique id of the request.
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+
+local count = redis.call('ZCARD', KEYS[1])
+local allowed = 0
+
+if count < limit then
+    redis.call('ZADD', KEYS[1], now, ARGV[3])
+    redis.call('PEXPIRE', KEYS[1], window)
+    count = count + 1
+    allowed = 1
+end
+
+local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+local reset = window
+
+if oldest[2] then
+    reset = tonumber(oldest[2]) + window - now
+end
+
+if allowed == 1 then
+    return {1, limit - count, reset, 0}
+end
+
+return {0, 0, reset, reset}
+"#;
+
+// KEYS[1]: hash with the tokens and the last refill time. ARGV[1]: capacity, ARGV[2]: refill rate in tokens per second.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated_at')
+local tokens = tonumber(bucket[1]) or capacity
+local updated_at = tonumber(bucket[2]) or now
+
+tokens = math.min(capacity, tokens + (now - updated_at) * rate / 1000)
+
+local allowed = 0
+local retry_after = 0
+
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    retry_after = math.ceil((1 - tokens) * 1000 / rate)
+end
+
+local reset = math.ceil((capacity - tokens) * 1000 / rate)
+
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated_at', now)
+redis.call('PEXPIRE', KEYS[1], math.max(reset, 1))
+
+return {allowed, math.floor(tokens), reset, retry_after}
+"#;
+
+#[derive(Clone, Debug)]
+pub enum RateLimitAlgorithm {
+    FixedWindow { limit: u64, window: Duration },
+    SlidingWindow { limit: u64, window: Duration },
+    TokenBucket { capacity: u64, refill_rate: f64 },
+}
+
+impl RateLimitAlgorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            RateLimitAlgorithm::FixedWindow { .. } => "fixed_window",
+            RateLimitAlgorithm::SlidingWindow { .. } => "sliding_window",
+            RateLimitAlgorithm::TokenBucket { .. } => "token_bucket",
+        }
+    }
+
+    fn limit(&self) -> u64 {
+        match self {
+            RateLimitAlgorithm::FixedWindow { limit, .. }
+            | RateLimitAlgorithm::SlidingWindow { limit, .. } => *limit,
+            RateLimitAlgorithm::TokenBucket { capacity, .. } => *capacity,
+        }
+    }
+
+    // The scripts divide by the window and the refill rate, and a bucket without capacity would never allow a request.
+    fn validate(&self) -> Result<(), RedisUtilsError> {
+        let reason = match self {
+            RateLimitAlgorithm::FixedWindow { limit, window }
+            | RateLimitAlgorithm::SlidingWindow { limit, window } => {
+                if *limit == 0 {
+                    Some("the limit must be greater than 0")
+                } else if window.as_millis() == 0 {
+                    Some("the window must be at least 1 millisecond")
+                } else {
+                    None
+                }
+            }
+            RateLimitAlgorithm::TokenBucket {
+                capacity,
+                refill_rate,
+            } => {
+                if *capacity == 0 {
+                    Some("the capacity must be greater than 0")
+                } else if !refill_rate.is_finite() || *refill_rate <= 0.0 {
+                    Some("the refill rate must be a finite number greater than 0")
+                } else {
+                    None
+                }
+            }
+        };
+
+        match reason {
+            Some(reason) => Err(RedisUtilsError::InvalidRateLimit(format!(
+                "{} {}",
+                self.name(),
+                reason
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn script(&self) -> Script {
+        match self {
+            RateLimitAlgorithm::FixedWindow { .. } => Script::new(FIXED_WINDOW_SCRIPT),
+            RateLimitAlgorithm::SlidingWindow { .. } => Script::new(SLIDING_WINDOW_SCRIPT),
+            RateLimitAlgorithm::TokenBucket { .. } => Script::new(TOKEN_BUCKET_SCRIPT),
+        }
+    }
+}
+
+type CustomKeyExtractor = Arc<dyn Fn(&Parts) -> Option<String> + Send + Sync>;
+
+/// The value used to identify the client that makes a request.
+#[derive(Clone)]
+pub enum RateLimitKey {
+    /// The IP address of the connection.
+    ClientIp,
+    /// The last IP address of the `X-Forwarded-For` header, which is appended by the proxy, or the IP address of the connection when
+    /// the header is not present.
+    ForwardedClientIp,
+    /// The value of a header containing an API key. The API key is hashed before using it as part of the Redis key. Requests
+    /// without the header are identified by the IP address of the connection.
+    ApiKey(HeaderName),
+    /// A custom key extracted from the request.
+    Custom(CustomKeyExtractor),
+}
+
+impl RateLimitKey {
+    pub fn custom(extractor: impl Fn(&Parts) -> Option<String> + Send + Sync + 'static) -> Self {
+        RateLimitKey::Custom(Arc::new(extractor))
+    }
+
+    pub(crate) fn extract(&self, parts: &Parts) -> Option<String> {
+        match self {
+            RateLimitKey::ClientIp => get_connection_ip(parts).map(|ip| format!("ip:{}", ip)),
+            RateLimitKey::ForwardedClientIp => get_forwarded_ip(parts)
+                .or_else(|| get_connection_ip(parts))
+                .map(|ip| format!("ip:{}", ip)),
+            RateLimitKey::ApiKey(header_name) => {
+                match parts.headers.get(header_name).map(HeaderValue::as_bytes) {
+                    Some(api_key) if !api_key.is_empty() => {
+                        Some(format!("api_key:{:x}", Sha256::digest(api_key)))
+                    }
+                    _ => RateLimitKey::ClientIp.extract(parts),
+                }
+            }
+            RateLimitKey::Custom(extractor) => {
+                extractor(parts).map(|key| format!("custom:{}", key))
+            }
+        }
+    }
+}
+
+impl Debug for RateLimitKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitKey::ClientIp => write!(f, "ClientIp"),
+            RateLimitKey::ForwardedClientIp => write!(f, "ForwardedClientIp"),
+            RateLimitKey::ApiKey(header_name) => write!(f, "ApiKey({})", header_name),
+            RateLimitKey::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+fn get_connection_ip(parts: &Parts) -> Option<String> {
+    parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+}
+
+fn get_forwarded_ip(parts: &Parts) -> Option<String> {
+    parts
+        .headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next_back())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty())
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitOptions {
+    /// The algorithm used to limit the requests.
+    pub algorithm: RateLimitAlgorithm,
+    /// The value used to identify the clients.
+    pub key: RateLimitKey,
+    /// The prefix of the Redis keys that contain the state of every client.
+    pub prefix: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitLayerBuilder {
+    options: RateLimitOptions,
+    redis_pool: Pool<RedisConnectionManager>,
+}
+
+impl RateLimitLayerBuilder {
+    pub fn new(redis_pool: Pool<RedisConnectionManager>, algorithm: RateLimitAlgorithm) -> Self {
+        RateLimitLayerBuilder {
+            redis_pool,
+            options: RateLimitOptions {
+                algorithm,
+                key: RateLimitKey::ClientIp,
+                prefix: DEFAULT_RATE_LIMIT_PREFIX.to_string(),
+            },
+        }
+    }
+
+    pub fn with_key(self, key: RateLimitKey) -> Self {
+        RateLimitLayerBuilder {
+            options: RateLimitOptions {
+                key,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    pub fn with_prefix(self, prefix: String) -> Self {
+        RateLimitLayerBuilder {
+            options: RateLimitOptions {
+                prefix,
+                ..self.options
+            },
+            ..self
+        }
+    }
+
+    /// Returns an error when the options of the algorithm would not limit the requests, like a window of zero or a token
+    /// bucket that never refills.
+    pub fn build(self) -> Result<RateLimitLayer, RedisUtilsError> {
+        self.options.algorithm.validate()?;
+
+        Ok(RateLimitLayer {
+            script: self.options.algorithm.script(),
+            redis_pool: self.redis_pool,
+            options: self.options,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitLayer {
+    options: RateLimitOptions,
+    redis_pool: Pool<RedisConnectionManager>,
+    script: Script,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitMiddleware {
+            inner,
+            redis_pool: self.redis_pool.clone(),
+            options: self.options.clone(),
+            script: self.script.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    redis_pool: Pool<RedisConnectionManager>,
+    options: RateLimitOptions,
+    script: Script,
+}
+
+impl<S> Service<Request> for RateLimitMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    /// When the client key cannot be extracted or there is a redis error, we return the response from the handler.
+    fn call(&mut self, req: Request) -> Self::Future {
+        let (parts, body) = req.into_parts();
+        let redis_pool = self.redis_pool.clone();
+        let options = self.options.clone();
+        let script = self.script.clone();
+        let client_key = options.key.extract(&parts);
+
+        let future = self.inner.call(Request::from_parts(parts, body));
+
+        Box::pin(async move {
+            let Some(client_key) = client_key else {
+                tracing::debug!("Unable to identify the client, skipping rate limit");
+
+                return future.await;
+            };
+
+            let redis_key = format!(
+                "{}:{}:{}",
+                options.prefix,
+                options.algorithm.name(),
+                client_key
+            );
+
+            let decision = match check_rate_limit(
+                &redis_pool,
+                &script,
+                &options.algorithm,
+                &redis_key,
+            )
+            .await
+            {
+                Ok(decision) => decision,
+                Err(err) => {
+                    tracing::warn!(
+                        redis_key,
+                        error = %err,
+                        "Unable to check the rate limit, skipping it"
+                    );
+
+                    return future.await;
+                }
+            };
+
+            if !decision.allowed {
+                tracing::info!(redis_key, "Rate limit exceeded");
+
+                let mut res =
+                    (StatusCode::TOO_MANY_REQUESTS, "Limit of requests exceeded").into_response();
+
+                decision.set_headers(res.headers_mut());
+
+                return Ok(res);
+            }
+
+            let mut res: Response = future.await?;
+
+            decision.set_headers(res.headers_mut());
+
+            Ok(res)
+        })
+    }
+}
+
+// The result of checking the rate limit of a client.
+#[derive(Debug, PartialEq)]
+struct RateLimitDecision {
+    allowed: bool,
+    limit: u64,
+    remaining: u64,
+    // Milliseconds until the limit is completely reset
+    reset_after: u64,
+    // Milliseconds until the client can make the next request
+    retry_after: u64,
+}
+
+impl RateLimitDecision {
+    fn from_script_result(limit: u64, result: &[i64]) -> Option<Self> {
+        let [allowed, remaining, reset_after, retry_after] = result else {
+            return None;
+        };
+
+        Some(RateLimitDecision {
+            allowed: *allowed == 1,
+            limit,
+            remaining: (*remaining).max(0) as u64,
+            reset_after: (*reset_after).max(0) as u64,
+            retry_after: (*retry_after).max(0) as u64,
+        })
+    }
+
+    // Sets the rate limit headers. The times are sent in seconds, rounding them up.
+    fn set_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(RATE_LIMIT_LIMIT_HEADER, HeaderValue::from(self.limit));
+        headers.insert(
+            RATE_LIMIT_REMAINING_HEADER,
+            HeaderValue::from(self.remaining),
+        );
+        headers.insert(
+            RATE_LIMIT_RESET_HEADER,
+            HeaderValue::from(self.reset_after.div_ceil(1000)),
+        );
+
+        if !self.allowed {
+            headers.insert(
+                RETRY_AFTER_HEADER,
+                HeaderValue::from(self.retry_after.div_ceil(1000).max(1)),
+            );
+        }
+    }
+}
+
+async fn check_rate_limit(
+    redis_pool: &Pool<RedisConnectionManager>,
+    script: &Script,
+    algorithm: &RateLimitAlgorithm,
+    redis_key: &str,
+) -> Result<RateLimitDecision, RedisUtilsError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(RedisUtilsError::RedisConnection)?;
+
+    let mut invocation = script.key(redis_key);
+
+    match algorithm {
+        RateLimitAlgorithm::FixedWindow { limit, window } => {
+            invocation.arg(limit).arg(window.as_millis() as u64);
+        }
+        RateLimitAlgorithm::SlidingWindow { limit, window } => {
+            invocation
+                .arg(limit)
+                .arg(window.as_millis() as u64)
+                .arg(uuid::Uuid::new_v4().to_string());
+        }
+        RateLimitAlgorithm::TokenBucket {
+            capacity,
+            refill_rate,
+        } => {
+            invocation.arg(capacity).arg(refill_rate);
+        }
+    }
+
+    let result: Vec<i64> = invocation
+        .invoke_async(&mut *redis_conn)
+        .await
+        .map_err(RedisUtilsError::Redis)?;
+
+    RateLimitDecision::from_script_result(algorithm.limit(), &result)
+        .ok_or_else(|| RedisUtilsError::UnexpectedScriptResult(redis_key.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    fn request_parts(headers: &[(&str, &str)], addr: Option<&str>) -> Parts {
+        let mut builder = Request::builder().uri("/api/v1/test");
+
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+
+        let (mut parts, _) = builder.body(()).unwrap().into_parts();
+
+        if let Some(addr) = addr {
+            parts
+                .extensions
+                .insert(ConnectInfo(addr.parse::<SocketAddr>().unwrap()));
+        }
+
+        parts
+    }
+
+    #[test]
+    fn test_client_ip_key() {
+        let parts = request_parts(&[("x-forwarded-for", "10.0.0.1")], Some("127.0.0.1:3000"));
+
+        assert_eq!(
+            RateLimitKey::ClientIp.extract(&parts),
+            Some("ip:127.0.0.1".to_string())
+        );
+        assert_eq!(
+            RateLimitKey::ClientIp.extract(&request_parts(&[], None)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_forwarded_client_ip_key() {
+        let parts = request_parts(
+            &[("x-forwarded-for", "10.0.0.1, 10.0.0.2")],
+            Some("127.0.0.1:3000"),
+        );
+
+        assert_eq!(
+            RateLimitKey::ForwardedClientIp.extract(&parts),
+            Some("ip:10.0.0.2".to_string())
+        );
+        assert_eq!(
+            RateLimitKey::ForwardedClientIp.extract(&request_parts(&[], Some("127.0.0.1:3000"))),
+            Some("ip:127.0.0.1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_ips_do_not_change_the_key() {
+        let parts = request_parts(&[("x-forwarded-for", "10.0.0.2")], Some("127.0.0.1:3000"));
+        let spoofed_parts = request_parts(
+            &[("x-forwarded-for", "1.2.3.4, 10.0.0.2")],
+            Some("127.0.0.1:3000"),
+        );
+
+        assert_eq!(
+            RateLimitKey::ForwardedClientIp.extract(&spoofed_parts),
+            RateLimitKey::ForwardedClientIp.extract(&parts)
+        );
+    }
+
+    #[test]
+    fn test_api_key_is_hashed() {
+        let key = RateLimitKey::ApiKey(HeaderName::from_static("x-api-key"));
+        let parts = request_parts(&[("x-api-key", "secret")], Some("127.0.0.1:3000"));
+
+        let redis_key = key.extract(&parts).unwrap();
+
+        assert!(redis_key.starts_with("api_key:"));
+        assert!(!redis_key.contains("secret"));
+        assert_eq!(
+            key.extract(&request_parts(&[], Some("127.0.0.1:3000"))),
+            Some("ip:127.0.0.1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_custom_key() {
+        let key = RateLimitKey::custom(|parts| {
+            parts
+                .headers
+                .get("x-tenant")
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        });
+
+        assert_eq!(
+            key.extract(&request_parts(&[("x-tenant", "acme")], None)),
+            Some("custom:acme".to_string())
+        );
+        assert_eq!(key.extract(&request_parts(&[], None)), None);
+    }
+
+    #[test]
+    fn test_rejected_decision_headers() {
+        let decision = RateLimitDecision::from_script_result(10, &[0, 0, 1500, 1200]).unwrap();
+        let mut headers = HeaderMap::new();
+
+        decision.set_headers(&mut headers);
+
+        assert!(!decision.allowed);
+        assert_eq!(headers.get("ratelimit-limit").unwrap(), "10");
+        assert_eq!(headers.get("ratelimit-remaining").unwrap(), "0");
+        assert_eq!(headers.get("ratelimit-reset").unwrap(), "2");
+        assert_eq!(headers.get("retry-after").unwrap(), "2");
+    }
+
+    #[test]
+    fn test_allowed_decision_headers() {
+        let decision = RateLimitDecision::from_script_result(10, &[1, 9, 60000, 0]).unwrap();
+        let mut headers = HeaderMap::new();
+
+        decision.set_headers(&mut headers);
+
+        assert!(decision.allowed);
+        assert_eq!(headers.get("ratelimit-remaining").unwrap(), "9");
+        assert_eq!(headers.get("ratelimit-reset").unwrap(), "60");
+        assert!(!headers.contains_key("retry-after"));
+    }
+
+    #[test]
+    fn test_invalid_algorithms_are_rejected() {
+        let invalid_algorithms = [
+            RateLimitAlgorithm::FixedWindow {
+                limit: 0,
+                window: Duration::from_secs(60),
+            },
+            RateLimitAlgorithm::SlidingWindow {
+                limit: 10,
+                window: Duration::ZERO,
+            },
+            RateLimitAlgorithm::TokenBucket {
+                capacity: 0,
+                refill_rate: 1.0,
+            },
+            RateLimitAlgorithm::TokenBucket {
+                capacity: 10,
+                refill_rate: 0.0,
+            },
+            RateLimitAlgorithm::TokenBucket {
+                capacity: 10,
+                refill_rate: -1.0,
+            },
+            RateLimitAlgorithm::TokenBucket {
+                capacity: 10,
+                refill_rate: f64::NAN,
+            },
+            RateLimitAlgorithm::TokenBucket {
+                capacity: 10,
+                refill_rate: f64::INFINITY,
+            },
+        ];
+
+        for algorithm in invalid_algorithms {
+            assert!(
+                matches!(
+                    algorithm.validate(),
+                    Err(RedisUtilsError::InvalidRateLimit(_))
+                ),
+                "{:?} should be rejected",
+                algorithm
+            );
+        }
+
+        assert!(RateLimitAlgorithm::TokenBucket {
+            capacity: 10,
+            refill_rate: 0.5,
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn test_invalid_script_result() {
+        assert_eq!(RateLimitDecision::from_script_result(10, &[1, 9]), None);
+    }
+}
@@ -0,0 +1,99 @@
+//! The work the crate runs in the background, like recording the stats of a namespace or releasing a dropped lease, is
+//! tracked, so it can be flushed before the application exits (for example, during a graceful shutdown). Otherwise, those
+//! tasks would be cancelled when the runtime shuts down.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use axum_redis_cache::tasks::flush_background_tasks;
+//! # use axum::Router;
+//! # use std::time::Duration;
+//! #
+//! # async fn shutdown_signal() {}
+//! #
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! # let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+//! # let app = Router::new();
+//!
+//! axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await?;
+//!
+//! if !flush_background_tasks(Duration::from_secs(5)).await {
+//!     tracing::warn!("Some background tasks did not finish before exiting");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{future::Future, sync::OnceLock, time::Duration};
+use tokio::runtime::Handle;
+use tokio_util::task::TaskTracker;
+
+static BACKGROUND_TASKS: OnceLock<TaskTracker> = OnceLock::new();
+
+fn background_tasks() -> &'static TaskTracker {
+    BACKGROUND_TASKS.get_or_init(TaskTracker::new)
+}
+
+/// Spawns a task on the current runtime, tracked with the background tasks of the crate, so it is flushed with them.
+pub fn spawn_background_task<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    background_tasks().spawn(future);
+}
+
+pub(crate) fn spawn_background_task_on<F>(handle: &Handle, future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    background_tasks().spawn_on(future, handle);
+}
+
+/// Returns the number of background tasks that are still running.
+pub fn pending_background_tasks() -> usize {
+    background_tasks().len()
+}
+
+/// Waits until the running background tasks finish, up to the timeout. Returns false when some of them were still running
+/// after the timeout. New tasks can be spawned while flushing, and they are waited for as well.
+pub async fn flush_background_tasks(timeout: Duration) -> bool {
+    let tasks = background_tasks();
+
+    tasks.close();
+
+    let flushed = tokio::time::timeout(timeout, tasks.wait()).await.is_ok();
+
+    tasks.reopen();
+
+    flushed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    // The background tasks are shared by the whole process, so both cases run in the same test
+    #[tokio::test]
+    async fn test_flush_background_tasks() {
+        let finished = Arc::new(AtomicBool::new(false));
+        let task_finished = finished.clone();
+
+        spawn_background_task(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            task_finished.store(true, Ordering::SeqCst);
+        });
+
+        assert!(flush_background_tasks(Duration::from_secs(5)).await);
+        assert!(finished.load(Ordering::SeqCst));
+
+        spawn_background_task(async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+
+        assert!(!flush_background_tasks(Duration::from_millis(50)).await);
+        assert_eq!(pending_background_tasks(), 1);
+    }
+}
@@ -0,0 +1,417 @@
+//! Utilities to test the code using this crate, enabled by the `test-util` feature.
+//!
+//! - TestNamespace isolates the keys of each test under a unique prefix (`test:<uuid>`), so the tests can run in parallel
+//!   against the same Redis server, and deletes them when it is dropped.
+//! - `RedisCache::in_memory()` creates a cache handle backed by an in-memory store instead of Redis, which follows the same
+//!   semantics (key prefixes, codecs and expiration), for the tests that do not need a Redis server. The RedisCacheLayer
+//!   always uses Redis, so the tests of the routes behind it set the namespace prefix with
+//!   `RedisCacheLayerBuilder::with_key_prefix` instead.
+//! - `assert_cached` and `assert_ttl_between` check the values saved by a RedisCache, no matter the store behind it.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use axum_redis_cache::testing::{self, TestNamespace};
+//! # use axum_redis_cache::cache::RedisCache;
+//! # use std::time::Duration;
+//! #
+//! # async fn compute_and_cache_stats(cache: &RedisCache) {
+//! #     cache.set("stats", &1, Duration::from_secs(600)).await.unwrap();
+//! # }
+//!
+//! #[tokio::test]
+//! async fn test_stats_are_cached() {
+//!     let namespace = TestNamespace::connect("redis://127.0.0.1:6379").await;
+//!     let cache = namespace.cache();
+//!
+//!     compute_and_cache_stats(&cache).await;
+//!
+//!     testing::assert_cached(&cache, "stats").await;
+//!     testing::assert_ttl_between(&cache, "stats", Duration::from_secs(590), Duration::from_secs(600)).await;
+//! }
+//! ```
+//!
+//! The cleanup scans the keys of the namespace, so it is only supported on standalone Redis servers.
+use axum::Router;
+use bb8::Pool;
+use redis::AsyncCommands;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use super::{cache::RedisCache, extractors::REDIS_KEY_DELIMITER, pool::RedisConnectionManager};
+
+const TEST_PREFIX: &str = "test";
+const CLEANUP_SCAN_COUNT: usize = 100;
+
+/// In-memory store behind `RedisCache::in_memory()`. Expired entries are removed when they are read, like Redis does
+/// with its passive expiration.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MemoryStore {
+    entries: Arc<Mutex<HashMap<String, MemoryEntry>>>,
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    document: serde_json::Value,
+    expires_at: Instant,
+}
+
+impl MemoryStore {
+    pub(crate) fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let mut entries = self.entries.lock().expect("Memory store lock poisoned");
+
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.document.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn set(&self, key: String, document: serde_json::Value, ttl: Duration) {
+        let entry = MemoryEntry {
+            document,
+            expires_at: Instant::now() + ttl,
+        };
+
+        self.entries
+            .lock()
+            .expect("Memory store lock poisoned")
+            .insert(key, entry);
+    }
+
+    pub(crate) fn delete(&self, key: &str) -> bool {
+        self.get(key).is_some()
+            && self
+                .entries
+                .lock()
+                .expect("Memory store lock poisoned")
+                .remove(key)
+                .is_some()
+    }
+
+    pub(crate) fn ttl(&self, key: &str) -> Option<Duration> {
+        self.get(key)?;
+
+        self.entries
+            .lock()
+            .expect("Memory store lock poisoned")
+            .get(key)
+            .map(|entry| entry.expires_at.saturating_duration_since(Instant::now()))
+    }
+}
+
+/// Unique key namespace for a test. Every key created through it is prefixed with `test:<uuid>`, and all of them are
+/// deleted when the namespace is dropped (or explicitly with `cleanup`).
+pub struct TestNamespace {
+    id: Uuid,
+    redis_url: String,
+    redis_pool: Pool<RedisConnectionManager>,
+}
+
+impl TestNamespace {
+    pub async fn connect(redis_url: impl Into<String>) -> Self {
+        let redis_url = redis_url.into();
+        let redis_manager =
+            RedisConnectionManager::new(redis_url.as_str()).expect("Redis manager failed");
+        let redis_pool = Pool::builder()
+            .build(redis_manager)
+            .await
+            .expect("Redis pool connection failed");
+
+        TestNamespace {
+            id: Uuid::new_v4(),
+            redis_url,
+            redis_pool,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Prefix of all the keys of the namespace, `test:<uuid>`.
+    pub fn prefix(&self) -> String {
+        [TEST_PREFIX, &self.id.to_string()].join(REDIS_KEY_DELIMITER)
+    }
+
+    /// Returns the Redis key for the name, inside the namespace.
+    pub fn key(&self, name: &str) -> String {
+        [self.prefix().as_str(), name].join(REDIS_KEY_DELIMITER)
+    }
+
+    pub fn redis_pool(&self) -> Pool<RedisConnectionManager> {
+        self.redis_pool.clone()
+    }
+
+    /// Returns a RedisCache that saves its values inside the namespace.
+    pub fn cache(&self) -> RedisCache {
+        RedisCache::new(self.redis_pool()).with_prefix(self.prefix())
+    }
+
+    /// Panics when the Redis key does not exist.
+    pub async fn assert_cached(&self, redis_key: &str) {
+        assert!(
+            self.exists(redis_key).await,
+            "Expected {redis_key} to be cached"
+        );
+    }
+
+    /// Panics when the Redis key exists.
+    pub async fn assert_not_cached(&self, redis_key: &str) {
+        assert!(
+            !self.exists(redis_key).await,
+            "Expected {redis_key} not to be cached"
+        );
+    }
+
+    /// Panics when the Redis key does not exist, or the time left before it expires is not between min and max.
+    pub async fn assert_ttl_between(&self, redis_key: &str, min: Duration, max: Duration) {
+        let mut redis_conn = self.redis_connection().await;
+        let ttl: i64 = redis_conn
+            .pttl(redis_key)
+            .await
+            .expect("Unable to get the key ttl");
+
+        let ttl = u64::try_from(ttl)
+            .map(Duration::from_millis)
+            .unwrap_or_else(|_| panic!("Expected {redis_key} to be cached with a ttl"));
+
+        assert_ttl_in_range(redis_key, ttl, min, max);
+    }
+
+    /// Deletes all the keys of the namespace. The keys are found by their uuid, so the ones whose name just contains it
+    /// (for example, inside a hash tag) are deleted too.
+    pub async fn cleanup(&self) {
+        let mut redis_conn = self.redis_connection().await;
+
+        delete_matching_keys(&mut *redis_conn, &self.cleanup_pattern())
+            .await
+            .expect("Unable to clean up the test namespace");
+    }
+
+    async fn exists(&self, redis_key: &str) -> bool {
+        let mut redis_conn = self.redis_connection().await;
+
+        redis_conn
+            .exists(redis_key)
+            .await
+            .expect("Unable to check if the key exists")
+    }
+
+    async fn redis_connection(&self) -> bb8::PooledConnection<'_, RedisConnectionManager> {
+        self.redis_pool
+            .get()
+            .await
+            .expect("Unable to get redis connection")
+    }
+
+    fn cleanup_pattern(&self) -> String {
+        format!("*{}*", self.id)
+    }
+}
+
+impl Drop for TestNamespace {
+    fn drop(&mut self) {
+        let redis_url = self.redis_url.clone();
+        let pattern = self.cleanup_pattern();
+
+        // The runtime of the test may be shutting down, so the keys are deleted from a new one on another thread
+        let cleanup = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Unable to build the cleanup runtime");
+
+            runtime.block_on(async move {
+                let client = redis::Client::open(redis_url)?;
+                let mut redis_conn = client.get_multiplexed_async_connection().await?;
+
+                delete_matching_keys(&mut redis_conn, &pattern).await
+            })
+        });
+
+        match cleanup.join() {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => {
+                tracing::warn!(error = %err, "Unable to clean up the test namespace");
+            }
+            Err(_) => {
+                tracing::warn!("Test namespace cleanup panicked");
+            }
+        }
+    }
+}
+
+/// Panics when the key is not cached.
+pub async fn assert_cached(cache: &RedisCache, key: &str) {
+    let value: Option<serde_json::Value> = cache
+        .get(key)
+        .await
+        .expect("Unable to get the cached value");
+
+    assert!(value.is_some(), "Expected {key} to be cached");
+}
+
+/// Panics when the key is cached.
+pub async fn assert_not_cached(cache: &RedisCache, key: &str) {
+    let value: Option<serde_json::Value> = cache
+        .get(key)
+        .await
+        .expect("Unable to get the cached value");
+
+    assert!(value.is_none(), "Expected {key} not to be cached");
+}
+
+/// Panics when the key is not cached, or the time left before it expires is not between min and max.
+pub async fn assert_ttl_between(cache: &RedisCache, key: &str, min: Duration, max: Duration) {
+    let ttl = cache
+        .ttl(key)
+        .await
+        .expect("Unable to get the key ttl")
+        .unwrap_or_else(|| panic!("Expected {key} to be cached with a ttl"));
+
+    assert_ttl_in_range(key, ttl, min, max);
+}
+
+/// Serves the router on a random local port, returning its base url.
+pub async fn spawn_app(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to create a tcp listener");
+
+    let base_url = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .expect("Error running the test server");
+    });
+
+    format!("http://{}", base_url)
+}
+
+fn assert_ttl_in_range(key: &str, ttl: Duration, min: Duration, max: Duration) {
+    assert!(
+        min <= ttl && ttl <= max,
+        "Expected the ttl of {key} to be between {min:?} and {max:?}, got {ttl:?}"
+    );
+}
+
+async fn delete_matching_keys<C>(redis_conn: &mut C, pattern: &str) -> redis::RedisResult<()>
+where
+    C: redis::aio::ConnectionLike + Send,
+{
+    let mut cursor: u64 = 0;
+
+    loop {
+        let (next_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(pattern)
+            .arg("COUNT")
+            .arg(CLEANUP_SCAN_COUNT)
+            .query_async(redis_conn)
+            .await?;
+
+        if !keys.is_empty() {
+            redis::cmd("DEL")
+                .arg(keys)
+                .query_async::<()>(redis_conn)
+                .await?;
+        }
+
+        if next_cursor == 0 {
+            return Ok(());
+        }
+
+        cursor = next_cursor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TestValue {
+        name: String,
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_in_memory_cache_expires_values() {
+        let cache = RedisCache::in_memory();
+        let value = TestValue {
+            name: String::from("cube"),
+        };
+
+        cache
+            .set("repository", &value, Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        assert_cached(&cache, "repository").await;
+        assert_ttl_between(
+            &cache,
+            "repository",
+            Duration::from_secs(59),
+            Duration::from_secs(60),
+        )
+        .await;
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+
+        assert_not_cached(&cache, "repository").await;
+        assert_eq!(cache.ttl("repository").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cache_keys_are_namespaced() {
+        let cache = RedisCache::in_memory();
+        let prefixed_cache = cache.clone().with_prefix(String::from("github"));
+        let value = TestValue {
+            name: String::from("cube"),
+        };
+
+        cache
+            .set("repository", &value, Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            cache.get::<TestValue>("repository").await.unwrap(),
+            Some(value)
+        );
+        assert_not_cached(&prefixed_cache, "repository").await;
+
+        assert!(cache.delete("repository").await.unwrap());
+        assert!(!cache.delete("repository").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cache_get_or_insert_with() {
+        let cache = RedisCache::in_memory();
+
+        let value: Result<u64, ()> = cache
+            .get_or_insert_with("stats", Duration::from_secs(60), || async { Ok(1) })
+            .await;
+        let cached_value: Result<u64, ()> = cache
+            .get_or_insert_with("stats", Duration::from_secs(60), || async { Ok(2) })
+            .await;
+
+        assert_eq!(value, Ok(1));
+        assert_eq!(cached_value, Ok(1));
+    }
+}
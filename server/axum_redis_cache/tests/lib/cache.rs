@@ -0,0 +1,223 @@
+use axum::{routing::get, Router};
+use axum_redis_cache::{
+    cache::{Cached, RedisCache},
+    codec::CacheCipher,
+    testing,
+};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::helpers::{TestApp, TestHandlerResponse};
+
+fn test_value() -> TestHandlerResponse {
+    TestHandlerResponse {
+        status: 200,
+        message: String::from("Cached value"),
+    }
+}
+
+#[tokio::test]
+async fn test_set_get_and_delete() {
+    let test_app = TestApp::new().await;
+    let cache = RedisCache::new(test_app.redis_pool.clone())
+        .with_prefix(format!("cache:{}", test_app.uuid));
+
+    assert!(cache
+        .get::<TestHandlerResponse>("value")
+        .await
+        .unwrap()
+        .is_none());
+
+    cache
+        .set("value", &test_value(), Duration::from_secs(60))
+        .await
+        .unwrap();
+
+    let value: TestHandlerResponse = cache.get("value").await.unwrap().unwrap();
+
+    assert_eq!(value.message, "Cached value");
+    assert!(cache.delete("value").await.unwrap());
+    assert!(!cache.delete("value").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_set_expires_value() {
+    let test_app = TestApp::new().await;
+    let cache = RedisCache::new(test_app.redis_pool.clone())
+        .with_prefix(format!("cache:{}", test_app.uuid));
+
+    cache
+        .set("expiring", &test_value(), Duration::from_millis(200))
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert!(cache
+        .get::<TestHandlerResponse>("expiring")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_get_or_insert_with_computes_value_once() {
+    let test_app = TestApp::new().await;
+    let cache = RedisCache::new(test_app.redis_pool.clone())
+        .with_prefix(format!("cache:{}", test_app.uuid));
+    let calls = AtomicUsize::new(0);
+
+    for _ in 0..2 {
+        let value = cache
+            .get_or_insert_with("computed", Duration::from_secs(60), || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+
+                Ok::<_, String>(test_value())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(value.message, "Cached value");
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    cache.delete("computed").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_or_insert_with_does_not_cache_errors() {
+    let test_app = TestApp::new().await;
+    let cache = RedisCache::new(test_app.redis_pool.clone())
+        .with_prefix(format!("cache:{}", test_app.uuid));
+
+    let result = cache
+        .get_or_insert_with("failed", Duration::from_secs(60), || async {
+            Err::<TestHandlerResponse, _>(String::from("Unable to compute value"))
+        })
+        .await;
+
+    assert!(result.is_err());
+    assert!(cache
+        .get::<TestHandlerResponse>("failed")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_encrypted_values() {
+    let test_app = TestApp::new().await;
+    let cipher = CacheCipher::new("test", &[7; 32]).unwrap();
+    let cache = RedisCache::new(test_app.redis_pool.clone())
+        .with_prefix(format!("cache:{}", test_app.uuid))
+        .with_encryption(cipher);
+    let plain_cache = RedisCache::new(test_app.redis_pool.clone())
+        .with_prefix(format!("cache:{}", test_app.uuid));
+
+    cache
+        .set("encrypted", &test_value(), Duration::from_secs(60))
+        .await
+        .unwrap();
+
+    let value: TestHandlerResponse = cache.get("encrypted").await.unwrap().unwrap();
+
+    assert_eq!(value.message, "Cached value");
+    assert!(plain_cache
+        .get::<TestHandlerResponse>("encrypted")
+        .await
+        .is_err());
+
+    cache.delete("encrypted").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_ttl_of_cached_values() {
+    let test_app = TestApp::new().await;
+    let cache = test_app.namespace.cache();
+
+    assert_eq!(cache.ttl("value").await.unwrap(), None);
+
+    cache
+        .set("value", &test_value(), Duration::from_secs(60))
+        .await
+        .unwrap();
+
+    testing::assert_cached(&cache, "value").await;
+    testing::assert_ttl_between(
+        &cache,
+        "value",
+        Duration::from_secs(59),
+        Duration::from_secs(60),
+    )
+    .await;
+    test_app
+        .namespace
+        .assert_ttl_between(
+            &test_app.namespace.key("value"),
+            Duration::from_secs(59),
+            Duration::from_secs(60),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn test_namespace_cleanup_deletes_its_keys() {
+    let test_app = TestApp::new().await;
+    let cache = test_app.namespace.cache();
+
+    cache
+        .set("value", &test_value(), Duration::from_secs(60))
+        .await
+        .unwrap();
+
+    test_app
+        .namespace
+        .assert_cached(&test_app.namespace.key("value"))
+        .await;
+
+    test_app.namespace.cleanup().await;
+
+    test_app
+        .namespace
+        .assert_not_cached(&test_app.namespace.key("value"))
+        .await;
+}
+
+async fn cached_handler(cached: Cached<TestHandlerResponse>, cache: RedisCache) -> String {
+    if let Some(value) = cached.value {
+        return value.message;
+    }
+
+    cache
+        .set(&cached.key, &test_value(), Duration::from_secs(60))
+        .await
+        .unwrap();
+
+    String::from("Computed value")
+}
+
+#[tokio::test]
+async fn test_cached_extractor_reads_the_value_of_the_request() {
+    let app = Router::new()
+        .route("/api/stats", get(cached_handler))
+        .with_state(RedisCache::in_memory());
+    let test_app_url = testing::spawn_app(app).await;
+    let client = reqwest::Client::new();
+
+    let mut bodies = Vec::new();
+
+    for _ in 0..2 {
+        let response = client
+            .get(format!("{}/api/stats?page=1", test_app_url))
+            .send()
+            .await
+            .expect("Failed to execute api request.");
+
+        bodies.push(response.text().await.unwrap());
+    }
+
+    assert_eq!(bodies, ["Computed value", "Cached value"]);
+}
@@ -1,14 +1,10 @@
 use serde::Deserialize;
-use std::{
-    fmt::Display,
-    net::{AddrParseError, SocketAddr},
-};
+use std::fmt::Display;
 
 #[derive(Debug)]
 pub enum SettingsError {
     EnvironmentLoad,
     EnvironmentVariableMissing(String),
-    InvalidVariableFormat(String),
 }
 
 impl Display for SettingsError {
@@ -18,40 +14,15 @@ impl Display for SettingsError {
             SettingsError::EnvironmentVariableMissing(key) => {
                 write!(f, "Failed to find environment variable: {}", key)
             }
-            SettingsError::InvalidVariableFormat(key) => {
-                write!(f, "Failed to parse environment variable: {}", key)
-            }
         }
     }
 }
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Settings {
-    pub application: ApplicationSettings,
     pub redis: RedisSettings,
 }
 
-#[derive(Clone, Deserialize, Debug)]
-pub struct ApplicationSettings {
-    port: u32,
-    host: String,
-}
-
-impl ApplicationSettings {
-    pub fn new() -> Result<Self, SettingsError> {
-        let port: u32 = get_env_value("PORT")?
-            .parse()
-            .map_err(|_| SettingsError::InvalidVariableFormat("PORT".to_string()))?;
-        let host = get_env_value("HOST")?;
-
-        Ok(Self { port, host })
-    }
-
-    pub fn get_addr(&self) -> Result<SocketAddr, AddrParseError> {
-        format!("{}:{}", self.host, self.port).parse()
-    }
-}
-
 #[derive(Clone, Deserialize, Debug)]
 pub struct RedisSettings {
     pub url: String,
@@ -69,7 +40,6 @@ pub fn get_app_settings() -> Result<Settings, SettingsError> {
     dotenv::from_filename(".env.test").map_err(|_| SettingsError::EnvironmentLoad)?;
 
     Ok(Settings {
-        application: ApplicationSettings::new()?,
         redis: RedisSettings::new()?,
     })
 }
@@ -1,60 +1,39 @@
 use axum::Router;
+use axum_redis_cache::{
+    pool::RedisConnectionManager,
+    testing::{self, TestNamespace},
+};
 use bb8::{Pool, PooledConnection};
-use bb8_redis::RedisConnectionManager;
-use redis::JsonAsyncCommands;
+use redis::{AsyncCommands, JsonAsyncCommands};
 use redis_macros::FromRedisValue;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::config::{get_app_settings, Settings};
+use crate::config::get_app_settings;
 
 pub struct TestApp {
     pub uuid: Uuid,
-    pub settings: Settings,
     pub redis_pool: Pool<RedisConnectionManager>,
+    pub namespace: TestNamespace,
 }
 
 impl TestApp {
     pub async fn new() -> Self {
         let settings = get_app_settings().expect("Unable to get server settings");
-        let redis_settings = settings.redis.clone();
-        let redis_manager =
-            RedisConnectionManager::new(redis_settings.url).expect("Redis manager failed");
-        let redis_pool = bb8::Pool::builder()
-            .build(redis_manager)
-            .await
-            .expect("Redis pool connection failed");
+        let namespace = TestNamespace::connect(settings.redis.url.clone()).await;
 
         TestApp {
-            redis_pool,
-            settings,
-            uuid: Uuid::new_v4(),
+            uuid: namespace.id(),
+            redis_pool: namespace.redis_pool(),
+            namespace,
         }
     }
 
     pub async fn spawn_app(&self, app: Router) -> String {
-        let address = self
-            .settings
-            .application
-            .get_addr()
-            .expect("Unable to get http address for running the tests");
-
-        let listener = tokio::net::TcpListener::bind(&address)
-            .await
-            .expect("Unable to create a tcp listener");
-
-        let base_url = listener.local_addr().unwrap();
-
-        tokio::spawn(async move {
-            axum::serve(listener, app)
-                .await
-                .expect("Error running the test server");
-        });
-
-        format!("http://{}", base_url)
+        testing::spawn_app(app).await
     }
 
-    pub async fn redis_connection(&self) -> PooledConnection<RedisConnectionManager> {
+    pub async fn redis_connection(&self) -> PooledConnection<'_, RedisConnectionManager> {
         self.redis_pool
             .get()
             .await
@@ -69,6 +48,15 @@ impl TestApp {
             .await
             .expect("Unable to clean redis");
     }
+
+    pub async fn redis_del(&self, key: String) {
+        let mut connection = self.redis_connection().await;
+
+        connection
+            .del::<&str, ()>(&key)
+            .await
+            .expect("Unable to clean redis");
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRedisValue)]
@@ -0,0 +1,292 @@
+use axum::http::HeaderName;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{post, Router},
+};
+use axum_redis_cache::{idempotency::IdempotencyLayerBuilder, rate_limit::RateLimitKey};
+use redis::AsyncCommands;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::helpers::TestApp;
+
+async fn test_handler(State(calls): State<Arc<AtomicUsize>>, body: String) -> Response {
+    let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+
+    (StatusCode::CREATED, format!("{}:{}", call, body)).into_response()
+}
+
+async fn slow_handler() -> Response {
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    (StatusCode::CREATED, "Slow handler response").into_response()
+}
+
+fn build_app(test_app: &TestApp, calls: Arc<AtomicUsize>) -> Router {
+    build_app_with_key(test_app, calls, RateLimitKey::ClientIp)
+}
+
+fn build_app_with_key(test_app: &TestApp, calls: Arc<AtomicUsize>, key: RateLimitKey) -> Router {
+    Router::new()
+        .route("/api/test", post(test_handler))
+        .route("/api/slow", post(slow_handler))
+        .layer(
+            IdempotencyLayerBuilder::new(test_app.redis_pool.clone())
+                .with_key(key)
+                .with_prefix(format!("idempotency:{}", test_app.uuid))
+                .build(),
+        )
+        .with_state(calls)
+}
+
+// The Redis key of an idempotency key sent by the local test client
+fn idempotency_redis_key(test_app: &TestApp, idempotency_key: &str) -> String {
+    format!(
+        "idempotency:{}:ip%3A127.0.0.1:{}",
+        test_app.uuid, idempotency_key
+    )
+}
+
+#[tokio::test]
+async fn test_idempotent_request_is_replayed() {
+    let test_app = TestApp::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let test_app_url = test_app
+        .spawn_app(build_app(&test_app, calls.clone()))
+        .await;
+    let client = reqwest::Client::new();
+
+    let mut responses = Vec::new();
+
+    for _ in 0..2 {
+        let response = client
+            .post(format!("{}/api/test", test_app_url))
+            .header("Idempotency-Key", "replayed")
+            .body("payload")
+            .send()
+            .await
+            .expect("Failed to execute api request.");
+
+        responses.push(response);
+    }
+
+    assert_eq!(responses[0].status().as_u16(), 201);
+    assert!(responses[0].headers().get("idempotent-replayed").is_none());
+    assert_eq!(responses[1].status().as_u16(), 201);
+    assert_eq!(
+        responses[1].headers().get("idempotent-replayed").unwrap(),
+        "true"
+    );
+
+    for response in responses {
+        assert_eq!(response.text().await.unwrap(), "1:payload");
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    test_app
+        .redis_del(idempotency_redis_key(&test_app, "replayed"))
+        .await;
+}
+
+#[tokio::test]
+async fn test_idempotency_key_reused_with_a_different_request() {
+    let test_app = TestApp::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let test_app_url = test_app
+        .spawn_app(build_app(&test_app, calls.clone()))
+        .await;
+    let client = reqwest::Client::new();
+
+    for (body, expected_status) in [("first", 201), ("second", 422)] {
+        let response = client
+            .post(format!("{}/api/test", test_app_url))
+            .header("Idempotency-Key", "reused")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute api request.");
+
+        assert_eq!(response.status().as_u16(), expected_status);
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    test_app
+        .redis_del(idempotency_redis_key(&test_app, "reused"))
+        .await;
+}
+
+#[tokio::test]
+async fn test_concurrent_idempotent_request_conflicts() {
+    let test_app = TestApp::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let test_app_url = test_app.spawn_app(build_app(&test_app, calls)).await;
+    let client = reqwest::Client::new();
+
+    let send_request = || {
+        client
+            .post(format!("{}/api/slow", test_app_url))
+            .header("Idempotency-Key", "concurrent")
+            .send()
+    };
+
+    let first = tokio::spawn(send_request());
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let second = send_request()
+        .await
+        .expect("Failed to execute api request.");
+    let first = first
+        .await
+        .unwrap()
+        .expect("Failed to execute api request.");
+
+    assert_eq!(first.status().as_u16(), 201);
+    assert_eq!(second.status().as_u16(), 409);
+
+    test_app
+        .redis_del(idempotency_redis_key(&test_app, "concurrent"))
+        .await;
+}
+
+#[tokio::test]
+async fn test_expired_idempotency_key_is_not_overwritten() {
+    let test_app = TestApp::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let test_app_url = test_app.spawn_app(build_app(&test_app, calls)).await;
+    let client = reqwest::Client::new();
+
+    let redis_key = idempotency_redis_key(&test_app, "expired");
+
+    let request = tokio::spawn(
+        client
+            .post(format!("{}/api/slow", test_app_url))
+            .header("Idempotency-Key", "expired")
+            .send(),
+    );
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Another request acquired the key after it expired, while the slow request was still running
+    test_app
+        .redis_connection()
+        .await
+        .set_ex::<&str, &str, ()>(&redis_key, "entry of another request", 60)
+        .await
+        .expect("Unable to replace the entry");
+
+    let response = request
+        .await
+        .unwrap()
+        .expect("Failed to execute api request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+
+    let saved_entry: Option<String> = test_app
+        .redis_connection()
+        .await
+        .get(&redis_key)
+        .await
+        .expect("Unable to read the entry");
+
+    assert_eq!(saved_entry.as_deref(), Some("entry of another request"));
+
+    test_app.redis_del(redis_key).await;
+}
+
+#[tokio::test]
+async fn test_requests_without_idempotency_key_are_not_saved() {
+    let test_app = TestApp::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let test_app_url = test_app
+        .spawn_app(build_app(&test_app, calls.clone()))
+        .await;
+    let client = reqwest::Client::new();
+
+    for _ in 0..2 {
+        let response = client
+            .post(format!("{}/api/test", test_app_url))
+            .body("payload")
+            .send()
+            .await
+            .expect("Failed to execute api request.");
+
+        assert_eq!(response.status().as_u16(), 201);
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_idempotency_keys_are_scoped_by_client() {
+    let test_app = TestApp::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let test_app_url = test_app
+        .spawn_app(build_app_with_key(
+            &test_app,
+            calls.clone(),
+            RateLimitKey::ApiKey(HeaderName::from_static("x-api-key")),
+        ))
+        .await;
+    let client = reqwest::Client::new();
+
+    let mut bodies = Vec::new();
+
+    for api_key in ["first-client", "second-client"] {
+        let response = client
+            .post(format!("{}/api/test", test_app_url))
+            .header("Idempotency-Key", "shared")
+            .header("x-api-key", api_key)
+            .body(api_key)
+            .send()
+            .await
+            .expect("Failed to execute api request.");
+
+        assert_eq!(response.status().as_u16(), 201);
+        assert!(response.headers().get("idempotent-replayed").is_none());
+
+        bodies.push(response.text().await.unwrap());
+    }
+
+    assert_eq!(bodies, ["1:first-client", "2:second-client"]);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_unreadable_idempotency_entry_is_not_handled_again() {
+    let test_app = TestApp::new().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let test_app_url = test_app
+        .spawn_app(build_app(&test_app, calls.clone()))
+        .await;
+    let client = reqwest::Client::new();
+
+    let redis_key = idempotency_redis_key(&test_app, "corrupt");
+
+    test_app
+        .redis_connection()
+        .await
+        .set_ex::<&str, &str, ()>(&redis_key, "not a saved entry", 60)
+        .await
+        .expect("Unable to save the corrupt entry");
+
+    let response = client
+        .post(format!("{}/api/test", test_app_url))
+        .header("Idempotency-Key", "corrupt")
+        .body("payload")
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(response.status().as_u16(), 500);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
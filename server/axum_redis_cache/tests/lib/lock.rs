@@ -0,0 +1,194 @@
+use axum_redis_cache::lock::{RedisLock, RedisSemaphore};
+use std::time::Duration;
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn test_lock_is_exclusive() {
+    let test_app = TestApp::new().await;
+    let lock = RedisLock::new(test_app.redis_pool.clone(), "exclusive")
+        .with_prefix(format!("lock:{}", test_app.uuid));
+
+    let lease = lock
+        .try_acquire()
+        .await
+        .unwrap()
+        .expect("Lock not acquired");
+
+    assert!(lease.is_valid());
+    assert!(lock.try_acquire().await.unwrap().is_none());
+    assert!(lease.release().await.unwrap());
+
+    let next_lease = lock
+        .try_acquire()
+        .await
+        .unwrap()
+        .expect("Lock not acquired");
+
+    assert!(next_lease.release().await.unwrap());
+
+    test_app
+        .redis_del(format!("lock:{}:{{exclusive}}:fencing", test_app.uuid))
+        .await;
+}
+
+#[tokio::test]
+async fn test_lock_fencing_token_increases() {
+    let test_app = TestApp::new().await;
+    let lock = RedisLock::new(test_app.redis_pool.clone(), "fencing")
+        .with_prefix(format!("lock:{}", test_app.uuid));
+
+    let lease = lock
+        .try_acquire()
+        .await
+        .unwrap()
+        .expect("Lock not acquired");
+    let first_token = lease.fencing_token();
+
+    lease.release().await.unwrap();
+
+    let lease = lock
+        .try_acquire()
+        .await
+        .unwrap()
+        .expect("Lock not acquired");
+
+    assert!(lease.fencing_token() > first_token);
+
+    lease.release().await.unwrap();
+
+    test_app
+        .redis_del(format!("lock:{}:{{fencing}}:fencing", test_app.uuid))
+        .await;
+}
+
+#[tokio::test]
+async fn test_lock_expired_lease_can_not_be_released() {
+    let test_app = TestApp::new().await;
+    let lock = RedisLock::new(test_app.redis_pool.clone(), "expired")
+        .with_prefix(format!("lock:{}", test_app.uuid))
+        .with_lease_time(Duration::from_millis(200));
+
+    let expired_lease = lock
+        .try_acquire()
+        .await
+        .unwrap()
+        .expect("Lock not acquired");
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert!(!expired_lease.is_valid());
+
+    let lease = lock
+        .acquire(Duration::from_secs(1))
+        .await
+        .unwrap()
+        .expect("Lock not acquired");
+
+    assert!(!expired_lease.release().await.unwrap());
+    assert!(lease.renew().await.unwrap());
+    assert!(lease.release().await.unwrap());
+
+    test_app
+        .redis_del(format!("lock:{}:{{expired}}:fencing", test_app.uuid))
+        .await;
+}
+
+#[tokio::test]
+async fn test_lock_auto_renewal() {
+    let test_app = TestApp::new().await;
+    let lock = RedisLock::new(test_app.redis_pool.clone(), "renewal")
+        .with_prefix(format!("lock:{}", test_app.uuid))
+        .with_lease_time(Duration::from_millis(300))
+        .with_auto_renewal();
+
+    let lease = lock
+        .try_acquire()
+        .await
+        .unwrap()
+        .expect("Lock not acquired");
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+
+    assert!(lease.is_valid());
+    assert!(lock.try_acquire().await.unwrap().is_none());
+    assert!(lease.release().await.unwrap());
+
+    test_app
+        .redis_del(format!("lock:{}:{{renewal}}:fencing", test_app.uuid))
+        .await;
+}
+
+#[tokio::test]
+async fn test_semaphore_limits_permits() {
+    let test_app = TestApp::new().await;
+    let semaphore = RedisSemaphore::new(test_app.redis_pool.clone(), "permits", 2)
+        .with_prefix(format!("semaphore:{}", test_app.uuid));
+
+    let first = semaphore.try_acquire().await.unwrap().expect("No permit");
+    let second = semaphore.try_acquire().await.unwrap().expect("No permit");
+
+    assert!(second.fencing_token() > first.fencing_token());
+    assert!(semaphore.try_acquire().await.unwrap().is_none());
+    assert!(first.release().await.unwrap());
+
+    let third = semaphore
+        .acquire(Duration::from_secs(1))
+        .await
+        .unwrap()
+        .expect("No permit");
+
+    second.release().await.unwrap();
+    third.release().await.unwrap();
+
+    test_app
+        .redis_del(format!("semaphore:{}:{{permits}}:fencing", test_app.uuid))
+        .await;
+}
+
+#[tokio::test]
+async fn test_semaphore_reclaims_expired_permits() {
+    let test_app = TestApp::new().await;
+    let semaphore = RedisSemaphore::new(test_app.redis_pool.clone(), "expired", 1)
+        .with_prefix(format!("semaphore:{}", test_app.uuid))
+        .with_lease_time(Duration::from_millis(200));
+
+    let expired = semaphore.try_acquire().await.unwrap().expect("No permit");
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let permit = semaphore.try_acquire().await.unwrap().expect("No permit");
+
+    assert!(!expired.renew().await.unwrap());
+    assert!(permit.release().await.unwrap());
+
+    test_app
+        .redis_del(format!("semaphore:{}:{{expired}}:fencing", test_app.uuid))
+        .await;
+}
+
+#[tokio::test]
+async fn test_semaphore_short_lease_does_not_expire_other_permits() {
+    let test_app = TestApp::new().await;
+    let semaphore = RedisSemaphore::new(test_app.redis_pool.clone(), "leases", 2)
+        .with_prefix(format!("semaphore:{}", test_app.uuid));
+
+    let long = semaphore.try_acquire().await.unwrap().expect("No permit");
+    let short = semaphore
+        .clone()
+        .with_lease_time(Duration::from_millis(200))
+        .try_acquire()
+        .await
+        .unwrap()
+        .expect("No permit");
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert!(long.renew().await.unwrap());
+    assert!(!short.renew().await.unwrap());
+    assert!(long.release().await.unwrap());
+
+    test_app
+        .redis_del(format!("semaphore:{}:{{leases}}:fencing", test_app.uuid))
+        .await;
+}
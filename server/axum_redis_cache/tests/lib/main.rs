@@ -1,3 +1,8 @@
+mod cache;
 mod config;
 mod helpers;
+mod idempotency;
+mod lock;
 mod middlewares;
+mod namespace;
+mod rate_limit;
@@ -1,11 +1,18 @@
 use axum::{
+    body::Body,
+    extract::Request,
     http::StatusCode,
+    middleware::{self, Next},
     response::{IntoResponse, Response},
     routing::{get, Router},
     Json,
 };
-use axum_redis_cache::middlewares::RedisCacheLayerBuilder;
-use redis::JsonAsyncCommands;
+use axum_redis_cache::{
+    codec::CacheCipher,
+    middlewares::{CacheExpirationTime, RedisCacheLayerBuilder},
+};
+use futures_util::stream;
+use redis::{AsyncCommands, JsonAsyncCommands};
 
 use crate::helpers::{TestApp, TestHandlerResponse};
 
@@ -20,6 +27,15 @@ async fn test_handler() -> Response {
         .into_response()
 }
 
+async fn test_stream_handler() -> Response {
+    let chunks = vec![
+        Ok::<_, std::io::Error>(r#"{"status": 200, "#),
+        Ok(r#""message": "Test handler response"}"#),
+    ];
+
+    (StatusCode::OK, Body::from_stream(stream::iter(chunks))).into_response()
+}
+
 #[tokio::test]
 async fn test_save_api_result_on_redis() {
     let test_app = TestApp::new().await;
@@ -166,3 +182,227 @@ async fn test_return_cache_response_with_expiration_time() {
         .redis_json_del(format!("api:test:{}", test_app.uuid))
         .await;
 }
+
+#[tokio::test]
+async fn test_expiration_time_extension_replaces_layer_expiration_time() {
+    let test_app = TestApp::new().await;
+    let app = Router::new()
+        .route(
+            &format!("/api/test/{}", test_app.uuid),
+            get(test_handler).layer(
+                RedisCacheLayerBuilder::new(test_app.redis_pool.clone())
+                    .with_expiration_time(500)
+                    .build::<TestHandlerResponse>(),
+            ),
+        )
+        .route_layer(middleware::from_fn(
+            |mut req: Request, next: Next| async move {
+                req.extensions_mut().insert(CacheExpirationTime(100));
+
+                next.run(req).await
+            },
+        ));
+
+    let test_app_url = test_app.spawn_app(app).await;
+
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/test/{}", test_app_url, test_app.uuid);
+
+    // First request should save the response on Redis
+    let _ = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute API request");
+
+    // Second request should return the response from Redis
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(
+        response.headers().get("Cache-Control").unwrap(),
+        "max-age=100"
+    );
+
+    test_app
+        .redis_json_del(format!("api:test:{}", test_app.uuid))
+        .await;
+}
+
+#[tokio::test]
+async fn test_not_save_api_result_on_redis_when_body_exceeds_max_body_size() {
+    let test_app = TestApp::new().await;
+    let app = Router::new().route(
+        &format!("/api/test/{}", test_app.uuid),
+        get(test_handler).layer(
+            RedisCacheLayerBuilder::new(test_app.redis_pool.clone())
+                .with_max_body_size(10)
+                .build::<TestHandlerResponse>(),
+        ),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/test/{}", test_app_url, test_app.uuid);
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let mut redis_connection = test_app.redis_connection().await;
+    let is_cached: bool = redis_connection
+        .exists(format!("api:test:{}", test_app.uuid))
+        .await
+        .expect("Could not check the key on Redis");
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!is_cached);
+
+    let body: TestHandlerResponse = response.json().await.unwrap();
+
+    assert_eq!(body.message, "Test handler response");
+}
+
+#[tokio::test]
+async fn test_not_save_api_result_on_redis_when_body_length_is_unknown() {
+    let test_app = TestApp::new().await;
+    let app = Router::new().route(
+        &format!("/api/test/{}", test_app.uuid),
+        get(test_stream_handler).layer(
+            RedisCacheLayerBuilder::new(test_app.redis_pool.clone()).build::<TestHandlerResponse>(),
+        ),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/test/{}", test_app_url, test_app.uuid);
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let mut redis_connection = test_app.redis_connection().await;
+    let is_cached: bool = redis_connection
+        .exists(format!("api:test:{}", test_app.uuid))
+        .await
+        .expect("Could not check the key on Redis");
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!is_cached);
+
+    let body: TestHandlerResponse = response.json().await.unwrap();
+
+    assert_eq!(body.message, "Test handler response");
+}
+
+#[tokio::test]
+async fn test_save_encrypted_api_result_on_redis() {
+    let test_app = TestApp::new().await;
+    let app = Router::new().route(
+        &format!("/api/test/{}", test_app.uuid),
+        get(test_handler).layer(
+            RedisCacheLayerBuilder::new(test_app.redis_pool.clone())
+                .with_encryption(CacheCipher::new("current", &[1; 32]).unwrap())
+                .build::<TestHandlerResponse>(),
+        ),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/test/{}", test_app_url, test_app.uuid);
+
+    // First request should save the encrypted response on Redis
+    let _ = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute API request");
+
+    let mut redis_connection = test_app.redis_connection().await;
+    let redis_key = format!("api:test:{}", test_app.uuid);
+    let raw_entry: String = redis_connection
+        .json_get(&redis_key, "$")
+        .await
+        .expect("Could not get handler response from Redis");
+
+    // Second request should return the decrypted response from Redis
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(raw_entry.contains(r#""kid":"current""#));
+    assert!(!raw_entry.contains("Test handler response"));
+
+    let body: TestHandlerResponse = response.json().await.unwrap();
+
+    assert_eq!(body.message, "Test handler response");
+
+    test_app.redis_json_del(redis_key).await;
+}
+
+#[tokio::test]
+async fn test_undecryptable_cache_entry_is_a_cache_miss() {
+    let test_app = TestApp::new().await;
+    let route = format!("/api/test/{}", test_app.uuid);
+    let previous_app = Router::new().route(
+        &route,
+        get(test_handler).layer(
+            RedisCacheLayerBuilder::new(test_app.redis_pool.clone())
+                .with_encryption(CacheCipher::new("previous", &[0; 32]).unwrap())
+                .build::<TestHandlerResponse>(),
+        ),
+    );
+    let app = Router::new().route(
+        &route,
+        get(test_handler).layer(
+            RedisCacheLayerBuilder::new(test_app.redis_pool.clone())
+                .with_encryption(CacheCipher::new("current", &[1; 32]).unwrap())
+                .build::<TestHandlerResponse>(),
+        ),
+    );
+
+    let previous_app_url = test_app.spawn_app(previous_app).await;
+    let test_app_url = test_app.spawn_app(app).await;
+
+    let client = reqwest::Client::new();
+
+    // The entry is saved with a key that the second app does not know about
+    let _ = client
+        .get(format!("{}{}", previous_app_url, route))
+        .send()
+        .await
+        .expect("Failed to execute API request");
+
+    let response = client
+        .get(format!("{}{}", test_app_url, route))
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    let mut redis_connection = test_app.redis_connection().await;
+    let redis_key = format!("api:test:{}", test_app.uuid);
+    let raw_entry: String = redis_connection
+        .json_get(&redis_key, "$")
+        .await
+        .expect("Could not get handler response from Redis");
+
+    // The response comes from the handler, which replaces the entry with one encrypted with the current key
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!response.headers().contains_key("Cache-Control"));
+    assert!(raw_entry.contains(r#""kid":"current""#));
+
+    test_app.redis_json_del(redis_key).await;
+}
@@ -0,0 +1,183 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, Router},
+    Json,
+};
+use axum_redis_cache::{
+    middlewares::RedisCacheLayerBuilder,
+    namespace::{CacheNamespace, NamespaceBudget},
+};
+use redis::AsyncCommands;
+use std::time::Duration;
+
+use crate::helpers::{TestApp, TestHandlerResponse};
+
+async fn test_handler() -> Response {
+    (
+        StatusCode::OK,
+        Json(TestHandlerResponse {
+            message: String::from("Test handler response"),
+            status: 200,
+        }),
+    )
+        .into_response()
+}
+
+async fn send_request(url: &str) {
+    reqwest::Client::new()
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+}
+
+#[tokio::test]
+async fn test_namespaced_entries_stats_and_invalidation() {
+    let test_app = TestApp::new().await;
+    let namespace_name = format!("test:{}", test_app.uuid);
+    let app = Router::new().route(
+        "/api/repositories/:repo/issues",
+        get(test_handler).layer(
+            RedisCacheLayerBuilder::new(test_app.redis_pool.clone())
+                .with_expiration_time(60)
+                .with_namespace(namespace_name.clone())
+                .build::<TestHandlerResponse>(),
+        ),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+
+    send_request(&format!(
+        "{}/api/repositories/cube/issues?page=1",
+        test_app_url
+    ))
+    .await;
+    send_request(&format!(
+        "{}/api/repositories/cube/issues?page=1",
+        test_app_url
+    ))
+    .await;
+    send_request(&format!("{}/api/repositories/axum/issues", test_app_url)).await;
+
+    // Stats are updated in the background
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let namespace = CacheNamespace::new(test_app.redis_pool.clone(), namespace_name.clone());
+    let mut keys = namespace.keys().await.unwrap();
+
+    keys.sort();
+
+    assert_eq!(
+        keys,
+        vec![
+            format!("{{{}}}:axum", namespace_name),
+            format!("{{{}}}:cube:page=1", namespace_name),
+        ]
+    );
+
+    let stats = namespace.stats().await.unwrap();
+
+    assert_eq!(stats.entries, 2);
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 2);
+
+    assert_eq!(namespace.invalidate().await.unwrap(), 2);
+    assert!(namespace.keys().await.unwrap().is_empty());
+
+    test_app.redis_del(namespace.meta_key("stats")).await;
+}
+
+#[tokio::test]
+async fn test_route_namespace_is_derived_from_template() {
+    let test_app = TestApp::new().await;
+    let route = format!("/api/{}/repositories/:repo", test_app.uuid);
+    let app = Router::new().route(
+        &route,
+        get(test_handler).layer(
+            RedisCacheLayerBuilder::new(test_app.redis_pool.clone())
+                .with_route_namespace()
+                .build::<TestHandlerResponse>(),
+        ),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+
+    send_request(&format!(
+        "{}/api/{}/repositories/cube",
+        test_app_url, test_app.uuid
+    ))
+    .await;
+
+    let namespace = CacheNamespace::from_route(test_app.redis_pool.clone(), &route);
+
+    assert_eq!(
+        namespace.name(),
+        format!("api:{}:repositories:$repo", test_app.uuid)
+    );
+    assert_eq!(
+        namespace.keys().await.unwrap(),
+        vec![format!("{{api:{}:repositories:$repo}}:cube", test_app.uuid)]
+    );
+
+    namespace.invalidate().await.unwrap();
+    test_app.redis_del(namespace.meta_key("stats")).await;
+}
+
+#[tokio::test]
+async fn test_namespace_budget_evicts_least_recently_used_entries() {
+    let test_app = TestApp::new().await;
+    let namespace_name = format!("test:{}", test_app.uuid);
+    let app = Router::new().route(
+        "/api/repositories",
+        get(test_handler).layer(
+            RedisCacheLayerBuilder::new(test_app.redis_pool.clone())
+                .with_expiration_time(60)
+                .with_namespace(namespace_name.clone())
+                .with_namespace_budget(NamespaceBudget {
+                    max_keys: Some(2),
+                    max_bytes: None,
+                })
+                .build::<TestHandlerResponse>(),
+        ),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+    let url = |page: u32| format!("{}/api/repositories?page={}", test_app_url, page);
+
+    send_request(&url(1)).await;
+    send_request(&url(2)).await;
+    // Reading the first page makes the second one the least recently used entry
+    send_request(&url(1)).await;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    send_request(&url(3)).await;
+
+    let namespace = CacheNamespace::new(test_app.redis_pool.clone(), namespace_name.clone());
+    let mut keys = namespace.keys().await.unwrap();
+
+    keys.sort();
+
+    assert_eq!(
+        keys,
+        vec![
+            format!("{{{}}}:page=1", namespace_name),
+            format!("{{{}}}:page=3", namespace_name),
+        ]
+    );
+
+    let mut redis_connection = test_app.redis_connection().await;
+    let evicted_exists: bool = redis_connection
+        .exists(format!("{{{}}}:page=2", namespace_name))
+        .await
+        .unwrap();
+    let stats = namespace.stats().await.unwrap();
+
+    assert!(!evicted_exists);
+    assert_eq!(stats.entries, 2);
+    assert_eq!(stats.evictions, 1);
+
+    namespace.invalidate().await.unwrap();
+    test_app.redis_del(namespace.meta_key("stats")).await;
+}
@@ -0,0 +1,197 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, Router},
+};
+use axum_redis_cache::rate_limit::{RateLimitAlgorithm, RateLimitKey, RateLimitLayerBuilder};
+use std::time::Duration;
+
+use crate::helpers::TestApp;
+
+async fn test_handler() -> Response {
+    (StatusCode::OK, "Test handler response").into_response()
+}
+
+async fn send_requests(url: &str, count: usize) -> Vec<reqwest::Response> {
+    let client = reqwest::Client::new();
+    let mut responses = Vec::new();
+
+    for _ in 0..count {
+        let response = client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to execute api request.");
+
+        responses.push(response);
+    }
+
+    responses
+}
+
+#[tokio::test]
+async fn test_fixed_window_rate_limit() {
+    let test_app = TestApp::new().await;
+    let app = Router::new().route("/api/test", get(test_handler)).layer(
+        RateLimitLayerBuilder::new(
+            test_app.redis_pool.clone(),
+            RateLimitAlgorithm::FixedWindow {
+                limit: 2,
+                window: Duration::from_secs(60),
+            },
+        )
+        .with_prefix(format!("rate_limit:{}", test_app.uuid))
+        .build()
+        .unwrap(),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+    let responses = send_requests(&format!("{}/api/test", test_app_url), 3).await;
+
+    assert_eq!(responses[0].status().as_u16(), 200);
+    assert_eq!(responses[0].headers().get("ratelimit-limit").unwrap(), "2");
+    assert_eq!(
+        responses[0].headers().get("ratelimit-remaining").unwrap(),
+        "1"
+    );
+    assert_eq!(responses[1].status().as_u16(), 200);
+    assert_eq!(
+        responses[1].headers().get("ratelimit-remaining").unwrap(),
+        "0"
+    );
+    assert_eq!(responses[2].status().as_u16(), 429);
+    assert!(responses[2].headers().contains_key("retry-after"));
+    assert!(responses[2].headers().contains_key("ratelimit-reset"));
+
+    test_app
+        .redis_del(format!(
+            "rate_limit:{}:fixed_window:ip:127.0.0.1",
+            test_app.uuid
+        ))
+        .await;
+}
+
+#[tokio::test]
+async fn test_sliding_window_rate_limit() {
+    let test_app = TestApp::new().await;
+    let app = Router::new().route("/api/test", get(test_handler)).layer(
+        RateLimitLayerBuilder::new(
+            test_app.redis_pool.clone(),
+            RateLimitAlgorithm::SlidingWindow {
+                limit: 2,
+                window: Duration::from_secs(60),
+            },
+        )
+        .with_prefix(format!("rate_limit:{}", test_app.uuid))
+        .build()
+        .unwrap(),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+    let responses = send_requests(&format!("{}/api/test", test_app_url), 3).await;
+
+    assert_eq!(responses[0].status().as_u16(), 200);
+    assert_eq!(responses[1].status().as_u16(), 200);
+    assert_eq!(responses[2].status().as_u16(), 429);
+    assert!(responses[2].headers().contains_key("retry-after"));
+
+    test_app
+        .redis_del(format!(
+            "rate_limit:{}:sliding_window:ip:127.0.0.1",
+            test_app.uuid
+        ))
+        .await;
+}
+
+#[tokio::test]
+async fn test_token_bucket_rate_limit() {
+    let test_app = TestApp::new().await;
+    let app = Router::new().route("/api/test", get(test_handler)).layer(
+        RateLimitLayerBuilder::new(
+            test_app.redis_pool.clone(),
+            RateLimitAlgorithm::TokenBucket {
+                capacity: 2,
+                refill_rate: 0.1,
+            },
+        )
+        .with_prefix(format!("rate_limit:{}", test_app.uuid))
+        .build()
+        .unwrap(),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+    let responses = send_requests(&format!("{}/api/test", test_app_url), 3).await;
+
+    assert_eq!(responses[0].status().as_u16(), 200);
+    assert_eq!(responses[1].status().as_u16(), 200);
+    assert_eq!(responses[2].status().as_u16(), 429);
+    // One token is refilled every 10 seconds
+    assert_eq!(responses[2].headers().get("retry-after").unwrap(), "10");
+
+    test_app
+        .redis_del(format!(
+            "rate_limit:{}:token_bucket:ip:127.0.0.1",
+            test_app.uuid
+        ))
+        .await;
+}
+
+#[tokio::test]
+async fn test_rate_limit_by_custom_key() {
+    let test_app = TestApp::new().await;
+    let app = Router::new().route("/api/test", get(test_handler)).layer(
+        RateLimitLayerBuilder::new(
+            test_app.redis_pool.clone(),
+            RateLimitAlgorithm::FixedWindow {
+                limit: 1,
+                window: Duration::from_secs(60),
+            },
+        )
+        .with_prefix(format!("rate_limit:{}", test_app.uuid))
+        .with_key(RateLimitKey::custom(|parts| {
+            parts
+                .headers
+                .get("x-tenant")
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        }))
+        .build()
+        .unwrap(),
+    );
+
+    let test_app_url = test_app.spawn_app(app).await;
+    let url = format!("{}/api/test", test_app_url);
+    let client = reqwest::Client::new();
+
+    let first_tenant_response = client
+        .get(&url)
+        .header("x-tenant", "first")
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+    let second_tenant_response = client
+        .get(&url)
+        .header("x-tenant", "second")
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+    let limited_response = client
+        .get(&url)
+        .header("x-tenant", "first")
+        .send()
+        .await
+        .expect("Failed to execute api request.");
+
+    assert_eq!(first_tenant_response.status().as_u16(), 200);
+    assert_eq!(second_tenant_response.status().as_u16(), 200);
+    assert_eq!(limited_response.status().as_u16(), 429);
+
+    for tenant in ["first", "second"] {
+        test_app
+            .redis_del(format!(
+                "rate_limit:{}:fixed_window:custom:{}",
+                test_app.uuid, tenant
+            ))
+            .await;
+    }
+}